@@ -0,0 +1,6 @@
+DROP TABLE IF EXISTS `identity_policy`;
+DROP TABLE IF EXISTS `group_policy`;
+DROP TABLE IF EXISTS `group_identity`;
+DROP TABLE IF EXISTS `identity`;
+DROP TABLE IF EXISTS `group`;
+DROP TABLE IF EXISTS `policy`;
@@ -0,0 +1,46 @@
+CREATE TABLE IF NOT EXISTS policy (
+    id character varying(1024) NOT NULL,
+    version integer DEFAULT 1 NOT NULL,
+    effect boolean NOT NULL,
+    actions jsonb NOT NULL,
+    resources jsonb NOT NULL,
+    CONSTRAINT policy_pk PRIMARY KEY (id)
+);
+
+CREATE TABLE IF NOT EXISTS "group" (
+    id character varying(1024) NOT NULL,
+    policy_id character varying(1024),
+    CONSTRAINT group_pk PRIMARY KEY (id),
+    CONSTRAINT group_policy_id_fk FOREIGN KEY (policy_id) REFERENCES policy(id) ON UPDATE RESTRICT ON DELETE RESTRICT
+);
+
+CREATE TABLE IF NOT EXISTS identity (
+    id character varying(1024) NOT NULL,
+    policy_id character varying(1024),
+    CONSTRAINT identity_pk PRIMARY KEY (id),
+    CONSTRAINT identity_policy_id_fk FOREIGN KEY (policy_id) REFERENCES policy(id) ON UPDATE RESTRICT ON DELETE RESTRICT
+);
+
+CREATE TABLE IF NOT EXISTS group_identity (
+    group_id character varying(1024) NOT NULL,
+    identity_id character varying(1024) NOT NULL,
+    CONSTRAINT group_identity_pk PRIMARY KEY (group_id, identity_id),
+    CONSTRAINT group_identity_group_id_fk FOREIGN KEY (group_id) REFERENCES "group"(id) ON UPDATE RESTRICT ON DELETE CASCADE,
+    CONSTRAINT group_identity_identity_id_fk FOREIGN KEY (identity_id) REFERENCES identity(id) ON UPDATE RESTRICT ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS group_policy (
+    group_id character varying(1024) NOT NULL,
+    policy_id character varying(1024) NOT NULL,
+    CONSTRAINT group_policy_pk PRIMARY KEY (group_id, policy_id),
+    CONSTRAINT group_policy_group_id_fk FOREIGN KEY (group_id) REFERENCES "group"(id) ON UPDATE RESTRICT ON DELETE CASCADE,
+    CONSTRAINT group_policy_policy_id_fk FOREIGN KEY (policy_id) REFERENCES policy(id) ON UPDATE RESTRICT ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS identity_policy (
+    identity_id character varying(1024) NOT NULL,
+    policy_id character varying(1024) NOT NULL,
+    CONSTRAINT identity_policy_pk PRIMARY KEY (identity_id, policy_id),
+    CONSTRAINT identity_policy_identity_id_fk FOREIGN KEY (identity_id) REFERENCES identity(id) ON UPDATE RESTRICT ON DELETE CASCADE,
+    CONSTRAINT identity_policy_policy_id_fk FOREIGN KEY (policy_id) REFERENCES policy(id) ON UPDATE RESTRICT ON DELETE CASCADE
+);
@@ -0,0 +1,2 @@
+-- Existing policies have no conditions: the column is left NULL.
+ALTER TABLE policy ADD COLUMN IF NOT EXISTS conditions jsonb;
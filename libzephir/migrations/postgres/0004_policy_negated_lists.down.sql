@@ -0,0 +1,2 @@
+ALTER TABLE policy DROP COLUMN IF EXISTS not_resources;
+ALTER TABLE policy DROP COLUMN IF EXISTS not_actions;
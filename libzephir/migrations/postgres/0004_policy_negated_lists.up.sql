@@ -0,0 +1,3 @@
+-- Existing policies have positive lists only: the columns are left NULL.
+ALTER TABLE policy ADD COLUMN IF NOT EXISTS not_actions jsonb;
+ALTER TABLE policy ADD COLUMN IF NOT EXISTS not_resources jsonb;
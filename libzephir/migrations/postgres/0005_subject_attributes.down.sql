@@ -0,0 +1,2 @@
+ALTER TABLE "group" DROP COLUMN IF EXISTS attributes;
+ALTER TABLE identity DROP COLUMN IF EXISTS attributes;
@@ -0,0 +1,3 @@
+-- Existing identities and groups have no attributes: the columns are left NULL.
+ALTER TABLE identity ADD COLUMN IF NOT EXISTS attributes jsonb;
+ALTER TABLE "group" ADD COLUMN IF NOT EXISTS attributes jsonb;
@@ -0,0 +1,47 @@
+CREATE TABLE IF NOT EXISTS policy (
+    id VARCHAR(1024) NOT NULL,
+    version INTEGER DEFAULT 1 NOT NULL,
+    effect BOOLEAN NOT NULL,
+    actions TEXT NOT NULL,
+    resources TEXT NOT NULL,
+    conditions TEXT,
+    CONSTRAINT policy_pk PRIMARY KEY (id)
+);
+
+CREATE TABLE IF NOT EXISTS "group" (
+    id VARCHAR(1024) NOT NULL,
+    policy_id VARCHAR(1024),
+    CONSTRAINT group_pk PRIMARY KEY (id),
+    CONSTRAINT group_policy_id_fk FOREIGN KEY (policy_id) REFERENCES policy (id) ON UPDATE RESTRICT ON DELETE RESTRICT
+);
+
+CREATE TABLE IF NOT EXISTS identity (
+    id VARCHAR(1024) NOT NULL,
+    policy_id VARCHAR(1024),
+    CONSTRAINT identity_pk PRIMARY KEY (id),
+    CONSTRAINT identity_policy_id_fk FOREIGN KEY (policy_id) REFERENCES policy (id) ON UPDATE RESTRICT ON DELETE RESTRICT
+);
+
+CREATE TABLE IF NOT EXISTS group_identity (
+    group_id VARCHAR(1024) NOT NULL,
+    identity_id VARCHAR(1024) NOT NULL,
+    CONSTRAINT group_identity_pk PRIMARY KEY (group_id, identity_id),
+    CONSTRAINT group_identity_group_id_fk FOREIGN KEY (group_id) REFERENCES "group" (id) ON UPDATE RESTRICT ON DELETE CASCADE,
+    CONSTRAINT group_identity_identity_id_fk FOREIGN KEY (identity_id) REFERENCES identity (id) ON UPDATE RESTRICT ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS group_policy (
+    group_id VARCHAR(1024) NOT NULL,
+    policy_id VARCHAR(1024) NOT NULL,
+    CONSTRAINT group_policy_pk PRIMARY KEY (group_id, policy_id),
+    CONSTRAINT group_policy_group_id_fk FOREIGN KEY (group_id) REFERENCES "group" (id) ON UPDATE RESTRICT ON DELETE CASCADE,
+    CONSTRAINT group_policy_policy_id_fk FOREIGN KEY (policy_id) REFERENCES policy (id) ON UPDATE RESTRICT ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS identity_policy (
+    identity_id VARCHAR(1024) NOT NULL,
+    policy_id VARCHAR(1024) NOT NULL,
+    CONSTRAINT identity_policy_pk PRIMARY KEY (identity_id, policy_id),
+    CONSTRAINT identity_policy_identity_id_fk FOREIGN KEY (identity_id) REFERENCES identity (id) ON UPDATE RESTRICT ON DELETE CASCADE,
+    CONSTRAINT identity_policy_policy_id_fk FOREIGN KEY (policy_id) REFERENCES policy (id) ON UPDATE RESTRICT ON DELETE CASCADE
+);
@@ -0,0 +1 @@
+ALTER TABLE policy DROP COLUMN statements;
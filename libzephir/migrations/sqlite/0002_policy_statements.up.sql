@@ -0,0 +1,2 @@
+-- Version 1 policies have no statements: the column is left NULL.
+ALTER TABLE policy ADD COLUMN statements TEXT;
@@ -0,0 +1,2 @@
+ALTER TABLE policy DROP COLUMN not_resources;
+ALTER TABLE policy DROP COLUMN not_actions;
@@ -0,0 +1,3 @@
+-- Existing policies have positive lists only: the columns are left NULL.
+ALTER TABLE policy ADD COLUMN not_actions TEXT;
+ALTER TABLE policy ADD COLUMN not_resources TEXT;
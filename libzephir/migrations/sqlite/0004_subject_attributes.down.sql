@@ -0,0 +1,2 @@
+ALTER TABLE "group" DROP COLUMN attributes;
+ALTER TABLE identity DROP COLUMN attributes;
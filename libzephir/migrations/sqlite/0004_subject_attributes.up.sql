@@ -0,0 +1,3 @@
+-- Existing identities and groups have no attributes: the columns are left NULL.
+ALTER TABLE identity ADD COLUMN attributes TEXT;
+ALTER TABLE "group" ADD COLUMN attributes TEXT;
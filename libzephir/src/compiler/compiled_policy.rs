@@ -1,3 +1,5 @@
+use crate::compiler::resource_matcher::{ResourceScheme, StructuredPattern};
+use crate::compiler::template::GlobTemplate;
 use crate::err::{Error, ErrorKind, NoneError};
 use crate::policy::condition::Condition;
 use log::{log_enabled, trace, warn, Level};
@@ -11,9 +13,20 @@ use std::fmt::Debug;
 #[derive(Clone, Debug)]
 pub struct CompiledPolicy {
     actions: Vec<Regex>,
+    /// Actions containing policy variables, compiled on evaluation.
+    action_templates: Vec<GlobTemplate>,
     resources: Vec<Regex>,
+    /// Resources containing policy variables, compiled on evaluation.
+    resource_templates: Vec<GlobTemplate>,
+    /// Resources matched segment by segment against the configured resource scheme.
+    structured_resources: Vec<StructuredPattern>,
     conditions: Vec<Condition>,
 
+    /// Whether the action regexes are negated (NotAction).
+    actions_negated: bool,
+    /// Whether the resource regexes are negated (NotResource).
+    resources_negated: bool,
+
     pub no_conditions: bool,
     pub all_resources: bool,
 }
@@ -42,6 +55,60 @@ fn redis_obj_to_regex(obj: &HashMap<String, String>, key: &str) -> Result<Vec<Re
     Ok(result)
 }
 
+/// Reads the structured resource patterns from a redis cache object.
+/// The patterns are parsed again, failing if the resource scheme has changed.
+fn redis_obj_to_structured(
+    obj: &HashMap<String, String>,
+    key: &str,
+) -> Result<Vec<StructuredPattern>, Error> {
+    let globs: Vec<String> = match obj.get(key) {
+        Option::None => return Ok(vec![]),
+        Option::Some(value) => serde_json::from_str(value)?,
+    };
+
+    if globs.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let scheme = ResourceScheme::configured()
+        .ok_or_else(|| Error::from("Structured resource matching is not configured"))?;
+    globs
+        .iter()
+        .map(|g| {
+            StructuredPattern::parse(scheme, g)?
+                .ok_or_else(|| Error::from("Structured resource does not follow the scheme"))
+        })
+        .collect()
+}
+
+/// Reads the glob templates from a redis cache object.
+fn redis_obj_to_templates(
+    obj: &HashMap<String, String>,
+    key: &str,
+) -> Result<Vec<GlobTemplate>, Error> {
+    let globs: Vec<String> = match obj.get(key) {
+        Option::None => return Ok(vec![]),
+        Option::Some(value) => serde_json::from_str(value)?,
+    };
+
+    globs
+        .iter()
+        .map(|g| {
+            GlobTemplate::parse(g)?
+                .ok_or_else(|| Error::from("Cached template does not contain variables"))
+        })
+        .collect()
+}
+
+/// Reads a negation flag from a redis cache object.
+/// Objects cached before the introduction of the flag are not negated.
+fn negation_flag(obj: &HashMap<String, String>, key: &str) -> Result<bool, Error> {
+    match obj.get(key) {
+        Option::None => Ok(false),
+        Option::Some(value) => Ok(value.parse()?),
+    }
+}
+
 impl CompiledPolicy {
     /// Creates a new compiled policy
     ///
@@ -53,9 +120,14 @@ impl CompiledPolicy {
     /// for a matching operation, while an empty actions vector means
     /// that no actions will be valid. This however should be prevented
     /// by the CompletePolicy::new that should not allow an empty actions array.
+    ///
+    /// Negated actions or resources match everything but the given regexes:
+    /// an empty set of negated resources does not represent a match-all.
     pub fn new(
         actions: Vec<Regex>,
+        actions_negated: bool,
         resources: Vec<Regex>,
+        resources_negated: bool,
         conditions: Vec<Condition>,
     ) -> CompiledPolicy {
         if log_enabled!(Level::Trace) {
@@ -67,17 +139,49 @@ impl CompiledPolicy {
         }
 
         let no_conditions = conditions.is_empty();
-        let all_resources = resources.is_empty();
+        let all_resources = resources.is_empty() && !resources_negated;
 
         CompiledPolicy {
             actions,
+            action_templates: vec![],
             resources,
+            resource_templates: vec![],
+            structured_resources: vec![],
             conditions,
+            actions_negated,
+            resources_negated,
             no_conditions,
             all_resources,
         }
     }
 
+    /// Adds actions and resources containing policy variables,
+    /// in addition to the action and resource regexes.
+    pub fn with_templates(
+        mut self,
+        actions: Vec<GlobTemplate>,
+        resources: Vec<GlobTemplate>,
+    ) -> Self {
+        if !resources.is_empty() {
+            self.all_resources = false;
+        }
+
+        self.action_templates.extend(actions);
+        self.resource_templates.extend(resources);
+        self
+    }
+
+    /// Adds resources matched against the configured resource scheme,
+    /// in addition to the resource regexes.
+    pub fn with_structured_resources(mut self, resources: Vec<StructuredPattern>) -> Self {
+        if !resources.is_empty() {
+            self.all_resources = false;
+        }
+
+        self.structured_resources.extend(resources);
+        self
+    }
+
     /// Try to match an action against this compiled policy
     ///
     /// This function will simply iterate the vector of actions regexes
@@ -86,8 +190,13 @@ impl CompiledPolicy {
     ///
     /// # Returns
     ///
-    /// True if at least one match is found, false otherwise
-    pub fn match_action<T: ToString>(&self, action: &T) -> bool {
+    /// True if at least one match is found, false otherwise.
+    /// The result is inverted if the actions are negated.
+    ///
+    /// Templates are matched after the regexes, resolving their variables
+    /// with the given params: without params, a template cannot be decided
+    /// and None is returned if no regex matches.
+    pub fn match_action<T: ToString>(&self, action: &T, params: Option<&Value>) -> Option<bool> {
         let action = action.to_string();
         let action_str = action.as_bytes();
 
@@ -103,14 +212,35 @@ impl CompiledPolicy {
                 Ok(result) => {
                     if result {
                         trace!("Regex {} matches the action {}", regex.as_str(), action);
-                        return true;
+                        return Some(!self.actions_negated);
                     }
                 }
             }
         }
 
+        let mut decided = true;
+        for template in &self.action_templates {
+            match template.matches(&action, params) {
+                Some(true) => {
+                    trace!(
+                        "Template {} matches the action {}",
+                        template.as_str(),
+                        action
+                    );
+                    return Some(!self.actions_negated);
+                }
+                Some(false) => {}
+                None => decided = false,
+            }
+        }
+
+        if !decided {
+            trace!("Undecided match");
+            return None;
+        }
+
         trace!("No match");
-        false
+        Some(self.actions_negated)
     }
 
     /// Try to match a resource string against this compiled policy.
@@ -129,7 +259,16 @@ impl CompiledPolicy {
     /// - true if this policy is a match-all or *at least one* resources regexes matches
     /// - false if this policy is *NOT* a match-all an no regex matches
     /// - Option::None if this policy is *NOT* a match-all and the passed resource is None
-    pub fn match_resource<T: ToString + Debug>(&self, resource: Option<T>) -> Option<bool> {
+    ///
+    /// Templates and structured resources are matched after the regexes,
+    /// resolving their variables with the given params: without params, they cannot
+    /// be decided and None is returned if nothing else matches.
+    /// If the resources are negated, the match result is inverted.
+    pub fn match_resource<T: ToString + Debug>(
+        &self,
+        resource: Option<T>,
+        params: Option<&Value>,
+    ) -> Option<bool> {
         trace!("Requesting match for resource {:#?}...", resource);
         if self.all_resources {
             trace!("Policy is resource-match-all");
@@ -141,10 +280,9 @@ impl CompiledPolicy {
                 trace!("Returning None");
                 Option::None
             }
-            Option::Some(resource) => Option::Some({
+            Option::Some(resource) => {
                 let string = resource.to_string();
                 let res = string.as_bytes();
-                let mut result = false;
 
                 for regex in &self.resources {
                     let is_match = regex.is_match(res);
@@ -157,19 +295,53 @@ impl CompiledPolicy {
                         Ok(regex_matching) => {
                             if regex_matching {
                                 trace!("Regex {} matches the resource {}", regex.as_str(), string);
-                                result = true;
-                                break;
+                                return Option::Some(!self.resources_negated);
+                            }
+                        }
+                    }
+                }
+
+                let mut decided = true;
+                for template in &self.resource_templates {
+                    match template.matches(&string, params) {
+                        Some(true) => {
+                            trace!(
+                                "Template {} matches the resource {}",
+                                template.as_str(),
+                                string
+                            );
+                            return Option::Some(!self.resources_negated);
+                        }
+                        Some(false) => {}
+                        None => decided = false,
+                    }
+                }
+
+                if let Some(scheme) = ResourceScheme::configured() {
+                    for pattern in &self.structured_resources {
+                        match pattern.matches(scheme, &string, params) {
+                            Some(true) => {
+                                trace!(
+                                    "Pattern {} matches the resource {}",
+                                    pattern.as_str(),
+                                    string
+                                );
+                                return Option::Some(!self.resources_negated);
                             }
+                            Some(false) => {}
+                            None => decided = false,
                         }
                     }
                 }
 
-                if !result {
-                    trace!("No match");
+                if !decided {
+                    trace!("Undecided match");
+                    return Option::None;
                 }
 
-                result
-            }),
+                trace!("No match");
+                Option::Some(self.resources_negated)
+            }
         }
     }
 
@@ -189,6 +361,30 @@ impl CompiledPolicy {
         true
     }
 
+    /// Try to partially match request parameters to the policy conditions.
+    /// Conditions on keys missing from the parameters are not evaluated.
+    ///
+    /// # Returns
+    ///
+    /// False if at least one decidable condition does not match,
+    /// None if some conditions could not be decided, true otherwise
+    pub fn match_conditions_partial(&self, params: &Value) -> Option<bool> {
+        let mut decided = true;
+        for c in &self.conditions {
+            match c.matching_partial(params) {
+                Some(false) => return Some(false),
+                Some(true) => {}
+                None => decided = false,
+            }
+        }
+
+        if decided {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
     /// INTERNAL: Hydrate from redis cache object.
     ///
     /// Compiled policies can be cached in memory or on redis.
@@ -204,14 +400,24 @@ impl CompiledPolicy {
     {
         let all_resources: bool = obj["all_res"].parse()?;
         let no_conditions: bool = obj["no_cond"].parse()?;
+        let actions_negated = negation_flag(&obj, "not_act")?;
+        let resources_negated = negation_flag(&obj, "not_res")?;
         let actions = redis_obj_to_regex(&obj, "actions")?;
         let resources = redis_obj_to_regex(&obj, "resources")?;
+        let action_templates = redis_obj_to_templates(&obj, "act_tpl")?;
+        let resource_templates = redis_obj_to_templates(&obj, "res_tpl")?;
+        let structured_resources = redis_obj_to_structured(&obj, "structured")?;
         let conditions: Vec<Condition> = serde_json::from_str(obj["conditions"].as_str())?;
 
         Ok(CompiledPolicy {
             actions,
+            action_templates,
             resources,
+            resource_templates,
+            structured_resources,
             conditions,
+            actions_negated,
+            resources_negated,
             no_conditions,
             all_resources,
         })
@@ -248,7 +454,24 @@ impl Cacheable for CompiledPolicy {
             String::from("resources"),
             Value::from(resources).to_string(),
         ));
+        let templates = |templates: &[GlobTemplate]| {
+            Value::from(templates.iter().map(|t| t.as_str()).collect::<Vec<&str>>()).to_string()
+        };
+        v.push((String::from("act_tpl"), templates(&self.action_templates)));
+        v.push((String::from("res_tpl"), templates(&self.resource_templates)));
+        v.push((
+            String::from("structured"),
+            Value::from(
+                self.structured_resources
+                    .iter()
+                    .map(|p| p.as_str())
+                    .collect::<Vec<&str>>(),
+            )
+            .to_string(),
+        ));
         v.push((String::from("all_res"), self.all_resources.to_string()));
+        v.push((String::from("not_act"), self.actions_negated.to_string()));
+        v.push((String::from("not_res"), self.resources_negated.to_string()));
         v.push((
             String::from("conditions"),
             serde_json::to_string(&self.conditions).unwrap(),
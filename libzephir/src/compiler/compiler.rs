@@ -1,6 +1,10 @@
 use crate::cache::create_cache;
 use crate::compiler::compiled_policy::CompiledPolicy;
+use crate::compiler::resource_matcher::{ResourceScheme, StructuredPattern};
+use crate::compiler::template::GlobTemplate;
+use crate::err::Error;
 use crate::policy::condition::Condition;
+use crate::policy::statement::PolicyTargets;
 use crate::utils::glob_to_regex;
 use lazy_static::lazy_static;
 use log::{debug, log_enabled, trace, warn, Level};
@@ -14,11 +18,29 @@ lazy_static! {
 pub(crate) mod cache {
     use crate::compiler::compiled_policy::CompiledPolicy;
     use crate::compiler::compiler::COMPILER;
+    use crate::policy::statement::MAX_STATEMENTS;
 
-    /// Removes a compiled policy from the cache.
+    /// Gets the cache key of a statement of a version 2 policy.
+    pub fn statement_key(policy_id: &str, index: usize) -> String {
+        format!("{}#{}", policy_id, index)
+    }
+
+    /// Removes a compiled policy (and its statements) from the cache.
     /// Should be called from the storage manager, when a policy is updated or removed.
+    ///
+    /// The statements of a policy are compiled together, in order: they are removed
+    /// up to the first one which is not cached.
     pub fn flush_policy(id: &str) {
         let _ = COMPILER.cache.remove::<_, CompiledPolicy>(id);
+        for index in 0..MAX_STATEMENTS {
+            let key = statement_key(id, index);
+            match COMPILER.cache.get::<_, CompiledPolicy>(key.as_str()) {
+                Ok(Some(_)) => {
+                    let _ = COMPILER.cache.remove::<_, CompiledPolicy>(key);
+                }
+                _ => break,
+            }
+        }
     }
 }
 
@@ -65,16 +87,27 @@ impl Compiler {
     /// The id field must be unique and represents the identifier of the policy.
     /// The field will be used as a cache key to avoid glob-to-regex recalculation.
     ///
+    /// Negated targets (not actions, not resources) are compiled as their positive
+    /// counterpart, the compiled policy inverting the match result.
+    ///
+    /// Anchored policies only match whole action and resource identifiers,
+    /// while legacy (version 1) policies match any identifier containing a glob match.
+    /// If a resource scheme is configured, the resources of anchored policies following
+    /// the scheme are matched segment by segment instead of being converted to regexes.
+    ///
+    /// Policy variables (ex: "${zephir:subject}") are only supported by anchored policies:
+    /// the globs containing them are kept as templates, compiled on evaluation.
+    ///
     /// # Returns
     ///
-    /// A CompiledPolicy object
+    /// A CompiledPolicy object, or an error if a glob cannot be parsed
     pub fn compile(
         &self,
         id: &str,
-        actions: &[String],
-        resources: &[String],
+        targets: &PolicyTargets,
+        anchored: bool,
         conditions: Vec<Condition>,
-    ) -> CompiledPolicy {
+    ) -> Result<CompiledPolicy, Error> {
         let item = if id.is_empty() {
             Err(CacheError::Other("".to_string()))
         } else {
@@ -82,25 +115,75 @@ impl Compiler {
         };
         if (&item).is_ok() && (&item).as_ref().unwrap().is_some() {
             debug!("Compiled policy {} found in cache.", id);
-            return item.unwrap().unwrap();
+            return Ok(item.unwrap().unwrap());
         }
 
-        let compiled_actions = actions
-            .iter()
-            .map(|a| glob_to_regex::from_string(a.to_string()))
-            .collect();
+        let actions_negated = targets.actions_negated();
+        let actions = if actions_negated {
+            &targets.not_actions
+        } else {
+            &targets.actions
+        };
+        let to_regex = if anchored {
+            glob_to_regex::from_str_anchored
+        } else {
+            glob_to_regex::from_str_legacy
+        };
+
+        let parse_template = |glob: &str| {
+            if anchored {
+                GlobTemplate::parse(glob)
+            } else {
+                Ok(Option::None)
+            }
+        };
 
-        let any_resource = resources.iter().any(|v| v == r"*");
-        let compiled_resources = if any_resource {
-            vec![]
+        let mut compiled_actions = vec![];
+        let mut action_templates = vec![];
+        for action in actions {
+            match parse_template(action)? {
+                Option::Some(template) => action_templates.push(template),
+                Option::None => compiled_actions.push(to_regex(action)?),
+            }
+        }
+
+        let resources_negated = targets.resources_negated();
+        let resources = if resources_negated {
+            &targets.not_resources
         } else {
-            resources
-                .iter()
-                .map(|a| glob_to_regex::from_string(a.to_string()))
-                .collect()
+            &targets.resources
         };
+        let any_resource = !resources_negated && resources.iter().any(|v| v == r"*");
+        let mut compiled_resources = vec![];
+        let mut resource_templates = vec![];
+        let mut structured_resources = vec![];
+        if !any_resource {
+            let scheme = ResourceScheme::configured().filter(|_| anchored);
+            for resource in resources {
+                let structured = match scheme {
+                    Option::Some(scheme) => StructuredPattern::parse(scheme, resource)?,
+                    Option::None => Option::None,
+                };
+
+                if let Some(pattern) = structured {
+                    structured_resources.push(pattern);
+                } else if let Some(template) = parse_template(resource)? {
+                    resource_templates.push(template);
+                } else {
+                    compiled_resources.push(to_regex(resource)?);
+                }
+            }
+        }
 
-        let cp = CompiledPolicy::new(compiled_actions, compiled_resources, conditions);
+        let cp = CompiledPolicy::new(
+            compiled_actions,
+            actions_negated,
+            compiled_resources,
+            resources_negated,
+            conditions,
+        )
+        .with_templates(action_templates, resource_templates)
+        .with_structured_resources(structured_resources);
         if !id.is_empty() {
             self.cache
                 .insert(id, cp.clone())
@@ -123,6 +206,6 @@ impl Compiler {
             trace!(r#"Compiled policy "{}": {:#?}"#, id, cp);
         }
 
-        cp
+        Ok(cp)
     }
 }
@@ -1,2 +1,4 @@
 pub mod compiled_policy;
 pub mod compiler;
+pub mod resource_matcher;
+pub mod template;
@@ -0,0 +1,414 @@
+use crate::compiler::template::GlobTemplate;
+use crate::err::Error;
+use crate::utils::glob_to_regex;
+use log::warn;
+use pcre2::bytes::Regex;
+use serde_json::Value;
+
+lazy_static! {
+    static ref RESOURCE_SCHEME: Option<ResourceScheme> = ResourceScheme::from_env();
+}
+
+/// Layout of structured resource identifiers, ex: "urn:service:region:account:resource".
+///
+/// Identifiers are made of colon-separated named segments. The last segment takes
+/// the remainder of the identifier (colons included) and is a path, whose components
+/// are separated by slashes (ex: "document/2021/report").
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResourceScheme {
+    segments: Vec<String>,
+}
+
+impl ResourceScheme {
+    /// Parses a scheme from its colon-separated segment names.
+    pub fn parse(scheme: &str) -> Result<Self, Error> {
+        let segments: Vec<String> = scheme.split(':').map(String::from).collect();
+        if segments.len() < 2 {
+            return Err(Error::from(format!(
+                r#"Resource scheme "{}" must have at least two segments"#,
+                scheme
+            )));
+        }
+
+        for (i, name) in segments.iter().enumerate() {
+            if name.is_empty() || segments[..i].contains(name) {
+                return Err(Error::from(format!(
+                    r#"Resource scheme "{}" has an empty or duplicated segment name"#,
+                    scheme
+                )));
+            }
+        }
+
+        Ok(ResourceScheme { segments })
+    }
+
+    /// Reads the scheme from the RESOURCE_SCHEME environment variable.
+    /// An unset or invalid scheme disables the structured matching.
+    fn from_env() -> Option<Self> {
+        let scheme = std::env::var("RESOURCE_SCHEME").ok()?;
+        if scheme.is_empty() {
+            return Option::None;
+        }
+
+        match Self::parse(&scheme) {
+            Ok(scheme) => Option::Some(scheme),
+            Err(e) => {
+                warn!("Structured resource matching is disabled: {}", e);
+                Option::None
+            }
+        }
+    }
+
+    /// Gets the configured scheme, if any.
+    pub fn configured() -> Option<&'static Self> {
+        RESOURCE_SCHEME.as_ref()
+    }
+
+    /// Splits an identifier into its segments, the last one being the path.
+    /// Returns None if the identifier has less segments than the scheme.
+    fn split<'a>(&self, resource: &'a str) -> Option<Vec<&'a str>> {
+        let parts: Vec<&str> = resource.splitn(self.segments.len(), ':').collect();
+        if parts.len() == self.segments.len() {
+            Option::Some(parts)
+        } else {
+            Option::None
+        }
+    }
+}
+
+/// Splits a glob on the given separator, up to limit parts.
+/// Escaped separators are not split, and None is returned if a separator
+/// would split an alternatives group.
+fn split_glob(glob: &str, separator: char, limit: usize) -> Option<Vec<&str>> {
+    let mut parts = vec![];
+    let mut start = 0;
+    let mut in_curlies = 0;
+    let mut escaped = false;
+
+    for (i, c) in glob.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '{' => in_curlies += 1,
+            '}' if in_curlies > 0 => in_curlies -= 1,
+            c if c == separator && parts.len() + 1 < limit => {
+                if in_curlies > 0 {
+                    return Option::None;
+                }
+
+                parts.push(&glob[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+
+    parts.push(&glob[start..]);
+    Option::Some(parts)
+}
+
+/// A pattern matching a single segment (or path component) of an identifier.
+#[derive(Clone, Debug)]
+enum SegmentPattern {
+    /// `*`: any value, empty included.
+    Any,
+    Glob(Box<Regex>),
+    Template(Box<GlobTemplate>),
+}
+
+impl SegmentPattern {
+    fn parse(glob: &str) -> Result<Self, Error> {
+        if glob == "*" {
+            return Ok(SegmentPattern::Any);
+        }
+
+        if let Some(template) = GlobTemplate::parse(glob)? {
+            return Ok(SegmentPattern::Template(Box::new(template)));
+        }
+
+        Ok(SegmentPattern::Glob(Box::new(
+            glob_to_regex::from_str_anchored(glob)?,
+        )))
+    }
+
+    /// Whether the pattern matches the given value.
+    /// Without params, templates cannot be decided (see `GlobTemplate::matches`).
+    fn matches(&self, value: &str, params: Option<&Value>) -> Option<bool> {
+        match self {
+            SegmentPattern::Any => Some(true),
+            SegmentPattern::Glob(regex) => Some(regex.is_match(value.as_bytes()).unwrap_or(false)),
+            SegmentPattern::Template(template) => template.matches(value, params),
+        }
+    }
+}
+
+/// Whether all the given matches succeed: None if no match fails, but some cannot be decided.
+fn all_match<I: Iterator<Item = Option<bool>>>(matches: I) -> Option<bool> {
+    let mut decided = true;
+    for is_match in matches {
+        match is_match {
+            Some(false) => return Some(false),
+            Some(true) => {}
+            None => decided = false,
+        }
+    }
+
+    if decided {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+/// Whether any of the given matches succeeds: None if no match succeeds, but some cannot be decided.
+fn any_match<I: Iterator<Item = Option<bool>>>(matches: I) -> Option<bool> {
+    let mut decided = true;
+    for is_match in matches {
+        match is_match {
+            Some(true) => return Some(true),
+            Some(false) => {}
+            None => decided = false,
+        }
+    }
+
+    if decided {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// A component of a path pattern.
+#[derive(Clone, Debug)]
+enum PathComponent {
+    /// `**`: any number of components, none included.
+    Recursive,
+    Segment(SegmentPattern),
+}
+
+fn match_path(
+    components: &[PathComponent],
+    parts: &[&str],
+    params: Option<&Value>,
+) -> Option<bool> {
+    match components.split_first() {
+        Option::None => Some(parts.is_empty()),
+        Option::Some((PathComponent::Recursive, rest)) => {
+            any_match((0..=parts.len()).map(|i| match_path(rest, &parts[i..], params)))
+        }
+        Option::Some((PathComponent::Segment(pattern), rest)) => match parts.split_first() {
+            Option::None => Some(false),
+            Option::Some((part, parts)) => all_match(
+                [pattern.matches(part, params)]
+                    .into_iter()
+                    .chain(std::iter::once_with(|| match_path(rest, parts, params))),
+            ),
+        },
+    }
+}
+
+/// A resource glob matched segment by segment against a resource scheme.
+///
+/// Each segment is matched as a whole: `*` matches any value of a single
+/// segment (the empty one included) and globs cannot cross segments.
+/// The path segment is matched component by component, `*` matching
+/// a single component and `**` any number of components
+/// (ex: "document/*/attachment/**").
+///
+/// Segments can contain policy variables, resolved on match (see `GlobTemplate`).
+#[derive(Clone, Debug)]
+pub struct StructuredPattern {
+    glob: String,
+    segments: Vec<SegmentPattern>,
+    path: Vec<PathComponent>,
+}
+
+impl StructuredPattern {
+    /// Parses a glob according to the given scheme.
+    ///
+    /// # Returns
+    ///
+    /// None if the glob does not follow the scheme (it has less segments,
+    /// or a `**` or an alternatives group crossing the segments), an error
+    /// if a segment cannot be parsed.
+    pub fn parse(scheme: &ResourceScheme, glob: &str) -> Result<Option<Self>, Error> {
+        let parts = match split_glob(glob, ':', scheme.segments.len()) {
+            Some(parts) if parts.len() == scheme.segments.len() => parts,
+            _ => return Ok(Option::None),
+        };
+
+        let (path, segments) = parts.split_last().unwrap();
+        if segments.iter().any(|s| s.contains("**")) {
+            return Ok(Option::None);
+        }
+
+        let components = match split_glob(path, '/', usize::MAX) {
+            Some(components) => components,
+            None => return Ok(Option::None),
+        };
+        if components.iter().any(|c| *c != "**" && c.contains("**")) {
+            return Ok(Option::None);
+        }
+
+        Ok(Option::Some(StructuredPattern {
+            glob: glob.to_string(),
+            segments: segments
+                .iter()
+                .map(|s| SegmentPattern::parse(s))
+                .collect::<Result<_, _>>()?,
+            path: components
+                .iter()
+                .map(|c| match *c {
+                    "**" => Ok(PathComponent::Recursive),
+                    c => SegmentPattern::parse(c).map(PathComponent::Segment),
+                })
+                .collect::<Result<_, _>>()?,
+        }))
+    }
+
+    /// Gets the glob the pattern has been parsed from.
+    pub fn as_str(&self) -> &str {
+        &self.glob
+    }
+
+    /// Whether the pattern matches the given resource identifier.
+    ///
+    /// Variables are replaced with the given request params: if no params
+    /// are given, a resource matching the segments without variables
+    /// cannot be decided (None).
+    pub fn matches(
+        &self,
+        scheme: &ResourceScheme,
+        resource: &str,
+        params: Option<&Value>,
+    ) -> Option<bool> {
+        let parts = match scheme.split(resource) {
+            Some(parts) => parts,
+            None => return Some(false),
+        };
+
+        let (path, segments) = parts.split_last().unwrap();
+        if segments.len() != self.segments.len() {
+            return Some(false);
+        }
+
+        let path = path.split('/').collect::<Vec<&str>>();
+        all_match(
+            self.segments
+                .iter()
+                .zip(segments)
+                .map(|(pattern, value)| pattern.matches(value, params))
+                .chain(std::iter::once_with(|| {
+                    match_path(&self.path, &path, params)
+                })),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compiler::resource_matcher::{ResourceScheme, StructuredPattern};
+    use crate::err::ErrorKind;
+    use serde_json::json;
+
+    fn scheme() -> ResourceScheme {
+        ResourceScheme::parse("urn:service:region:account:resource").unwrap()
+    }
+
+    fn matches(glob: &str, resource: &str) -> bool {
+        let scheme = scheme();
+        StructuredPattern::parse(&scheme, glob)
+            .unwrap()
+            .unwrap()
+            .matches(&scheme, resource, Some(&json!({ "owner": "alice" })))
+            .unwrap()
+    }
+
+    #[test]
+    fn scheme_should_be_parsed() {
+        assert_eq!(scheme().segments.len(), 5);
+        assert!(ResourceScheme::parse("urn").is_err());
+        assert!(ResourceScheme::parse("urn::resource").is_err());
+        assert!(ResourceScheme::parse("urn:id:id").is_err());
+    }
+
+    #[test]
+    fn segments_should_be_matched_as_a_whole() {
+        assert!(matches(
+            "urn:docs:*:123:report",
+            "urn:docs:eu-west:123:report"
+        ));
+        assert!(matches("urn:docs:*:123:report", "urn:docs::123:report"));
+        assert!(!matches("urn:docs:*:123:report", "urn:docs:eu:1234:report"));
+        assert!(matches(
+            "urn:docs:eu-*:12?:report",
+            "urn:docs:eu-west:123:report"
+        ));
+        assert!(!matches(
+            "urn:docs:eu-*:12?:report",
+            "urn:docs:us-east:123:report"
+        ));
+        assert!(matches("urn:{docs,mail}:*:*:*", "urn:mail:eu:1:inbox"));
+        assert!(!matches("urn:docs:*:*:*", "urn:docs:eu:1:inbox/archive"));
+    }
+
+    #[test]
+    fn paths_should_be_matched_by_component() {
+        let glob = "urn:docs:*:*:folder/*/child";
+        assert!(matches(glob, "urn:docs:eu:1:folder/a/child"));
+        assert!(!matches(glob, "urn:docs:eu:1:folder/a/b/child"));
+
+        let glob = "urn:docs:*:*:folder/**/child";
+        assert!(matches(glob, "urn:docs:eu:1:folder/child"));
+        assert!(matches(glob, "urn:docs:eu:1:folder/a/b/child"));
+        assert!(!matches(glob, "urn:docs:eu:1:folder/a/b/other"));
+
+        assert!(matches("urn:docs:*:*:a:b/**", "urn:docs:eu:1:a:b/c/d"));
+    }
+
+    #[test]
+    fn variables_should_be_replaced_with_params() {
+        let glob = "urn:docs:*:*:home/${owner}/**";
+        assert!(matches(glob, "urn:docs:eu:1:home/alice/notes"));
+        assert!(!matches(glob, "urn:docs:eu:1:home/bob/notes"));
+        assert!(matches(
+            "urn:docs:*:user-${owner}:*",
+            "urn:docs:eu:user-alice:x"
+        ));
+        assert!(!matches("urn:docs:*:${missing}:*", "urn:docs:eu:alice:x"));
+
+        let scheme = scheme();
+        let pattern = StructuredPattern::parse(&scheme, glob).unwrap().unwrap();
+        assert_eq!(
+            pattern.matches(&scheme, "urn:docs:eu:1:home/bob/notes", None),
+            None
+        );
+        assert_eq!(
+            pattern.matches(&scheme, "urn:docs:eu:1:other/bob/notes", None),
+            Some(false)
+        );
+        assert_eq!(
+            pattern.matches(
+                &scheme,
+                "urn:docs:eu:1:home/bob/notes",
+                Some(&json!({ "owner": "b*" }))
+            ),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn globs_not_following_the_scheme_should_not_be_parsed() {
+        let scheme = scheme();
+        for glob in ["urn:docs:*", "urn:**:report", "urn:{a:b}:*:*:*"] {
+            assert!(StructuredPattern::parse(&scheme, glob).unwrap().is_none());
+        }
+
+        for glob in ["urn:docs:[a:*:*", "urn:docs:*:*:home/${owner"] {
+            assert_eq!(
+                StructuredPattern::parse(&scheme, glob).unwrap_err().kind(),
+                ErrorKind::InvalidGlobError
+            );
+        }
+    }
+}
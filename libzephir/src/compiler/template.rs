@@ -0,0 +1,129 @@
+use crate::err::Error;
+use crate::policy::variables;
+use crate::utils::glob_to_regex;
+use hashlink::LruCache;
+use pcre2::bytes::Regex;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+
+/// Maximum number of resolved globs kept in the cache of each template.
+/// The least recently used ones are evicted first.
+const MAX_RESOLVED_GLOBS: usize = 256;
+
+/// A glob containing policy variables (ex: "urn:docs:${zephir:subject}:*").
+///
+/// Templates cannot be compiled with the rest of the policy: they are compiled
+/// once their variables are resolved, on evaluation. The resulting regexes are cached
+/// by the template (shared by its clones), so that the values used by the most recent
+/// requests are not compiled again.
+/// Templates are always matched against whole identifiers.
+#[derive(Clone, Debug)]
+pub struct GlobTemplate {
+    glob: String,
+    resolved: Arc<Mutex<LruCache<String, Regex>>>,
+}
+
+impl GlobTemplate {
+    /// Parses a glob as a template.
+    ///
+    /// # Returns
+    ///
+    /// None if the glob does not contain any variable, an error if it cannot be parsed.
+    pub fn parse(glob: &str) -> Result<Option<Self>, Error> {
+        if !variables::has_variables(glob) {
+            return Ok(Option::None);
+        }
+
+        glob_to_regex::to_pattern(&variables::strip(glob)?)?;
+
+        Ok(Option::Some(GlobTemplate {
+            glob: glob.to_string(),
+            resolved: Arc::new(Mutex::new(LruCache::new(MAX_RESOLVED_GLOBS))),
+        }))
+    }
+
+    /// Gets the template glob.
+    pub fn as_str(&self) -> &str {
+        &self.glob
+    }
+
+    /// Resolves the variables of the template with the given params and compiles the result,
+    /// reusing the regex compiled for a previous request if any.
+    /// Returns None if a variable cannot be resolved.
+    pub fn resolve(&self, params: &Value) -> Option<Regex> {
+        let glob = variables::resolve_glob(&self.glob, params)?;
+        if let Ok(mut resolved) = self.resolved.lock() {
+            if let Some(regex) = resolved.get(&glob) {
+                return Some(regex.clone());
+            }
+        }
+
+        let regex = glob_to_regex::from_str_anchored(&glob).ok()?;
+        if let Ok(mut resolved) = self.resolved.lock() {
+            resolved.insert(glob, regex.clone());
+        }
+
+        Some(regex)
+    }
+
+    /// Whether the template matches the given value.
+    ///
+    /// Without params, the match cannot be decided: None is returned, as a possible
+    /// match to be negated or not by the caller.
+    /// A template with unresolved variables does not match any value.
+    pub fn matches(&self, value: &str, params: Option<&Value>) -> Option<bool> {
+        let params = params?;
+        Some(
+            self.resolve(params)
+                .map(|regex| regex.is_match(value.as_bytes()).unwrap_or(false))
+                .unwrap_or(false),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compiler::template::GlobTemplate;
+    use crate::err::ErrorKind;
+    use serde_json::json;
+
+    #[test]
+    fn templates_should_be_matched_once_resolved() {
+        let template = GlobTemplate::parse("urn:docs:${zephir:subject}:*")
+            .unwrap()
+            .unwrap();
+        let params = json!({ "zephir:subject": "alice" });
+
+        assert_eq!(
+            template.matches("urn:docs:alice:report", Some(&params)),
+            Some(true)
+        );
+        assert_eq!(
+            template.matches("urn:docs:bob:report", Some(&params)),
+            Some(false)
+        );
+        assert_eq!(
+            template.matches("urn:docs:alice:report", Some(&json!({}))),
+            Some(false)
+        );
+        assert_eq!(template.matches("urn:docs:bob:report", None), None);
+
+        let params = json!({ "zephir:subject": "*" });
+        assert_eq!(
+            template.matches("urn:docs:bob:report", Some(&params)),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn globs_without_variables_should_not_be_templates() {
+        assert!(GlobTemplate::parse("urn:docs:*").unwrap().is_none());
+
+        for glob in ["urn:docs:${zephir:subject", "urn:[${a}:*"] {
+            assert_eq!(
+                GlobTemplate::parse(glob).unwrap_err().kind(),
+                ErrorKind::InvalidGlobError
+            );
+        }
+    }
+}
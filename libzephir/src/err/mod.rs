@@ -15,6 +15,12 @@ pub enum ErrorKind {
     /// Raised when trying to unwrap an Option::None value.
     UnwrapNoneValueError = 3,
 
+    /// Raised when an action or resource glob cannot be parsed.
+    InvalidGlobError = 4,
+
+    /// Raised when a condition regex cannot be parsed or uses unsupported constructs.
+    InvalidRegexError = 5,
+
     /// Represents any other error including the one not raised by this library
     /// and wrapped into a Error object exposed from this crate.
     UnknownError = -1,
@@ -60,6 +66,26 @@ impl Error {
         )
     }
 
+    pub fn invalid_glob<S: ToString>(glob: &str, reason: S) -> Self {
+        Self::new(
+            ErrorKind::InvalidGlobError,
+            InvalidGlobError {
+                glob: glob.to_string(),
+                reason: reason.to_string(),
+            },
+        )
+    }
+
+    pub fn invalid_regex<S: ToString>(pattern: &str, reason: S) -> Self {
+        Self::new(
+            ErrorKind::InvalidRegexError,
+            InvalidRegexError {
+                pattern: pattern.to_string(),
+                reason: reason.to_string(),
+            },
+        )
+    }
+
     pub fn unknown_policy_version(version: i32) -> Self {
         Self::new(
             ErrorKind::UnknownError,
@@ -96,6 +122,34 @@ impl fmt::Display for UnknownPolicyVersionError {
 
 impl std::error::Error for UnknownPolicyVersionError {}
 
+#[derive(Debug)]
+struct InvalidGlobError {
+    glob: String,
+    reason: String,
+}
+
+impl fmt::Display for InvalidGlobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, r#"Invalid glob "{}": {}"#, self.glob, self.reason)
+    }
+}
+
+impl std::error::Error for InvalidGlobError {}
+
+#[derive(Debug)]
+struct InvalidRegexError {
+    pattern: String,
+    reason: String,
+}
+
+impl fmt::Display for InvalidRegexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, r#"Invalid regex "{}": {}"#, self.pattern, self.reason)
+    }
+}
+
+impl std::error::Error for InvalidRegexError {}
+
 #[derive(Debug)]
 pub struct NoneError {}
 
@@ -0,0 +1,587 @@
+use crate::identity::group::Group;
+use crate::identity::identity::Identity;
+use crate::identity::role::Role;
+use crate::identity::subject::Subject;
+use crate::policy::allowed_result::AllowedResult;
+use crate::policy::policy::{CompletePolicy, MatchablePolicy, ToJson};
+use crate::policy::variables;
+use crate::policy::{PolicyEffect, PolicyVersion};
+use crate::utils::glob_to_regex;
+use chrono::Utc;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Represents a single check of an authorization batch.
+#[derive(Clone, Debug)]
+pub struct AllowedCheck {
+    pub action: String,
+    pub resource: Option<String>,
+
+    /// The request parameters, used to evaluate the policy conditions.
+    pub params: Value,
+}
+
+/// Evaluates a request against the policies of an identity and of the groups it belongs to.
+///
+/// Identity policies are evaluated first: if one of them explicitly denies
+/// the request, the group policies are not evaluated.
+/// Evaluations are tagged with their source subject (see `AllowedResult::explain`).
+///
+/// The identity id is available to the policies as the `zephir:subject` variable,
+/// and the identity and group attributes as `zephir:identity.<name>` and
+/// `zephir:group.<name>` context keys (see `variables::context_params`).
+/// The time of the evaluation is available as the `zephir:current_time` context key.
+pub fn allowed_for_identity(
+    identity: &Identity,
+    groups: &[Group],
+    action: Option<&str>,
+    resource: Option<&str>,
+    params: &Value,
+) -> AllowedResult {
+    evaluate_for_identity(identity, groups, action, resource, params, false)
+}
+
+/// Same as `allowed_for_identity`, but conditions on keys missing from the params
+/// are treated as unknown: the conditions which cannot be decided are returned
+/// as residual conditions of the partial policies, instead of not matching.
+pub fn allowed_partial_for_identity(
+    identity: &Identity,
+    groups: &[Group],
+    action: Option<&str>,
+    resource: Option<&str>,
+    params: &Value,
+) -> AllowedResult {
+    evaluate_for_identity(identity, groups, action, resource, params, true)
+}
+
+fn evaluate_for_identity(
+    identity: &Identity,
+    groups: &[Group],
+    action: Option<&str>,
+    resource: Option<&str>,
+    params: &Value,
+    partial: bool,
+) -> AllowedResult {
+    let params = &variables::context_params(
+        params,
+        identity.get_id(),
+        identity.get_attributes(),
+        Utc::now(),
+    );
+    let mut result = identity
+        .evaluate(action, resource, params, partial)
+        .with_source(format!("identity:{}", identity.get_id()));
+
+    if result.is_denied() {
+        return result;
+    }
+
+    for group in groups {
+        let params = variables::group_context_params(params, group.get_attributes());
+        result.merge(
+            group
+                .evaluate(action, resource, &params, partial)
+                .with_source(format!("group:{}", group.get_name())),
+        );
+    }
+
+    result
+}
+
+/// Evaluates a batch of checks against the same identity and groups.
+/// Results are returned in the same order of the checks.
+pub fn allowed_batch(
+    identity: &Identity,
+    groups: &[Group],
+    checks: &[AllowedCheck],
+) -> Vec<AllowedResult> {
+    checks
+        .iter()
+        .map(|check| {
+            allowed_for_identity(
+                identity,
+                groups,
+                Option::Some(check.action.as_str()),
+                check.resource.as_deref(),
+                &check.params,
+            )
+        })
+        .collect()
+}
+
+/// A policy applying to an identity, tagged with the subject it comes from.
+#[derive(Clone, Debug)]
+pub struct EffectivePolicy {
+    pub policy: CompletePolicy,
+
+    /// The subject the policy is attached to (ex: "identity:id" or "group:name").
+    pub source: String,
+
+    /// Whether the policy is the inline policy of its source.
+    pub inline: bool,
+}
+
+impl ToJson for EffectivePolicy {
+    fn to_json(&self) -> Map<String, Value> {
+        let mut result = Map::new();
+        result.insert(String::from("source"), Value::from(self.source.as_str()));
+        result.insert(String::from("inline"), Value::from(self.inline));
+        result.insert(String::from("policy"), self.policy.to_value());
+
+        result
+    }
+}
+
+/// The merged effect of the effective policies on an action pattern.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ActionSummary {
+    pub action: String,
+
+    /// Deny if at least one deny policy covers the action pattern.
+    pub effect: PolicyEffect,
+
+    /// Ids of the allow policies covering the action pattern.
+    pub allowed_by: Vec<String>,
+
+    /// Ids of the deny policies covering the action pattern.
+    pub denied_by: Vec<String>,
+}
+
+impl ToJson for ActionSummary {
+    fn to_json(&self) -> Map<String, Value> {
+        let mut result = Map::new();
+        result.insert(String::from("action"), Value::from(self.action.as_str()));
+        result.insert(String::from("effect"), Value::from(&self.effect));
+        result.insert(
+            String::from("allowed_by"),
+            Value::from(self.allowed_by.as_slice()),
+        );
+        result.insert(
+            String::from("denied_by"),
+            Value::from(self.denied_by.as_slice()),
+        );
+
+        result
+    }
+}
+
+fn collect_subject_policies<T: Subject>(
+    subject: &T,
+    source: String,
+    policies: &mut Vec<EffectivePolicy>,
+) {
+    if let Some(policy) = subject.get_inline_policy() {
+        policies.push(EffectivePolicy {
+            policy: policy.clone(),
+            source: source.clone(),
+            inline: true,
+        });
+    }
+
+    let mut linked: Vec<&CompletePolicy> = subject.linked_policies().into_iter().collect();
+    linked.sort_by(|a, b| a.id.cmp(&b.id));
+    for policy in linked {
+        policies.push(EffectivePolicy {
+            policy: policy.clone(),
+            source: source.clone(),
+            inline: false,
+        });
+    }
+}
+
+/// Collects all the policies applying to an identity: its inline and linked
+/// policies, followed by the inline and linked policies of its groups.
+pub fn effective_policies(identity: &Identity, groups: &[Group]) -> Vec<EffectivePolicy> {
+    let mut policies = vec![];
+    collect_subject_policies(
+        identity,
+        format!("identity:{}", identity.get_id()),
+        &mut policies,
+    );
+
+    for group in groups {
+        collect_subject_policies(group, format!("group:{}", group.get_name()), &mut policies);
+    }
+
+    policies
+}
+
+/// Merges the effective policies by action pattern.
+///
+/// A policy covers an action pattern if one of its statements has an action glob
+/// matching the pattern itself (ex: "s3:*" covers "s3:DeleteObject"). As in the allowed
+/// checks, a covering deny statement overrides any allow. A statement with negated actions
+/// covers the patterns not matched by any of them. Resources and conditions are not taken into account:
+/// an override could be limited to some resources or to some request contexts.
+pub fn merge_by_action(policies: &[EffectivePolicy]) -> Vec<ActionSummary> {
+    let statements: Vec<_> = policies
+        .iter()
+        .flat_map(|p| p.policy.statements().iter().map(move |s| (&p.policy.id, s)))
+        .collect();
+    let globs: Vec<_> = statements
+        .iter()
+        .map(|(_, statement)| {
+            let negated = statement.targets().actions_negated();
+            let actions = if negated {
+                statement.get_not_actions()
+            } else {
+                statement.get_actions()
+            };
+
+            // Globs have already been parsed when compiling the statement.
+            let to_regex = if statement.version == PolicyVersion::Version1 {
+                glob_to_regex::from_str_legacy
+            } else {
+                glob_to_regex::from_str
+            };
+            let globs = actions
+                .iter()
+                .filter_map(|a| to_regex(a).ok())
+                .collect::<Vec<_>>();
+            (negated, globs)
+        })
+        .collect();
+
+    let mut summaries: BTreeMap<&str, ActionSummary> = BTreeMap::new();
+    for action in statements.iter().flat_map(|(_, s)| s.get_actions()) {
+        if summaries.contains_key(action.as_str()) {
+            continue;
+        }
+
+        let mut summary = ActionSummary {
+            action: action.clone(),
+            effect: PolicyEffect::Allow,
+            allowed_by: vec![],
+            denied_by: vec![],
+        };
+
+        for ((policy_id, statement), (negated, globs)) in statements.iter().zip(globs.iter()) {
+            let matches = globs
+                .iter()
+                .any(|g| glob_to_regex::matches_whole(g, action));
+            if matches == *negated {
+                continue;
+            }
+
+            let ids = match statement.get_effect() {
+                PolicyEffect::Allow => &mut summary.allowed_by,
+                PolicyEffect::Deny => &mut summary.denied_by,
+            };
+
+            if !ids.contains(policy_id) {
+                ids.push(policy_id.to_string());
+            }
+        }
+
+        if !summary.denied_by.is_empty() {
+            summary.effect = PolicyEffect::Deny;
+        }
+
+        summaries.insert(action.as_str(), summary);
+    }
+
+    summaries.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::identity::authorization::{
+        allowed_batch, allowed_for_identity, allowed_partial_for_identity, effective_policies,
+        merge_by_action, AllowedCheck,
+    };
+    use crate::identity::group::Group;
+    use crate::identity::identity::Identity;
+    use crate::policy::allowed_result::AllowedOutcome;
+    use crate::policy::policy::CompletePolicy;
+    use crate::policy::policy_set::PolicySetTrait;
+    use crate::policy::{PolicyEffect, PolicyVersion};
+    use crate::zephir_policy;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn group_policies_should_be_evaluated() {
+        let identity = Identity::new("AuthorizationIdentity", None);
+        let group = Group::new("AuthorizationGroup", None).add_policy(
+            zephir_policy!(
+                "AuthorizationGroupPolicy",
+                PolicyVersion::Version1,
+                PolicyEffect::Allow,
+                vec!["core:GetVersion"]
+            )
+            .unwrap(),
+        );
+
+        let result = allowed_for_identity(
+            &identity,
+            &[group],
+            Option::Some("core:GetVersion"),
+            Option::None,
+            &Value::Null,
+        );
+
+        assert_eq!(result.outcome(), AllowedOutcome::Allowed);
+        assert_eq!(
+            result.decided_by().unwrap().source,
+            Some("group:AuthorizationGroup".to_string())
+        );
+    }
+
+    #[test]
+    fn identity_deny_should_not_be_overridden_by_groups() {
+        let identity = Identity::new("AuthorizationDeniedIdentity", None).add_policy(
+            zephir_policy!(
+                "AuthorizationDenyPolicy",
+                PolicyVersion::Version1,
+                PolicyEffect::Deny,
+                vec!["core:Delete"]
+            )
+            .unwrap(),
+        );
+        let group = Group::new("AuthorizationAllowGroup", None).add_policy(
+            zephir_policy!(
+                "AuthorizationAllowPolicy",
+                PolicyVersion::Version1,
+                PolicyEffect::Allow,
+                vec!["core:*"]
+            )
+            .unwrap(),
+        );
+        let groups = [group];
+
+        let results = allowed_batch(
+            &identity,
+            &groups,
+            &[
+                AllowedCheck {
+                    action: "core:Delete".to_string(),
+                    resource: Option::None,
+                    params: Value::Null,
+                },
+                AllowedCheck {
+                    action: "core:GetVersion".to_string(),
+                    resource: Option::None,
+                    params: Value::Null,
+                },
+                AllowedCheck {
+                    action: "other:GetVersion".to_string(),
+                    resource: Option::None,
+                    params: Value::Null,
+                },
+            ],
+        );
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].outcome(), AllowedOutcome::Denied);
+        assert_eq!(
+            results[0].decided_by().unwrap().policy_id,
+            "AuthorizationDenyPolicy"
+        );
+        assert_eq!(results[1].outcome(), AllowedOutcome::Allowed);
+        assert_eq!(results[2].outcome(), AllowedOutcome::Denied);
+        assert_eq!(results[2].decided_by().is_none(), true);
+    }
+
+    #[test]
+    fn subject_variable_should_not_be_spoofed() {
+        let group = Group::new("AuthorizationHomeGroup", None).add_policy(
+            CompletePolicy::from_statements(
+                "AuthorizationHomePolicy".to_string(),
+                &json!([{
+                    "Effect": "Allow",
+                    "Action": "docs:*",
+                    "Resource": "urn:docs:home:${zephir:subject}",
+                }]),
+            )
+            .unwrap(),
+        );
+        let groups = [group];
+        let identity = Identity::new("AuthorizationAlice", None);
+
+        let result = allowed_for_identity(
+            &identity,
+            &groups,
+            Option::Some("docs:Get"),
+            Option::Some("urn:docs:home:AuthorizationAlice"),
+            &Value::Null,
+        );
+        assert_eq!(result.outcome(), AllowedOutcome::Allowed);
+
+        let result = allowed_for_identity(
+            &identity,
+            &groups,
+            Option::Some("docs:Get"),
+            Option::Some("urn:docs:home:AuthorizationBob"),
+            &json!({ "zephir:subject": "AuthorizationBob" }),
+        );
+        assert_eq!(result.outcome(), AllowedOutcome::Denied);
+    }
+
+    #[test]
+    fn subject_attributes_should_be_available_to_conditions() {
+        let policy = CompletePolicy::from_statements(
+            "AuthorizationAttributesPolicy".to_string(),
+            &json!([{
+                "Effect": "Allow",
+                "Action": "billing:Get*",
+                "Condition": {
+                    "StringEquals": { "department": "${zephir:identity.department}" },
+                    "NumericGreaterThanEquals": { "zephir:identity.clearance": 2 },
+                    "Bool": { "zephir:group.billing": true },
+                },
+            }]),
+        )
+        .unwrap();
+        let attributes = |value: Value| value.as_object().unwrap().clone();
+
+        let group = Group::new("AuthorizationBillingGroup", None)
+            .add_policy(policy)
+            .set_attributes(attributes(json!({ "billing": true })));
+        let groups = [group];
+        let identity = Identity::new("AuthorizationAttributes", None)
+            .set_attributes(attributes(json!({ "department": "sales", "clearance": 2 })));
+
+        let check = |identity: &Identity, params: Value| {
+            allowed_for_identity(
+                identity,
+                &groups,
+                Option::Some("billing:GetInvoice"),
+                Option::None,
+                &params,
+            )
+            .outcome()
+        };
+
+        assert_eq!(
+            check(&identity, json!({ "department": "sales" })),
+            AllowedOutcome::Allowed
+        );
+        assert_eq!(
+            check(&identity, json!({ "department": "marketing" })),
+            AllowedOutcome::Denied
+        );
+
+        let identity = identity.set_attributes(attributes(json!({ "department": "sales" })));
+        assert_eq!(
+            check(&identity, json!({ "department": "sales" })),
+            AllowedOutcome::Denied
+        );
+
+        let result = allowed_partial_for_identity(
+            &identity,
+            &groups,
+            Option::Some("billing:GetInvoice"),
+            Option::None,
+            &json!({ "department": "sales" }),
+        );
+        assert_eq!(result.outcome(), AllowedOutcome::Abstain);
+        assert_eq!(
+            result.get_partials()[0].conditions,
+            json!({ "NumericGreaterThanEquals": { "zephir:identity.clearance": 2 } })
+        );
+    }
+
+    #[test]
+    fn current_time_should_be_provided_by_the_server() {
+        let policy = CompletePolicy::from_statements(
+            "AuthorizationCurrentTimePolicy".to_string(),
+            &json!([{
+                "Effect": "Allow",
+                "Action": "docs:Read",
+                "Condition": {
+                    "DateGreaterThan": { "zephir:identity.expires_at": "now" },
+                },
+            }]),
+        )
+        .unwrap();
+        let identity = |expires_at: &str| {
+            Identity::new("AuthorizationCurrentTime", None)
+                .add_policy(policy.clone())
+                .set_attributes(
+                    json!({ "expires_at": expires_at })
+                        .as_object()
+                        .unwrap()
+                        .clone(),
+                )
+        };
+        let check = |identity: &Identity| {
+            allowed_for_identity(
+                identity,
+                &[],
+                Option::Some("docs:Read"),
+                Option::None,
+                &json!({ "zephir:current_time": "2000-01-01T00:00:00Z" }),
+            )
+            .outcome()
+        };
+
+        assert_eq!(
+            check(&identity("2999-01-01T00:00:00Z")),
+            AllowedOutcome::Allowed
+        );
+        assert_eq!(
+            check(&identity("2020-01-01T00:00:00Z")),
+            AllowedOutcome::Denied
+        );
+    }
+
+    #[test]
+    fn effective_policies_should_be_tagged_and_merged() {
+        let identity = Identity::new("EffectiveIdentity", None)
+            .set_inline_policy(
+                zephir_policy!(
+                    "",
+                    PolicyVersion::Version1,
+                    PolicyEffect::Deny,
+                    vec!["s3:Delete*"]
+                )
+                .unwrap(),
+            )
+            .add_policy(
+                zephir_policy!(
+                    "EffectiveS3Read",
+                    PolicyVersion::Version1,
+                    PolicyEffect::Allow,
+                    vec!["s3:GetObject"]
+                )
+                .unwrap(),
+            );
+        let group = Group::new("EffectiveGroup", None).add_policy(
+            zephir_policy!(
+                "EffectiveS3All",
+                PolicyVersion::Version1,
+                PolicyEffect::Allow,
+                vec!["s3:*"]
+            )
+            .unwrap(),
+        );
+
+        let policies = effective_policies(&identity, &[group]);
+        assert_eq!(policies.len(), 3);
+        assert_eq!(policies[0].inline, true);
+        assert_eq!(policies[0].source, "identity:EffectiveIdentity");
+        assert_eq!(policies[1].policy.id, "EffectiveS3Read");
+        assert_eq!(policies[2].source, "group:EffectiveGroup");
+
+        let summaries = merge_by_action(&policies);
+        assert_eq!(summaries.len(), 3);
+
+        assert_eq!(summaries[0].action, "s3:*");
+        assert_eq!(summaries[0].effect, PolicyEffect::Allow);
+        assert_eq!(summaries[0].allowed_by, vec!["EffectiveS3All"]);
+
+        assert_eq!(summaries[1].action, "s3:Delete*");
+        assert_eq!(summaries[1].effect, PolicyEffect::Deny);
+        assert_eq!(summaries[1].allowed_by, vec!["EffectiveS3All"]);
+        assert_eq!(
+            summaries[1].denied_by,
+            vec!["__embedded_policy_identity_EffectiveIdentity__"]
+        );
+
+        assert_eq!(summaries[2].action, "s3:GetObject");
+        assert_eq!(summaries[2].effect, PolicyEffect::Allow);
+        assert_eq!(
+            summaries[2].allowed_by,
+            vec!["EffectiveS3Read", "EffectiveS3All"]
+        );
+    }
+}
@@ -108,6 +108,9 @@ pub struct Group {
 
     /// All the linked policies to this group.
     pub(crate) linked_policies: PolicySet<CompletePolicy>,
+
+    /// Arbitrary attributes (ex: cost center), available to the policy conditions.
+    pub(crate) attributes: Map<String, Value>,
 }
 
 impl Group {
@@ -124,6 +127,7 @@ impl Group {
             identities: IdentitySet::default(),
             inline_policy: policy,
             linked_policies: PolicySet::new(),
+            attributes: Map::new(),
         }
     }
 
@@ -132,6 +136,20 @@ impl Group {
         &self.name
     }
 
+    /// Gets the attributes of this group.
+    pub fn get_attributes(&self) -> &Map<String, Value> {
+        &self.attributes
+    }
+
+    /// Replaces the attributes of this group.
+    ///
+    /// # Returns
+    /// The current object.
+    pub fn set_attributes(mut self, attributes: Map<String, Value>) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
     /// Clears the inline policy associated to this group.
     /// The inline policy will be effectively deleted on next saving.
     ///
@@ -226,6 +244,10 @@ impl ToJson for Group {
                     .collect::<Vec<&str>>(),
             ),
         );
+        map.insert(
+            String::from("attributes"),
+            Value::Object(self.attributes.clone()),
+        );
 
         map
     }
@@ -246,12 +268,24 @@ impl Role for Group {
         &self.linked_policies
     }
 
-    fn allowed<T, S>(&self, action: Option<T>, resource: Option<S>, params: &Value) -> AllowedResult
+    fn evaluate<T, S>(
+        &self,
+        action: Option<T>,
+        resource: Option<S>,
+        params: &Value,
+        partial: bool,
+    ) -> AllowedResult
     where
         T: ToString + Display,
         S: ToString + Display + Debug,
     {
-        allowed(SubjectIterator::new(self), action, resource, params)
+        allowed(
+            SubjectIterator::new(self),
+            action,
+            resource,
+            params,
+            partial,
+        )
     }
 }
 
@@ -12,6 +12,9 @@ pub struct Identity {
     pub(crate) id: String,
     pub(crate) inline_policy: Option<CompletePolicy>,
     pub(crate) linked_policies: PolicySet<CompletePolicy>,
+
+    /// Arbitrary attributes (ex: department), available to the policy conditions.
+    pub(crate) attributes: Map<String, Value>,
 }
 
 impl Identity {
@@ -20,6 +23,7 @@ impl Identity {
             id: id.to_string(),
             inline_policy: policy,
             linked_policies: PolicySet::new(),
+            attributes: Map::new(),
         }
     }
 
@@ -27,6 +31,15 @@ impl Identity {
         &self.id
     }
 
+    pub fn get_attributes(&self) -> &Map<String, Value> {
+        &self.attributes
+    }
+
+    pub fn set_attributes(mut self, attributes: Map<String, Value>) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
     pub fn clear_inline_policy(mut self) -> Self {
         self.inline_policy = Option::None;
         self
@@ -95,6 +108,10 @@ impl ToJson for Identity {
                     .collect::<Vec<&str>>(),
             ),
         );
+        map.insert(
+            String::from("attributes"),
+            Value::Object(self.attributes.clone()),
+        );
 
         map
     }
@@ -123,12 +140,24 @@ impl Role for Identity {
         &self.linked_policies
     }
 
-    fn allowed<T, S>(&self, action: Option<T>, resource: Option<S>, params: &Value) -> AllowedResult
+    fn evaluate<T, S>(
+        &self,
+        action: Option<T>,
+        resource: Option<S>,
+        params: &Value,
+        partial: bool,
+    ) -> AllowedResult
     where
         T: ToString + Display,
         S: ToString + Display + Debug,
     {
-        allowed(SubjectIterator::new(self), action, resource, params)
+        allowed(
+            SubjectIterator::new(self),
+            action,
+            resource,
+            params,
+            partial,
+        )
     }
 }
 
@@ -1,4 +1,4 @@
-use crate::policy::allowed_result::{AllowedOutcome, AllowedResult};
+use crate::policy::allowed_result::{AllowedOutcome, AllowedResult, PolicyEvaluation};
 use crate::policy::policy::{CompletePolicy, MatchablePolicy};
 use crate::policy::policy_set::PolicySet;
 use crate::policy::PolicyEffect;
@@ -15,11 +15,15 @@ where
     pub(crate) params: &'a Value,
 }
 
+/// Evaluates a request against the given policies.
+/// On partial evaluation, conditions on keys missing from the params are treated
+/// as unknown and returned as residual conditions of the partial policies.
 pub(super) fn allowed<'a, T, S, I>(
     policies: I,
     action: Option<T>,
     resource: Option<S>,
     params: &'a Value,
+    partial: bool,
 ) -> AllowedResult
 where
     T: ToString + Display,
@@ -28,6 +32,8 @@ where
 {
     let mut outcome: AllowedOutcome = AllowedOutcome::Abstain;
     let mut partials = vec![];
+    let mut evaluations = vec![];
+    let mut decided_by = Option::None;
 
     let request = AllowedRequest {
         action: action.as_ref(),
@@ -36,30 +42,73 @@ where
     };
 
     for p in policies {
-        let result = p.matching(&request);
-        if !result.is_match() {
-            continue;
-        }
+        for statement in p.statements() {
+            let result = if partial {
+                statement.matching_partial(&request)
+            } else {
+                statement.matching(&request)
+            };
+            evaluations.push(PolicyEvaluation::new(p, statement, &result));
+            if !result.is_match() {
+                continue;
+            }
+
+            if result.is_full() {
+                if statement.effect == PolicyEffect::Deny {
+                    let decided_by = Option::Some(evaluations.len() - 1);
+                    return AllowedResult::new(AllowedOutcome::Denied, vec![])
+                        .with_evaluations(evaluations, decided_by);
+                }
 
-        if result.is_full() {
-            if p.effect == PolicyEffect::Deny {
-                return AllowedResult::new(AllowedOutcome::Denied, vec![]);
+                if decided_by.is_none() {
+                    decided_by = Option::Some(evaluations.len() - 1);
+                }
+
+                outcome = AllowedOutcome::Allowed;
+                continue;
             }
 
-            outcome = AllowedOutcome::Allowed;
-            continue;
+            partials.push(result.get_partial());
         }
-
-        partials.push(result.get_partial());
     }
 
-    AllowedResult::new(outcome, partials)
+    AllowedResult::new(outcome, partials).with_evaluations(evaluations, decided_by)
 }
 
 pub trait Role: Into<Value> {
     fn linked_policies(&self) -> &PolicySet<CompletePolicy>;
 
     fn allowed<T, S>(&self, action: Option<T>, resource: Option<S>, params: &Value) -> AllowedResult
+    where
+        T: ToString + Display,
+        S: ToString + Display + Debug,
+    {
+        self.evaluate(action, resource, params, false)
+    }
+
+    /// Same as allowed, but the conditions which cannot be decided with the given params
+    /// are returned as residual conditions of the partial policies, instead of not matching.
+    fn allowed_partial<T, S>(
+        &self,
+        action: Option<T>,
+        resource: Option<S>,
+        params: &Value,
+    ) -> AllowedResult
+    where
+        T: ToString + Display,
+        S: ToString + Display + Debug,
+    {
+        self.evaluate(action, resource, params, true)
+    }
+
+    /// Evaluates a request against the role policies, fully or partially.
+    fn evaluate<T, S>(
+        &self,
+        action: Option<T>,
+        resource: Option<S>,
+        params: &Value,
+        partial: bool,
+    ) -> AllowedResult
     where
         T: ToString + Display,
         S: ToString + Display + Debug,
@@ -70,7 +119,7 @@ pub trait Role: Into<Value> {
             policies.push(policy);
         }
 
-        allowed(policies.into_iter(), action, resource, params)
+        allowed(policies.into_iter(), action, resource, params, partial)
     }
 
     fn into(self) -> Value {
@@ -111,6 +160,7 @@ mod tests {
             Option::None,
             Option::None,
             &Value::Null,
+            false,
         );
         assert_eq!(res.outcome(), AllowedOutcome::Denied);
     }
@@ -138,6 +188,7 @@ mod tests {
             Option::Some("get_first"),
             Option::None,
             &Value::Null,
+            false,
         );
 
         assert_eq!(res.outcome(), AllowedOutcome::Allowed);
@@ -168,6 +219,7 @@ mod tests {
             Option::Some("get_first"),
             Option::None,
             &Value::Null,
+            false,
         );
 
         assert_eq!(res.outcome(), AllowedOutcome::Abstain);
@@ -207,6 +259,7 @@ mod tests {
             Option::Some(String::from("get_first")),
             Option::Some(String::from("resource_onw")),
             &Value::Null,
+            false,
         );
 
         assert_eq!(res.outcome(), AllowedOutcome::Denied);
@@ -262,6 +315,7 @@ mod tests {
         );
         assert_eq!(result.outcome(), AllowedOutcome::Allowed);
         assert_eq!(result.get_partials().len(), 0);
+        assert_eq!(result.decided_by().unwrap().policy_id, "RoleTestPolicy");
 
         let result = role.allowed(
             Option::Some("TestAction"),
@@ -270,6 +324,7 @@ mod tests {
         );
         assert_eq!(result.outcome(), AllowedOutcome::Denied);
         assert_eq!(result.get_partials().len(), 0);
+        assert_eq!(result.decided_by().unwrap().policy_id, "RoleTestPolicy2");
 
         let result = role.allowed(
             Option::Some("FooAction"),
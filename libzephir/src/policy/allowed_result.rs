@@ -1,4 +1,6 @@
-use crate::policy::policy::{PartialPolicy, ToJson};
+use crate::policy::match_result::MatchResult;
+use crate::policy::policy::{CompletePolicy, PartialPolicy, ToJson};
+use crate::policy::statement::PolicyStatement;
 use crate::policy::PolicyEffect;
 use serde_json::{Map, Value};
 
@@ -9,10 +11,93 @@ pub enum AllowedOutcome {
     Allowed = 1,
 }
 
+/// Records the evaluation of a single policy during an allowed check.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PolicyEvaluation {
+    /// The id of the evaluated policy.
+    pub policy_id: String,
+
+    /// The subject the policy has been loaded from (ex: "identity:id" or "group:name").
+    /// None if the policy has been evaluated outside of a subject.
+    pub source: Option<String>,
+
+    /// The sid of the evaluated statement, if any.
+    pub sid: Option<String>,
+
+    pub effect: PolicyEffect,
+    pub action_matches: Option<bool>,
+    pub resource_matches: Option<bool>,
+    pub conditions_match: Option<bool>,
+
+    /// Whether the policy matched the request (fully or partially).
+    pub matched: bool,
+
+    /// Whether the policy has been evaluated completely.
+    pub full: bool,
+}
+
+impl PolicyEvaluation {
+    pub(crate) fn new(
+        policy: &CompletePolicy,
+        statement: &PolicyStatement,
+        result: &MatchResult,
+    ) -> Self {
+        PolicyEvaluation {
+            policy_id: policy.id.clone(),
+            source: Option::None,
+            sid: statement.sid.clone(),
+            effect: statement.effect,
+            action_matches: result.action_matches(),
+            resource_matches: result.resource_matches(),
+            conditions_match: result.conditions_match(),
+            matched: result.is_match(),
+            full: result.is_full(),
+        }
+    }
+}
+
+impl ToJson for PolicyEvaluation {
+    fn to_json(&self) -> Map<String, Value> {
+        let mut result = Map::new();
+        result.insert(String::from("policy"), Value::from(self.policy_id.as_str()));
+        result.insert(
+            String::from("source"),
+            self.source.as_deref().map_or(Value::Null, Value::from),
+        );
+        result.insert(
+            String::from("sid"),
+            self.sid.as_deref().map_or(Value::Null, Value::from),
+        );
+        result.insert(String::from("effect"), Value::from(&self.effect));
+        result.insert(String::from("action"), Value::from(self.action_matches));
+        result.insert(String::from("resource"), Value::from(self.resource_matches));
+        result.insert(
+            String::from("conditions"),
+            Value::from(self.conditions_match),
+        );
+        result.insert(
+            String::from("result"),
+            Value::from(match (self.matched, self.full) {
+                (true, true) => "MATCH",
+                (true, false) => "PARTIAL",
+                (false, _) => "NO_MATCH",
+            }),
+        );
+
+        result
+    }
+}
+
 #[derive(Debug)]
 pub struct AllowedResult {
     outcome: AllowedOutcome,
     partials: Vec<PartialPolicy>,
+
+    /// The evaluations of all the policies checked for this result.
+    evaluations: Vec<PolicyEvaluation>,
+
+    /// Index of the evaluation of the policy which produced the outcome.
+    decided_by: Option<usize>,
 }
 
 impl AllowedResult {
@@ -27,6 +112,8 @@ impl AllowedResult {
                     .collect(),
                 _ => partials,
             },
+            evaluations: vec![],
+            decided_by: Option::None,
         }
     }
 
@@ -34,9 +121,66 @@ impl AllowedResult {
         Self {
             outcome: AllowedOutcome::Denied,
             partials: vec![],
+            evaluations: vec![],
+            decided_by: Option::None,
         }
     }
 
+    /// Sets the policy evaluations and the index of the deciding one.
+    pub(crate) fn with_evaluations(
+        mut self,
+        evaluations: Vec<PolicyEvaluation>,
+        decided_by: Option<usize>,
+    ) -> Self {
+        self.evaluations = evaluations;
+        self.decided_by = decided_by;
+        self
+    }
+
+    /// Sets the source subject on the evaluations not having one yet.
+    pub fn with_source<T: ToString>(mut self, source: T) -> Self {
+        let source = source.to_string();
+        for evaluation in self.evaluations.iter_mut() {
+            if evaluation.source.is_none() {
+                evaluation.source = Option::Some(source.clone());
+            }
+        }
+
+        self
+    }
+
+    /// Gets the evaluations of all the checked policies.
+    pub fn get_evaluations(&self) -> &[PolicyEvaluation] {
+        self.evaluations.as_slice()
+    }
+
+    /// Gets the evaluation of the policy which produced the outcome.
+    /// None if the outcome has not been decided by a single policy
+    /// (ex: no policy matched or the result is conditional).
+    pub fn decided_by(&self) -> Option<&PolicyEvaluation> {
+        self.decided_by.and_then(|i| self.evaluations.get(i))
+    }
+
+    /// Gets a JSON value explaining how the outcome has been reached.
+    pub fn explain(&self) -> Value {
+        let mut result = Map::new();
+        result.insert(
+            String::from("evaluations"),
+            Value::from(
+                self.evaluations
+                    .iter()
+                    .map(|e| e.to_value())
+                    .collect::<Vec<Value>>(),
+            ),
+        );
+        result.insert(
+            String::from("decided_by"),
+            self.decided_by().map_or(Value::Null, |e| e.to_value()),
+        );
+
+        Value::Object(result)
+    }
+
     pub fn get_partials(&self) -> Vec<&PartialPolicy> {
         self.partials.iter().collect()
     }
@@ -51,10 +195,21 @@ impl AllowedResult {
         }
     }
 
+    /// Whether the request has been explicitly denied by a policy.
+    /// Differently from `outcome`, a result without any matching policy is not denied.
+    pub fn is_denied(&self) -> bool {
+        self.outcome == AllowedOutcome::Denied
+    }
+
     pub fn merge(&mut self, other: Self) {
-        if other.outcome == AllowedOutcome::Denied {
+        let offset = self.evaluations.len();
+        let other_decided_by = other.decided_by.map(|i| i + offset);
+        self.evaluations.extend(other.evaluations);
+
+        if other.outcome == AllowedOutcome::Denied && self.outcome != AllowedOutcome::Denied {
             self.outcome = AllowedOutcome::Denied;
             self.partials = vec![];
+            self.decided_by = other_decided_by;
         }
 
         if self.outcome == AllowedOutcome::Denied {
@@ -62,6 +217,10 @@ impl AllowedResult {
         }
 
         if other.outcome == AllowedOutcome::Allowed {
+            if self.outcome != AllowedOutcome::Allowed {
+                self.decided_by = other_decided_by;
+            }
+
             self.outcome = AllowedOutcome::Allowed;
         }
 
@@ -102,7 +261,7 @@ impl ToJson for AllowedResult {
 
 #[cfg(test)]
 mod tests {
-    use crate::policy::allowed_result::{AllowedOutcome, AllowedResult};
+    use crate::policy::allowed_result::{AllowedOutcome, AllowedResult, PolicyEvaluation};
     use crate::policy::policy::{PartialPolicy, ToJson};
     use crate::policy::PolicyEffect;
     use serde_json::{Map, Value};
@@ -130,10 +289,7 @@ mod tests {
 
     #[test]
     fn outcome_should_be_denied_if_abstain_with_no_partials() {
-        let ar = AllowedResult {
-            outcome: AllowedOutcome::Abstain,
-            partials: vec![],
-        };
+        let ar = AllowedResult::new(AllowedOutcome::Abstain, vec![]);
 
         let mut json = Map::new();
         json.insert(String::from("outcome"), Value::from("DENIED"));
@@ -148,10 +304,7 @@ mod tests {
 
     #[test]
     fn outcome_abstain_should_be_returned() {
-        let ar = AllowedResult {
-            outcome: AllowedOutcome::Abstain,
-            partials: vec![PartialPolicy::default()],
-        };
+        let ar = AllowedResult::new(AllowedOutcome::Abstain, vec![PartialPolicy::default()]);
 
         let mut json = Map::new();
         json.insert(String::from("outcome"), Value::from("ABSTAIN"));
@@ -219,4 +372,58 @@ mod tests {
         assert_eq!(ar.outcome(), AllowedOutcome::Allowed);
         assert_eq!(ar.to_json(), json);
     }
+
+    fn evaluation(policy_id: &str, effect: PolicyEffect) -> PolicyEvaluation {
+        PolicyEvaluation {
+            policy_id: policy_id.to_string(),
+            source: None,
+            sid: None,
+            effect,
+            action_matches: Some(true),
+            resource_matches: Some(true),
+            conditions_match: Some(true),
+            matched: true,
+            full: true,
+        }
+    }
+
+    #[test]
+    fn merge_should_keep_track_of_the_deciding_policy() {
+        let mut ar = AllowedResult::new(AllowedOutcome::Abstain, vec![])
+            .with_evaluations(vec![evaluation("p1", PolicyEffect::Allow)], None)
+            .with_source("identity:test");
+        assert_eq!(ar.decided_by().is_none(), true);
+
+        ar.merge(
+            AllowedResult::new(AllowedOutcome::Allowed, vec![])
+                .with_evaluations(vec![evaluation("p2", PolicyEffect::Allow)], Some(0))
+                .with_source("group:first"),
+        );
+        assert_eq!(ar.decided_by().unwrap().policy_id, "p2");
+        assert_eq!(
+            ar.decided_by().unwrap().source,
+            Some("group:first".to_string())
+        );
+
+        ar.merge(
+            AllowedResult::new(AllowedOutcome::Denied, vec![]).with_evaluations(
+                vec![
+                    evaluation("p3", PolicyEffect::Allow),
+                    evaluation("p4", PolicyEffect::Deny),
+                ],
+                Some(1),
+            ),
+        );
+        assert_eq!(ar.outcome(), AllowedOutcome::Denied);
+        assert_eq!(ar.get_evaluations().len(), 4);
+        assert_eq!(ar.decided_by().unwrap().policy_id, "p4");
+
+        ar.merge(
+            AllowedResult::new(AllowedOutcome::Allowed, vec![])
+                .with_evaluations(vec![evaluation("p5", PolicyEffect::Allow)], Some(0)),
+        );
+        assert_eq!(ar.decided_by().unwrap().policy_id, "p4");
+        assert_eq!(ar.explain()["decided_by"]["policy"], "p4");
+        assert_eq!(ar.explain()["evaluations"].as_array().unwrap().len(), 5);
+    }
 }
@@ -2,10 +2,80 @@ use crate::err::{Error, ErrorKind};
 use crate::policy::condition::flags::Flags;
 use crate::policy::condition::CompareFn;
 use crate::policy::condition::Condition;
-use chrono::{DateTime, Utc};
+use crate::policy::variables;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 use std::cmp::Ordering;
 
+/// Maximum offset of a relative date operand, in days.
+const MAX_OFFSET_DAYS: i64 = 36500;
+
+/// The operand of a date condition.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum DateOperand {
+    Fixed(#[serde(with = "chrono::serde::ts_milliseconds")] DateTime<Utc>),
+    /// A date relative to the time of the evaluation (ex: "now-15m"), as an offset in milliseconds.
+    Now(i64),
+}
+
+impl DateOperand {
+    /// Parses an RFC 3339 date, or a date relative to the current time: "now", optionally
+    /// followed by a signed duration made of integers with units (ex: "now-1h30m", "now+7d").
+    /// Supported units are "w" (weeks), "d" (days), "h" (hours), "m" (minutes) and "s" (seconds).
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.strip_prefix("now") {
+            Some(offset) => parse_offset(offset).map(DateOperand::Now),
+            None => DateTime::parse_from_rfc3339(s)
+                .ok()
+                .map(|d| DateOperand::Fixed(d.with_timezone(&Utc))),
+        }
+    }
+
+    /// Resolves the operand against the time of the evaluation (see `variables::current_time`).
+    pub fn resolve(&self, params: &Value) -> DateTime<Utc> {
+        match self {
+            DateOperand::Fixed(date) => *date,
+            DateOperand::Now(offset) => {
+                variables::current_time(params) + Duration::milliseconds(*offset)
+            }
+        }
+    }
+}
+
+/// Parses the offset of a relative date, in milliseconds.
+fn parse_offset(offset: &str) -> Option<i64> {
+    let (sign, mut rest) = match offset.chars().next() {
+        Option::None => return Some(0),
+        Some('+') => (1, &offset[1..]),
+        Some('-') => (-1, &offset[1..]),
+        _ => return Option::None,
+    };
+
+    let mut total: i64 = 0;
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let amount: i64 = rest[..digits].parse().ok()?;
+        let unit: i64 = match rest[digits..].chars().next() {
+            Some('w') => 604_800_000,
+            Some('d') => 86_400_000,
+            Some('h') => 3_600_000,
+            Some('m') => 60_000,
+            Some('s') => 1_000,
+            _ => return Option::None,
+        };
+
+        total = total.checked_add(amount.checked_mul(unit)?)?;
+        rest = &rest[digits + 1..];
+    }
+
+    if offset.len() == 1 || total > MAX_OFFSET_DAYS * 86_400_000 {
+        return Option::None;
+    }
+
+    Some(sign * total)
+}
+
 macro_rules! impl_make_date {
     ($suffix: ident, $key: literal, $fn: ident) => {
         #[inline]
@@ -17,16 +87,12 @@ macro_rules! impl_make_date {
                     std::format!("Conditions.{} is not an object", $key),
                 )
             })? {
-                let comp = comp
-                    .as_str()
-                    .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
-                    .map(|d| d.with_timezone(&chrono::Utc))
-                    .ok_or_else(|| {
-                        Error::new(
-                            ErrorKind::UnwrapNoneValueError,
-                            std::format!("Conditions.{} value is not a string", $key),
-                        )
-                    })?;
+                let comp = comp.as_str().and_then(DateOperand::parse).ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::UnwrapNoneValueError,
+                        std::format!("Conditions.{} value is not a valid date", $key),
+                    )
+                })?;
 
                 result.push(Condition::DateCompare(
                     field.clone(),
@@ -87,5 +153,80 @@ pub(super) fn eval_value_date_compare(
                 CompareFn::Gte => Ordering::is_ge(cmp),
             }
         })
-        .unwrap()
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::policy::condition::date_compare::DateOperand;
+    use crate::policy::condition::Condition;
+    use chrono::{Duration, TimeZone, Utc};
+    use serde_json::json;
+
+    #[test]
+    fn relative_dates_should_be_parsed() {
+        let params = json!({ "zephir:current_time": "2022-03-01T10:30:00Z" });
+        let now = Utc.with_ymd_and_hms(2022, 3, 1, 10, 30, 0).unwrap();
+        for (operand, expected) in [
+            ("now", now),
+            ("now-15m", now - Duration::minutes(15)),
+            ("now+1h30m", now + Duration::minutes(90)),
+            ("now-1w2d", now - Duration::days(9)),
+            (
+                "2021-01-01T00:00:00+01:00",
+                Utc.with_ymd_and_hms(2020, 12, 31, 23, 0, 0).unwrap(),
+            ),
+        ] {
+            assert_eq!(
+                DateOperand::parse(operand).unwrap().resolve(&params),
+                expected,
+                "{}",
+                operand
+            );
+        }
+
+        for operand in [
+            "now-",
+            "now15m",
+            "now-15",
+            "now-m",
+            "now-15y",
+            "now-999999d",
+            "today",
+        ] {
+            assert!(DateOperand::parse(operand).is_none(), "{}", operand);
+        }
+    }
+
+    #[test]
+    fn should_compare_with_relative_dates() {
+        let conditions = Condition::from_value(
+            &json!({
+                "DateGreaterThan": { "token_issued_at": "now-15m" },
+                "DateLessThan": { "zephir:current_time": "2022-12-31T00:00:00Z" },
+            }),
+            false,
+        )
+        .unwrap();
+
+        let params = json!({
+            "zephir:current_time": "2022-03-01T10:30:00Z",
+            "token_issued_at": "2022-03-01T10:20:00Z",
+        });
+        assert!(conditions.iter().all(|c| c.matching(&params)));
+
+        let params = json!({
+            "zephir:current_time": "2022-03-01T10:30:00Z",
+            "token_issued_at": "2022-03-01T10:10:00Z",
+        });
+        assert_eq!(conditions[0].matching(&params), false);
+        assert_eq!(
+            conditions[0].matching(&json!({ "token_issued_at": 5 })),
+            false
+        );
+
+        let serialized = serde_json::to_string(&conditions).unwrap();
+        let conditions: Vec<Condition> = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(conditions[0].matching(&params), false);
+    }
 }
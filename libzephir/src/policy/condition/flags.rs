@@ -7,5 +7,7 @@ bitflags! {
         const ForAllValues = 0b00000001;
         const ForAnyValue = 0b00000010;
         const IfExists = 0b00000100;
+        /// The (string) value contains policy variables.
+        const Variables = 0b00001000;
     }
 }
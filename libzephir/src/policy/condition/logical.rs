@@ -0,0 +1,195 @@
+use crate::err::{Error, ErrorKind};
+use crate::policy::condition::Condition;
+use serde_json::Value;
+
+/// Builds a single condition from a condition block (an object of operators),
+/// whose conditions are ANDed together.
+fn make_block(block: &Value, operator: &str, variables: bool) -> Result<Condition, Error> {
+    if !block.is_object() {
+        return Err(Error::new(
+            ErrorKind::UnwrapNoneValueError,
+            format!("Conditions.{} block is not an object", operator),
+        ));
+    }
+
+    let mut conditions = Condition::from_value(block, variables)?;
+    Ok(if conditions.len() == 1 {
+        conditions.pop().unwrap()
+    } else {
+        Condition::AllOf(conditions)
+    })
+}
+
+/// Builds the conditions of a list of condition blocks.
+fn make_blocks(value: &Value, operator: &str, variables: bool) -> Result<Vec<Condition>, Error> {
+    let blocks = value.as_array().ok_or_else(|| {
+        Error::new(
+            ErrorKind::UnwrapNoneValueError,
+            format!("Conditions.{} is not an array", operator),
+        )
+    })?;
+
+    if blocks.is_empty() {
+        return Err(Error::new(
+            ErrorKind::UnwrapNoneValueError,
+            format!("Conditions.{} cannot be empty", operator),
+        ));
+    }
+
+    blocks
+        .iter()
+        .map(|block| make_block(block, operator, variables))
+        .collect()
+}
+
+#[inline]
+pub(super) fn make_any_of(value: &Value, variables: bool) -> Result<Condition, Error> {
+    Ok(Condition::AnyOf(make_blocks(value, "AnyOf", variables)?))
+}
+
+#[inline]
+pub(super) fn make_all_of(value: &Value, variables: bool) -> Result<Condition, Error> {
+    Ok(Condition::AllOf(make_blocks(value, "AllOf", variables)?))
+}
+
+#[inline]
+pub(super) fn make_not(value: &Value, variables: bool) -> Result<Condition, Error> {
+    Ok(Condition::Not(Box::new(make_block(
+        value, "Not", variables,
+    )?)))
+}
+
+/// Partially evaluates the conjunction of the given conditions, stopping at the first
+/// non-matching one: None if no condition fails, but some cannot be decided.
+pub(super) fn evaluate_all_of_partial(conditions: &[Condition], params: &Value) -> Option<bool> {
+    let mut decided = true;
+    for c in conditions {
+        match c.matching_partial(params) {
+            Some(false) => return Some(false),
+            Some(true) => {}
+            None => decided = false,
+        }
+    }
+
+    if decided {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+/// Partially evaluates the disjunction of the given conditions, stopping at the first
+/// matching one: None if no condition matches, but some cannot be decided.
+pub(super) fn evaluate_any_of_partial(conditions: &[Condition], params: &Value) -> Option<bool> {
+    let mut decided = true;
+    for c in conditions {
+        match c.matching_partial(params) {
+            Some(true) => return Some(true),
+            Some(false) => {}
+            None => decided = false,
+        }
+    }
+
+    if decided {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::policy::condition::Condition;
+    use serde_json::json;
+
+    #[test]
+    fn should_build_condition_tree() {
+        let conditions = Condition::from_value(
+            &json!({
+                "AnyOf": [
+                    { "Bool": { "mfa": true } },
+                    {
+                        "StringEquals": { "network": "internal" },
+                        "Not": { "Bool": { "guest": true } },
+                    },
+                ],
+            }),
+            false,
+        )
+        .unwrap();
+        assert_eq!(conditions.len(), 1);
+
+        let condition = &conditions[0];
+        assert!(matches!(condition, Condition::AnyOf(c) if c.len() == 2));
+
+        assert_eq!(condition.matching(&json!({ "mfa": true })), true);
+        assert_eq!(
+            condition.matching(&json!({ "mfa": false, "network": "internal", "guest": false })),
+            true
+        );
+        assert_eq!(
+            condition.matching(&json!({ "mfa": false, "network": "internal", "guest": true })),
+            false
+        );
+    }
+
+    #[test]
+    fn should_partially_evaluate_condition_tree() {
+        let conditions = Condition::from_value(
+            &json!({
+                "AnyOf": [
+                    { "Bool": { "mfa": true } },
+                    { "Bool": { "admin": true } },
+                ],
+            }),
+            false,
+        )
+        .unwrap();
+        let condition = &conditions[0];
+
+        assert_eq!(
+            condition.matching_partial(&json!({ "mfa": true })),
+            Some(true)
+        );
+        assert_eq!(condition.matching_partial(&json!({ "mfa": false })), None);
+        assert_eq!(
+            condition.matching_partial(&json!({ "mfa": false, "admin": false })),
+            Some(false)
+        );
+
+        let conditions =
+            Condition::from_value(&json!({ "Not": { "Bool": { "mfa": true } } }), false).unwrap();
+        assert_eq!(conditions[0].matching_partial(&json!({})), None);
+        assert_eq!(
+            conditions[0].matching_partial(&json!({ "mfa": false })),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn should_raise_err_if_malformed_blocks() {
+        for value in [
+            json!({ "AnyOf": {} }),
+            json!({ "AllOf": [] }),
+            json!({ "AnyOf": [ "Bool" ] }),
+            json!({ "Not": [ { "Bool": { "mfa": true } } ] }),
+            json!({ "ForAnyValueAnyOf": [ { "Bool": { "mfa": true } } ] }),
+        ] {
+            Condition::from_value(&value, false).expect_err("Should raise error");
+        }
+    }
+
+    #[test]
+    fn condition_tree_should_be_serializable() {
+        let conditions = Condition::from_value(
+            &json!({ "AllOf": [ { "Not": { "Bool": { "guest": true } } } ] }),
+            false,
+        )
+        .unwrap();
+
+        let serialized = serde_json::to_string(&conditions).unwrap();
+        let conditions: Vec<Condition> = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(conditions[0].matching(&json!({ "guest": false })), true);
+        assert_eq!(conditions[0].matching(&json!({ "guest": true })), false);
+    }
+}
@@ -4,10 +4,16 @@ mod binary_compare;
 mod bool_compare;
 mod date_compare;
 mod ip_compare;
+mod logical;
 mod numeric_compare;
+mod operands;
+mod pattern;
 mod script;
 mod string_equals;
+mod string_like;
+mod string_matches;
 mod string_not_equals;
+mod time_compare;
 
 use crate::err::{Error, ErrorKind};
 use crate::policy::condition::binary_compare::{
@@ -19,30 +25,48 @@ use crate::policy::condition::bool_compare::{
 use crate::policy::condition::date_compare::{
     eval_value_date_compare, evaluate_date_compare, make_date_equals, make_date_greater_than,
     make_date_greater_than_or_equal, make_date_less_than, make_date_less_than_or_equal,
-    make_date_not_equals,
+    make_date_not_equals, DateOperand,
 };
 use crate::policy::condition::ip_compare::{
     eval_value_ip_address, eval_value_not_ip_address, evaluate_ip_address, evaluate_not_ip_address,
     make_ip_address, make_not_ip_address,
 };
+use crate::policy::condition::logical::{
+    evaluate_all_of_partial, evaluate_any_of_partial, make_all_of, make_any_of, make_not,
+};
 use crate::policy::condition::numeric_compare::{
     eval_value_numeric_compare, evaluate_numeric_compare, make_numeric_equals,
     make_numeric_greater_than, make_numeric_greater_than_or_equal, make_numeric_less_than,
     make_numeric_less_than_or_equal, make_numeric_not_equals,
 };
+use crate::policy::condition::operands::{eval_value_operands, evaluate_operands, make_operands};
+use crate::policy::condition::pattern::Pattern;
 use crate::policy::condition::script::{evaluate_script, make_script};
 use crate::policy::condition::string_equals::{
     eval_value_str_equals, evaluate_string_equals, make_string_equals,
 };
+use crate::policy::condition::string_like::{
+    eval_value_string_like, eval_value_string_not_like, evaluate_string_like,
+    evaluate_string_not_like, make_string_like, make_string_not_like,
+};
+use crate::policy::condition::string_matches::{
+    eval_value_string_matches, evaluate_string_matches, make_string_matches,
+};
 use crate::policy::condition::string_not_equals::{
     eval_value_str_not_equals, evaluate_string_not_equals, make_string_not_equals,
 };
+use crate::policy::condition::time_compare::{
+    eval_value_time_of_day, eval_value_weekday, evaluate_time_of_day, evaluate_weekday,
+    make_time_of_day, make_weekday, TimeOfDayRange, Weekdays,
+};
+use crate::policy::variables;
+use crate::utils::decimal::Decimal;
 use crate::utils::string_utils::StringUtils;
-use chrono::{DateTime, Utc};
 use cidr::AnyIpCidr;
 use flags::Flags;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
+use std::borrow::Cow;
 use std::fmt::Debug;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -59,18 +83,22 @@ pub enum CompareFn {
 pub enum Condition {
     StringEquals(String, String, bool, Flags),
     StringNotEquals(String, String, bool, Flags),
-    NumericCompare(String, i64, CompareFn, Flags),
-    DateCompare(
-        String,
-        #[serde(with = "chrono::serde::ts_milliseconds")] DateTime<Utc>,
-        CompareFn,
-        Flags,
-    ),
+    StringLike(String, Pattern, Flags),
+    StringNotLike(String, Pattern, Flags),
+    StringMatches(String, Pattern, Flags),
+    NumericCompare(String, Decimal, CompareFn, Flags),
+    DateCompare(String, DateOperand, CompareFn, Flags),
+    TimeOfDay(String, TimeOfDayRange, Flags),
+    Weekday(String, Weekdays, Flags),
     BoolEquals(String, bool, Flags),
     BinaryEquals(String, Vec<u8>, Flags),
     IpAddress(String, AnyIpCidr, Flags),
     NotIpAddress(String, AnyIpCidr, Flags),
     Script(String),
+    AnyOf(Vec<Condition>),
+    AllOf(Vec<Condition>),
+    Not(Box<Condition>),
+    Operands(String, Vec<Condition>, Flags),
 }
 
 lazy_static! {
@@ -108,8 +136,84 @@ where
     }
 }
 
+/// Keeps the Variables flag only if the value actually contains policy variables.
+pub(super) fn variable_flags(value: &str, flags: Flags) -> Flags {
+    if variables::has_variables(value) {
+        flags
+    } else {
+        flags - Flags::Variables
+    }
+}
+
+/// Resolves the policy variables of a string condition value, if flagged as containing any.
+/// Returns None if a variable cannot be resolved with the given params.
+fn resolve_value<'a>(
+    value: &'a str,
+    case_sensitive: bool,
+    flags: &Flags,
+    params: &Value,
+) -> Option<Cow<'a, str>> {
+    if !flags.intersects(Flags::Variables) {
+        return Some(Cow::Borrowed(value));
+    }
+
+    let value = variables::resolve(value, params)?;
+    Some(Cow::Owned(if case_sensitive {
+        value
+    } else {
+        value.to_lowercase()
+    }))
+}
+
+/// Builds the conditions of an operator (without the flags prefix and suffix).
+fn make_conditions(
+    key: &str,
+    value: &Value,
+    flags: Flags,
+    variables: bool,
+) -> Result<Vec<Condition>, Error> {
+    let string_flags = if variables {
+        flags | Flags::Variables
+    } else {
+        flags
+    };
+
+    match key {
+        "StringEquals" => make_string_equals(value, false, string_flags),
+        "StringNotEquals" => make_string_not_equals(value, false, string_flags),
+        "StringEqualsIgnoreCase" => make_string_equals(value, true, string_flags),
+        "StringNotEqualsIgnoreCase" => make_string_not_equals(value, true, string_flags),
+        "StringLike" => make_string_like(value, string_flags),
+        "StringNotLike" => make_string_not_like(value, string_flags),
+        "StringMatches" => make_string_matches(value, flags),
+        "NumericEquals" => make_numeric_equals(value, flags),
+        "NumericNotEquals" => make_numeric_not_equals(value, flags),
+        "NumericLessThan" => make_numeric_less_than(value, flags),
+        "NumericLessThanEquals" => make_numeric_less_than_or_equal(value, flags),
+        "NumericGreaterThan" => make_numeric_greater_than(value, flags),
+        "NumericGreaterThanEquals" => make_numeric_greater_than_or_equal(value, flags),
+        "DateEquals" => make_date_equals(value, flags),
+        "DateNotEquals" => make_date_not_equals(value, flags),
+        "DateLessThan" => make_date_less_than(value, flags),
+        "DateLessThanEquals" => make_date_less_than_or_equal(value, flags),
+        "DateGreaterThan" => make_date_greater_than(value, flags),
+        "DateGreaterThanEquals" => make_date_greater_than_or_equal(value, flags),
+        "TimeOfDay" => make_time_of_day(value, flags),
+        "Weekday" => make_weekday(value, flags),
+        "Bool" => make_bool_equals(value, flags),
+        "Binary" => make_binary_equals(value, flags),
+        "IpAddress" => make_ip_address(value, flags),
+        "NotIpAddress" => make_not_ip_address(value, flags),
+        _ => Err(Error::from("Unknown condition key")),
+    }
+}
+
 impl Condition {
-    pub fn from_value(conditions: &Value) -> Result<Vec<Self>, Error> {
+    /// Builds the conditions from their JSON representation.
+    ///
+    /// If variables are enabled, the string condition values can contain
+    /// policy variables (ex: "${zephir:subject}"), resolved on evaluation.
+    pub fn from_value(conditions: &Value, variables: bool) -> Result<Vec<Self>, Error> {
         let mut result = vec![];
         if conditions.is_null() {
             return Ok(result);
@@ -126,9 +230,24 @@ impl Condition {
             let mut key = key.as_str();
             let mut flags = Flags::None;
 
-            if key == "Script" {
-                result.push(make_script(value)?);
-                continue;
+            match key {
+                "Script" => {
+                    result.push(make_script(value)?);
+                    continue;
+                }
+                "AnyOf" => {
+                    result.push(make_any_of(value, variables)?);
+                    continue;
+                }
+                "AllOf" => {
+                    result.push(make_all_of(value, variables)?);
+                    continue;
+                }
+                "Not" => {
+                    result.push(make_not(value, variables)?);
+                    continue;
+                }
+                _ => {}
             }
 
             if key.starts_with("ForAnyValue") {
@@ -144,68 +263,169 @@ impl Condition {
                 key = key.slice(0..(key.len() - 8));
             }
 
-            match key {
-                "StringEquals" => result.append(make_string_equals(value, false, flags)?.as_mut()),
-                "StringNotEquals" => {
-                    result.append(make_string_not_equals(value, false, flags)?.as_mut())
-                }
-                "StringEqualsIgnoreCase" => {
-                    result.append(make_string_equals(value, true, flags)?.as_mut())
+            match value.as_object() {
+                Some(entries) if entries.values().any(Value::is_array) => {
+                    for (field, operand) in entries {
+                        match operand.as_array() {
+                            Some(operands) => result.push(make_operands(
+                                key,
+                                field,
+                                operands,
+                                flags,
+                                |value, flags| make_conditions(key, value, flags, variables),
+                            )?),
+                            None => {
+                                let mut entry = Map::new();
+                                entry.insert(field.clone(), operand.clone());
+                                result.append(
+                                    make_conditions(key, &Value::Object(entry), flags, variables)?
+                                        .as_mut(),
+                                );
+                            }
+                        }
+                    }
                 }
-                "StringNotEqualsIgnoreCase" => {
-                    result.append(make_string_not_equals(value, true, flags)?.as_mut())
-                }
-                "NumericEquals" => result.append(make_numeric_equals(value, flags)?.as_mut()),
-                "NumericNotEquals" => {
-                    result.append(make_numeric_not_equals(value, flags)?.as_mut())
-                }
-                "NumericLessThan" => result.append(make_numeric_less_than(value, flags)?.as_mut()),
-                "NumericLessThanEquals" => {
-                    result.append(make_numeric_less_than_or_equal(value, flags)?.as_mut())
-                }
-                "NumericGreaterThan" => {
-                    result.append(make_numeric_greater_than(value, flags)?.as_mut())
-                }
-                "NumericGreaterThanEquals" => {
-                    result.append(make_numeric_greater_than_or_equal(value, flags)?.as_mut())
-                }
-                "DateEquals" => result.append(make_date_equals(value, flags)?.as_mut()),
-                "DateNotEquals" => result.append(make_date_not_equals(value, flags)?.as_mut()),
-                "DateLessThan" => result.append(make_date_less_than(value, flags)?.as_mut()),
-                "DateLessThanEquals" => {
-                    result.append(make_date_less_than_or_equal(value, flags)?.as_mut())
-                }
-                "DateGreaterThan" => result.append(make_date_greater_than(value, flags)?.as_mut()),
-                "DateGreaterThanEquals" => {
-                    result.append(make_date_greater_than_or_equal(value, flags)?.as_mut())
-                }
-                "Bool" => result.append(make_bool_equals(value, flags)?.as_mut()),
-                "Binary" => result.append(make_binary_equals(value, flags)?.as_mut()),
-                "IpAddress" => result.append(make_ip_address(value, flags)?.as_mut()),
-                "NotIpAddress" => result.append(make_not_ip_address(value, flags)?.as_mut()),
-
-                _ => return Err(Error::from("Unknown condition key")),
+                _ => result.append(make_conditions(key, value, flags, variables)?.as_mut()),
             }
         }
 
         Ok(result)
     }
 
+    /// Whether the condition can be decided with the given params.
+    /// Conditions on keys missing from the params are unknown, unless flagged as IfExists,
+    /// as well as conditions on values with unresolved policy variables.
+    /// Condition blocks are decidable if their result does not depend on the unknown conditions.
+    pub fn is_decidable(&self, params: &Value) -> bool {
+        if let Self::AnyOf(_) | Self::AllOf(_) | Self::Not(_) = self {
+            return self.matching_partial(params).is_some();
+        }
+
+        let extra = params.as_object().unwrap_or(&EMPTY_MAP);
+        let resolvable = self.is_resolvable(params);
+        let (key, flags) = match self {
+            Self::StringEquals(key, _, _, flags) | Self::StringNotEquals(key, _, _, flags) => {
+                (key, flags)
+            }
+            Self::StringLike(key, _, flags)
+            | Self::StringNotLike(key, _, flags)
+            | Self::StringMatches(key, _, flags) => (key, flags),
+            Self::NumericCompare(key, _, _, flags) => (key, flags),
+            Self::DateCompare(key, _, _, flags) => (key, flags),
+            Self::TimeOfDay(key, _, flags) => (key, flags),
+            Self::Weekday(key, _, flags) => (key, flags),
+            Self::BoolEquals(key, _, flags) => (key, flags),
+            Self::BinaryEquals(key, _, flags) => (key, flags),
+            Self::IpAddress(key, _, flags) | Self::NotIpAddress(key, _, flags) => (key, flags),
+            Self::Operands(key, _, flags) => (key, flags),
+            Self::Script(_) | Self::AnyOf(_) | Self::AllOf(_) | Self::Not(_) => return true,
+        };
+
+        if !resolvable {
+            return flags.intersects(Flags::IfExists) && !extra.contains_key(key);
+        }
+
+        flags.intersects(Flags::IfExists) || extra.contains_key(key)
+    }
+
+    /// Whether the policy variables of the condition values can be resolved with the given params.
+    fn is_resolvable(&self, params: &Value) -> bool {
+        match self {
+            Self::StringEquals(_, other, _, flags) | Self::StringNotEquals(_, other, _, flags)
+                if flags.intersects(Flags::Variables) =>
+            {
+                variables::is_resolvable(other, params)
+            }
+            Self::Operands(_, operands, _) => operands.iter().all(|c| c.is_resolvable(params)),
+            _ => true,
+        }
+    }
+
+    /// Whether the condition is the negation of another one (ex: NotIpAddress).
+    fn is_negated(&self) -> bool {
+        matches!(
+            self,
+            Self::StringNotEquals(..)
+                | Self::StringNotLike(..)
+                | Self::NotIpAddress(..)
+                | Self::NumericCompare(_, _, CompareFn::NEq, _)
+                | Self::DateCompare(_, _, CompareFn::NEq, _)
+        )
+    }
+
+    /// Whether a single request value matches the condition value, regardless of the flags.
+    /// Values with unresolved policy variables do not match.
+    fn matching_value(&self, value: &Value, params: &Value) -> bool {
+        match self {
+            Self::StringEquals(_, other, case_sensitive, flags) => {
+                resolve_value(other, *case_sensitive, flags, params)
+                    .map(|other| eval_value_str_equals(value, &other, case_sensitive))
+                    .unwrap_or(false)
+            }
+            Self::StringNotEquals(_, other, case_sensitive, flags) => {
+                resolve_value(other, *case_sensitive, flags, params)
+                    .map(|other| eval_value_str_not_equals(value, &other, case_sensitive))
+                    .unwrap_or(false)
+            }
+            Self::StringLike(_, other, _) => eval_value_string_like(value, other),
+            Self::StringNotLike(_, other, _) => eval_value_string_not_like(value, other),
+            Self::StringMatches(_, other, _) => eval_value_string_matches(value, other),
+            Self::NumericCompare(_, other, operator, _) => {
+                eval_value_numeric_compare(value, other, operator)
+            }
+            Self::DateCompare(_, other, operator, _) => {
+                eval_value_date_compare(value, &other.resolve(params), operator)
+            }
+            Self::TimeOfDay(_, other, _) => eval_value_time_of_day(value, other),
+            Self::Weekday(_, other, _) => eval_value_weekday(value, other),
+            Self::BoolEquals(_, other, _) => eval_value_bool_equals(value, other),
+            Self::BinaryEquals(_, other, _) => eval_value_binary_equals(value, other),
+            Self::IpAddress(_, other, _) => eval_value_ip_address(value, other),
+            Self::NotIpAddress(_, other, _) => eval_value_not_ip_address(value, other),
+            Self::Operands(_, operands, _) => eval_value_operands(operands, value, params),
+            Self::Script(_) | Self::AnyOf(_) | Self::AllOf(_) | Self::Not(_) => false,
+        }
+    }
+
     pub fn matching(&self, params: &Value) -> bool {
         let extra = params.as_object().unwrap_or(&EMPTY_MAP);
         match self {
             Self::StringEquals(key, other, case_sensitive, flags) => {
+                let other = match resolve_value(other, *case_sensitive, flags, params) {
+                    Some(other) => other,
+                    None => return flags.intersects(Flags::IfExists) && extra.get(key).is_none(),
+                };
+                let other = other.as_ref();
+
                 internal_matching(extra, key, flags, |v| {
                     eval_value_str_equals(v, other, case_sensitive)
                 })
                 .unwrap_or_else(|| evaluate_string_equals(extra, key, other, case_sensitive))
             }
             Self::StringNotEquals(key, other, case_sensitive, flags) => {
+                let other = match resolve_value(other, *case_sensitive, flags, params) {
+                    Some(other) => other,
+                    None => return flags.intersects(Flags::IfExists) && extra.get(key).is_none(),
+                };
+                let other = other.as_ref();
+
                 internal_matching(extra, key, flags, |v| {
                     eval_value_str_not_equals(v, other, case_sensitive)
                 })
                 .unwrap_or_else(|| evaluate_string_not_equals(extra, key, other, case_sensitive))
             }
+            Self::StringLike(key, other, flags) => {
+                internal_matching(extra, key, flags, |v| eval_value_string_like(v, other))
+                    .unwrap_or_else(|| evaluate_string_like(extra, key, other))
+            }
+            Self::StringNotLike(key, other, flags) => {
+                internal_matching(extra, key, flags, |v| eval_value_string_not_like(v, other))
+                    .unwrap_or_else(|| evaluate_string_not_like(extra, key, other))
+            }
+            Self::StringMatches(key, other, flags) => {
+                internal_matching(extra, key, flags, |v| eval_value_string_matches(v, other))
+                    .unwrap_or_else(|| evaluate_string_matches(extra, key, other))
+            }
             Self::NumericCompare(key, other, operator, flags) => {
                 internal_matching(extra, key, flags, |v| {
                     eval_value_numeric_compare(v, other, operator)
@@ -213,11 +433,20 @@ impl Condition {
                 .unwrap_or_else(|| evaluate_numeric_compare(extra, key, other, operator))
             }
             Self::DateCompare(key, other, operator, flags) => {
+                let other = &other.resolve(params);
                 internal_matching(extra, key, flags, |v| {
                     eval_value_date_compare(v, other, operator)
                 })
                 .unwrap_or_else(|| evaluate_date_compare(extra, key, other, operator))
             }
+            Self::TimeOfDay(key, other, flags) => {
+                internal_matching(extra, key, flags, |v| eval_value_time_of_day(v, other))
+                    .unwrap_or_else(|| evaluate_time_of_day(extra, key, other))
+            }
+            Self::Weekday(key, other, flags) => {
+                internal_matching(extra, key, flags, |v| eval_value_weekday(v, other))
+                    .unwrap_or_else(|| evaluate_weekday(extra, key, other))
+            }
             Self::BoolEquals(key, other, flags) => {
                 internal_matching(extra, key, flags, |v| eval_value_bool_equals(v, other))
                     .unwrap_or_else(|| evaluate_bool_equals(extra, key, other))
@@ -235,6 +464,29 @@ impl Condition {
                     .unwrap_or_else(|| evaluate_not_ip_address(extra, key, other))
             }
             Self::Script(script) => evaluate_script(script.as_str(), params),
+            Self::AnyOf(conditions) => conditions.iter().any(|c| c.matching(params)),
+            Self::AllOf(conditions) => conditions.iter().all(|c| c.matching(params)),
+            Self::Not(condition) => !condition.matching(params),
+            Self::Operands(key, operands, flags) => internal_matching(extra, key, flags, |v| {
+                eval_value_operands(operands, v, params)
+            })
+            .unwrap_or_else(|| evaluate_operands(extra, key, operands, params)),
+        }
+    }
+
+    /// Partially evaluates the condition with the given params.
+    ///
+    /// # Returns
+    ///
+    /// None if the condition cannot be decided (see `is_decidable`),
+    /// whether the condition matches otherwise
+    pub fn matching_partial(&self, params: &Value) -> Option<bool> {
+        match self {
+            Self::AnyOf(conditions) => evaluate_any_of_partial(conditions, params),
+            Self::AllOf(conditions) => evaluate_all_of_partial(conditions, params),
+            Self::Not(condition) => condition.matching_partial(params).map(|m| !m),
+            _ if self.is_decidable(params) => Some(self.matching(params)),
+            _ => None,
         }
     }
 }
@@ -2,6 +2,7 @@ use crate::err::{Error, ErrorKind};
 use crate::policy::condition::flags::Flags;
 use crate::policy::condition::CompareFn;
 use crate::policy::condition::Condition;
+use crate::utils::decimal::Decimal;
 use serde_json::{Map, Value};
 use std::cmp::Ordering;
 
@@ -16,10 +17,10 @@ macro_rules! impl_make_numeric {
                     std::format!("Conditions.{} is not an object", $key),
                 )
             })? {
-                let comp = comp.as_i64().ok_or_else(|| {
+                let comp = Decimal::from_value(comp).ok_or_else(|| {
                     Error::new(
                         ErrorKind::UnwrapNoneValueError,
-                        std::format!("Conditions.{} value is not a string", $key),
+                        std::format!("Conditions.{} value is not a number", $key),
                     )
                 })?;
 
@@ -55,7 +56,7 @@ impl_make_numeric!(
 pub(super) fn evaluate_numeric_compare(
     value: &Map<String, Value>,
     key: &str,
-    other: &i64,
+    other: &Decimal,
     operator: &CompareFn,
 ) -> bool {
     value
@@ -65,10 +66,11 @@ pub(super) fn evaluate_numeric_compare(
         .unwrap()
 }
 
+/// Compares a numeric value (or a string containing a number) with exact decimal semantics.
+/// Values which are not numbers do not match, whatever the operator.
 #[inline]
-pub(super) fn eval_value_numeric_compare(value: &Value, other: &i64, op: &CompareFn) -> bool {
-    value
-        .as_i64()
+pub(super) fn eval_value_numeric_compare(value: &Value, other: &Decimal, op: &CompareFn) -> bool {
+    Decimal::from_value(value)
         .map(|v| {
             let cmp = v.cmp(other);
             match *op {
@@ -80,5 +82,90 @@ pub(super) fn eval_value_numeric_compare(value: &Value, other: &i64, op: &Compar
                 CompareFn::Gte => Ordering::is_ge(cmp),
             }
         })
-        .unwrap()
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::policy::condition::flags::Flags;
+    use crate::policy::condition::numeric_compare::{
+        eval_value_numeric_compare, make_numeric_greater_than, make_numeric_less_than_or_equal,
+    };
+    use crate::policy::condition::{CompareFn, Condition};
+    use serde_json::{json, Value};
+
+    fn number(s: &str) -> Value {
+        serde_json::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn should_build_decimal_numeric_condition() {
+        let mut condition =
+            make_numeric_less_than_or_equal(&json!({ "risk": number("0.75") }), Flags::None)
+                .unwrap();
+        assert_eq!(condition.len(), 1);
+
+        let cond = condition.pop().unwrap();
+        assert_eq!(cond.matching(&json!({ "risk": number("0.75") })), true);
+        assert_eq!(
+            cond.matching(&json!({ "risk": number("0.750000000000000001") })),
+            false
+        );
+        assert_eq!(cond.matching(&json!({ "risk": 0 })), true);
+        assert_eq!(cond.matching(&json!({ "risk": "0.5" })), true);
+        assert_eq!(cond.matching(&json!({ "risk": "high" })), false);
+    }
+
+    #[test]
+    fn should_raise_err_if_malformed_object() {
+        make_numeric_greater_than(&json!(""), Flags::None).expect_err("Should raise error");
+        make_numeric_greater_than(&json!({ "price": true }), Flags::None)
+            .expect_err("Should raise error");
+        make_numeric_greater_than(&json!({ "price": "cheap" }), Flags::None)
+            .expect_err("Should raise error");
+    }
+
+    #[test]
+    fn should_compare_large_numbers_exactly() {
+        let other = "18446744073709551617.5".parse().unwrap();
+        assert_eq!(
+            eval_value_numeric_compare(&number("18446744073709551617.25"), &other, &CompareFn::Lt),
+            true
+        );
+        assert_eq!(
+            eval_value_numeric_compare(
+                &number("1.84467440737095516175e19"),
+                &other,
+                &CompareFn::Eq
+            ),
+            true
+        );
+    }
+
+    #[test]
+    fn should_support_flags_and_serialization() {
+        let conditions = Condition::from_value(
+            &json!({
+                "ForAllValuesNumericGreaterThan": { "prices": number("9.99") },
+                "ForAnyValueNumericEquals": { "ratios": number("0.1") },
+            }),
+            false,
+        )
+        .unwrap();
+
+        let serialized = serde_json::to_string(&conditions).unwrap();
+        let conditions: Vec<Condition> = serde_json::from_str(&serialized).unwrap();
+
+        let params = json!({
+            "prices": [number("10"), number("9.991")],
+            "ratios": [number("0.3"), number("0.10")],
+        });
+        assert!(conditions.iter().all(|c| c.matching(&params)));
+
+        let params = json!({
+            "prices": [number("10"), number("9.99")],
+            "ratios": [number("0.3"), number("0.1000001")],
+        });
+        assert!(conditions.iter().all(|c| !c.matching(&params)));
+    }
 }
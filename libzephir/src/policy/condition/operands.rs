@@ -0,0 +1,146 @@
+use crate::err::{Error, ErrorKind};
+use crate::policy::condition::flags::Flags;
+use crate::policy::condition::Condition;
+use serde_json::{Map, Value};
+
+/// Builds the condition of a multi-valued entry (ex: `"source": ["10.0.0.0/8", "::1/128"]`).
+///
+/// Each operand is built with the given maker as a single-valued entry,
+/// the ForAnyValue/ForAllValues/IfExists flags being applied to the whole entry.
+pub(super) fn make_operands<F>(
+    operator: &str,
+    field: &str,
+    operands: &[Value],
+    flags: Flags,
+    make: F,
+) -> Result<Condition, Error>
+where
+    F: Fn(&Value, Flags) -> Result<Vec<Condition>, Error>,
+{
+    if operands.is_empty() {
+        return Err(Error::new(
+            ErrorKind::UnwrapNoneValueError,
+            format!("Conditions.{} operands cannot be empty", operator),
+        ));
+    }
+
+    let operand_flags = flags - (Flags::ForAnyValue | Flags::ForAllValues | Flags::IfExists);
+    let mut conditions = vec![];
+    for operand in operands {
+        let mut entry = Map::new();
+        entry.insert(field.to_string(), operand.clone());
+        conditions.append(make(&Value::Object(entry), operand_flags)?.as_mut());
+    }
+
+    Ok(Condition::Operands(field.to_string(), conditions, flags))
+}
+
+/// Whether a single request value satisfies a multi-valued entry: the value must match
+/// any of the operands, or none of them for negated operators (ex: NotIpAddress).
+#[inline]
+pub(super) fn eval_value_operands(operands: &[Condition], value: &Value, params: &Value) -> bool {
+    if operands.iter().any(Condition::is_negated) {
+        operands.iter().all(|c| c.matching_value(value, params))
+    } else {
+        operands.iter().any(|c| c.matching_value(value, params))
+    }
+}
+
+#[inline]
+pub(super) fn evaluate_operands(
+    value: &Map<String, Value>,
+    key: &str,
+    operands: &[Condition],
+    params: &Value,
+) -> bool {
+    value
+        .get(key)
+        .map(|v| eval_value_operands(operands, v, params))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::policy::condition::Condition;
+    use serde_json::json;
+
+    fn condition(value: serde_json::Value) -> Condition {
+        let mut conditions = Condition::from_value(&value, true).unwrap();
+        assert_eq!(conditions.len(), 1);
+
+        conditions.pop().unwrap()
+    }
+
+    #[test]
+    fn any_operand_should_satisfy_the_entry() {
+        let cond = condition(json!({
+            "IpAddress": { "source": ["10.0.0.0/8", "192.168.0.0/16"] },
+        }));
+        assert_eq!(cond.matching(&json!({ "source": "10.1.2.3" })), true);
+        assert_eq!(cond.matching(&json!({ "source": "192.168.1.1" })), true);
+        assert_eq!(cond.matching(&json!({ "source": "172.16.0.1" })), false);
+        assert_eq!(cond.matching(&json!({})), false);
+
+        let cond = condition(json!({
+            "StringEquals": { "owner": ["admin", "${zephir:subject}"] },
+        }));
+        let params = json!({ "owner": "alice", "zephir:subject": "alice" });
+        assert_eq!(cond.matching(&params), true);
+        let params = json!({ "owner": "bob", "zephir:subject": "alice" });
+        assert_eq!(cond.matching(&params), false);
+    }
+
+    #[test]
+    fn negated_operators_should_match_none_of_the_operands() {
+        let cond = condition(json!({
+            "NotIpAddress": { "source": ["10.0.0.0/8", "192.168.0.0/16"] },
+        }));
+        assert_eq!(cond.matching(&json!({ "source": "172.16.0.1" })), true);
+        assert_eq!(cond.matching(&json!({ "source": "10.1.2.3" })), false);
+
+        let cond = condition(json!({ "NumericNotEquals": { "level": [1, 2] } }));
+        assert_eq!(cond.matching(&json!({ "level": 3 })), true);
+        assert_eq!(cond.matching(&json!({ "level": 2 })), false);
+    }
+
+    #[test]
+    fn operands_should_compose_with_flags() {
+        let cond = condition(json!({
+            "ForAllValuesStringEquals": { "tags": ["a", "b"] },
+        }));
+        assert_eq!(cond.matching(&json!({ "tags": ["a", "b", "a"] })), true);
+        assert_eq!(cond.matching(&json!({ "tags": ["a", "c"] })), false);
+
+        let cond = condition(json!({
+            "ForAnyValueNumericEquals": { "levels": [1, 2] },
+        }));
+        assert_eq!(cond.matching(&json!({ "levels": [3, 2] })), true);
+        assert_eq!(cond.matching(&json!({ "levels": [3, 4] })), false);
+
+        let cond = condition(json!({ "BoolIfExists": { "mfa": [true] } }));
+        assert_eq!(cond.matching(&json!({})), true);
+        assert_eq!(cond.matching(&json!({ "mfa": false })), false);
+    }
+
+    #[test]
+    fn should_raise_err_if_malformed_operands() {
+        for value in [
+            json!({ "IpAddress": { "source": [] } }),
+            json!({ "IpAddress": { "source": ["10.0.0.0/8", 1] } }),
+            json!({ "IpAddress": { "source": [["10.0.0.0/8"]] } }),
+            json!({ "UnknownOperator": { "source": ["a"] } }),
+        ] {
+            Condition::from_value(&value, false).expect_err("Should raise error");
+        }
+    }
+
+    #[test]
+    fn operands_should_be_serializable() {
+        let cond = condition(json!({ "StringLike": { "email": ["*@a.com", "*@b.com"] } }));
+        let serialized = serde_json::to_string(&cond).unwrap();
+        let cond: Condition = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(cond.matching(&json!({ "email": "x@b.com" })), true);
+        assert_eq!(cond.matching(&json!({ "email": "x@c.com" })), false);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::err::Error;
+use crate::policy::condition::string_matches::compile_regex;
+use crate::utils::glob_to_regex;
+use pcre2::bytes::Regex;
+use serde::de::Error as DeError;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Debug, Formatter};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PatternKind {
+    Glob,
+    Regex,
+}
+
+/// A pattern condition value, compiled once when the condition is built.
+///
+/// Patterns are serialized as their source (ex: in the compiled policy cache)
+/// and compiled again when deserialized.
+#[derive(Clone)]
+pub struct Pattern {
+    kind: PatternKind,
+    source: String,
+    regex: Regex,
+}
+
+impl Pattern {
+    /// Compiles a glob, matching whole values.
+    pub fn glob(glob: &str) -> Result<Self, Error> {
+        Ok(Pattern {
+            kind: PatternKind::Glob,
+            source: glob.to_string(),
+            regex: glob_to_regex::from_str_anchored(glob)?,
+        })
+    }
+
+    /// Compiles a regex, matching whole values (see `StringMatches`).
+    pub fn regex(pattern: &str) -> Result<Self, Error> {
+        Ok(Pattern {
+            kind: PatternKind::Regex,
+            source: pattern.to_string(),
+            regex: compile_regex(pattern)?,
+        })
+    }
+
+    fn compile(kind: PatternKind, source: &str) -> Result<Self, Error> {
+        match kind {
+            PatternKind::Glob => Self::glob(source),
+            PatternKind::Regex => Self::regex(source),
+        }
+    }
+
+    /// Whether the pattern matches the given value.
+    /// Values exceeding the regex engine limits are not matched.
+    pub fn is_match(&self, value: &str) -> bool {
+        self.regex.is_match(value.as_bytes()).unwrap_or(false)
+    }
+}
+
+impl Debug for Pattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}({:?})", self.kind, self.source)
+    }
+}
+
+impl Serialize for Pattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.kind, &self.source).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (kind, source) = <(PatternKind, String)>::deserialize(deserializer)?;
+        Self::compile(kind, &source).map_err(|e| D::Error::custom(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::policy::condition::pattern::Pattern;
+
+    #[test]
+    fn patterns_should_match_whole_values() {
+        let pattern = Pattern::glob("*@example.com").unwrap();
+        assert!(pattern.is_match("alice@example.com"));
+        assert!(!pattern.is_match("alice@example.com.evil"));
+
+        let pattern = Pattern::regex("[a-z]+-[0-9]{2}").unwrap();
+        assert!(pattern.is_match("build-42"));
+        assert!(!pattern.is_match("build-42\n"));
+        assert!(!pattern.is_match("a build-42"));
+    }
+
+    #[test]
+    fn patterns_should_be_compiled_when_deserialized() {
+        for pattern in [
+            Pattern::glob("Mozilla/*").unwrap(),
+            Pattern::regex("v[0-9]+").unwrap(),
+        ] {
+            let serialized = serde_json::to_string(&pattern).unwrap();
+            let deserialized: Pattern = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(deserialized.source, pattern.source);
+            assert_eq!(
+                deserialized.is_match("Mozilla/5.0"),
+                pattern.is_match("Mozilla/5.0")
+            );
+        }
+
+        serde_json::from_str::<Pattern>(r#"["Regex", "(a)\\1"]"#).expect_err("Should fail");
+    }
+}
@@ -0,0 +1,162 @@
+use crate::err::{Error, ErrorKind};
+use crate::policy::condition::flags::Flags;
+use crate::policy::condition::pattern::Pattern;
+use crate::policy::condition::Condition;
+use crate::policy::variables;
+use serde_json::{Map, Value};
+
+/// Parses the globs of a StringLike/StringNotLike condition.
+/// Policy variables are not supported in condition globs.
+fn make_globs(
+    operator: &str,
+    value: &Value,
+    flags: Flags,
+) -> Result<Vec<(String, Pattern)>, Error> {
+    let mut result = vec![];
+    for (field, comp) in value.as_object().ok_or_else(|| {
+        Error::new(
+            ErrorKind::UnwrapNoneValueError,
+            format!("Conditions.{} is not an object", operator),
+        )
+    })? {
+        let comp = comp.as_str().ok_or_else(|| {
+            Error::new(
+                ErrorKind::UnwrapNoneValueError,
+                format!("Conditions.{} value is not a string", operator),
+            )
+        })?;
+
+        if flags.intersects(Flags::Variables) && variables::has_variables(comp) {
+            return Err(Error::invalid_glob(
+                comp,
+                "policy variables are not supported in condition globs",
+            ));
+        }
+
+        result.push((field.clone(), Pattern::glob(comp)?));
+    }
+
+    Ok(result)
+}
+
+#[inline]
+pub(super) fn make_string_like(value: &Value, flags: Flags) -> Result<Vec<Condition>, Error> {
+    Ok(make_globs("StringLike", value, flags)?
+        .into_iter()
+        .map(|(field, pattern)| Condition::StringLike(field, pattern, flags - Flags::Variables))
+        .collect())
+}
+
+#[inline]
+pub(super) fn make_string_not_like(value: &Value, flags: Flags) -> Result<Vec<Condition>, Error> {
+    Ok(make_globs("StringNotLike", value, flags)?
+        .into_iter()
+        .map(|(field, pattern)| Condition::StringNotLike(field, pattern, flags - Flags::Variables))
+        .collect())
+}
+
+#[inline]
+pub(super) fn evaluate_string_like(value: &Map<String, Value>, key: &str, other: &Pattern) -> bool {
+    value
+        .get(key)
+        .map(|v| eval_value_string_like(v, other))
+        .unwrap_or(false)
+}
+
+#[inline]
+pub(super) fn eval_value_string_like(value: &Value, other: &Pattern) -> bool {
+    value.as_str().map(|v| other.is_match(v)).unwrap_or(false)
+}
+
+#[inline]
+pub(super) fn evaluate_string_not_like(
+    value: &Map<String, Value>,
+    key: &str,
+    other: &Pattern,
+) -> bool {
+    value
+        .get(key)
+        .map(|v| eval_value_string_not_like(v, other))
+        .unwrap_or(false)
+}
+
+#[inline]
+pub(super) fn eval_value_string_not_like(value: &Value, other: &Pattern) -> bool {
+    value.as_str().map(|v| !other.is_match(v)).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::err::ErrorKind;
+    use crate::policy::condition::flags::Flags;
+    use crate::policy::condition::pattern::Pattern;
+    use crate::policy::condition::string_like::{
+        eval_value_string_like, eval_value_string_not_like, make_string_like, make_string_not_like,
+    };
+    use crate::policy::condition::Condition;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn should_build_string_like_condition() {
+        let mut condition =
+            make_string_like(&json!({ "email": "*@example.com" }), Flags::None).unwrap();
+        assert_eq!(condition.len(), 1);
+
+        let cond = condition.pop().unwrap();
+        assert_eq!(
+            cond.matching(&json!({ "email": "alice@example.com" })),
+            true
+        );
+        assert_eq!(
+            cond.matching(&json!({ "email": "alice@example.org" })),
+            false
+        );
+        assert_eq!(cond.matching(&json!({})), false);
+
+        let cond = make_string_not_like(&json!({ "path": "/admin/**" }), Flags::None)
+            .unwrap()
+            .pop()
+            .unwrap();
+        assert_eq!(cond.matching(&json!({ "path": "/home/alice" })), true);
+        assert_eq!(cond.matching(&json!({ "path": "/admin/users" })), false);
+    }
+
+    #[test]
+    fn should_raise_err_if_malformed_object() {
+        make_string_like(&json!(""), Flags::None).expect_err("Should raise error");
+        make_string_like(&json!({ "FieldOne": 1 }), Flags::None).expect_err("Should raise error");
+
+        let err = make_string_like(&json!({ "FieldOne": "[a" }), Flags::None).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidGlobError);
+
+        let err =
+            make_string_like(&json!({ "FieldOne": "${owner}*" }), Flags::Variables).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidGlobError);
+    }
+
+    #[test]
+    fn should_return_false_if_value_is_not_a_string() {
+        let pattern = Pattern::glob("*").unwrap();
+        assert_eq!(eval_value_string_like(&Value::from(1), &pattern), false);
+        assert_eq!(eval_value_string_not_like(&Value::from(1), &pattern), false);
+    }
+
+    #[test]
+    fn should_support_flags() {
+        let conditions = Condition::from_value(
+            &json!({
+                "ForAnyValueStringLike": { "tags": "team-*" },
+                "ForAllValuesStringNotLike": { "groups": "guest*" },
+                "StringLikeIfExists": { "userAgent": "Mozilla/*" },
+            }),
+            false,
+        )
+        .unwrap();
+
+        let params = json!({ "tags": ["prod", "team-a"], "groups": ["staff", "dev"] });
+        assert!(conditions.iter().all(|c| c.matching(&params)));
+
+        let params = json!({ "tags": ["prod"], "groups": ["guest-1"], "userAgent": "curl/7.0" });
+        assert!(conditions.iter().all(|c| !c.matching(&params)));
+    }
+}
@@ -0,0 +1,264 @@
+use crate::err::{Error, ErrorKind};
+use crate::policy::condition::flags::Flags;
+use crate::policy::condition::pattern::Pattern;
+use crate::policy::condition::Condition;
+use pcre2::bytes::{Regex, RegexBuilder};
+use serde_json::{Map, Value};
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Maximum length of a StringMatches regex.
+const MAX_REGEX_LENGTH: usize = 1024;
+
+/// Checks the group construct following an opening parenthesis.
+/// Only capturing, non-capturing, named, atomic and comment groups are allowed,
+/// as well as inline options (ex: "(?i)").
+fn check_group(pattern: &str, chars: &mut Peekable<Chars>) -> Result<(), Error> {
+    let unsupported = || {
+        Error::invalid_regex(
+            pattern,
+            "lookarounds, backreferences, recursion, callouts and verbs are not supported",
+        )
+    };
+
+    match chars.peek() {
+        Some('*') => return Err(unsupported()),
+        Some('?') => chars.next(),
+        _ => return Ok(()),
+    };
+
+    match chars.next() {
+        Some(':' | '>' | '|' | '\'') => Ok(()),
+        Some('#') => {
+            for c in chars.by_ref() {
+                if c == ')' {
+                    return Ok(());
+                }
+            }
+
+            Ok(())
+        }
+        Some('<') => match chars.peek() {
+            Some(c) if c.is_alphabetic() || *c == '_' => Ok(()),
+            _ => Err(unsupported()),
+        },
+        Some('P') => match chars.next() {
+            Some('<') => Ok(()),
+            _ => Err(unsupported()),
+        },
+        Some('i' | 'm' | 's' | 'x' | '-') => {
+            for c in chars.by_ref() {
+                match c {
+                    'i' | 'm' | 's' | 'x' | '-' => {}
+                    ':' | ')' => return Ok(()),
+                    _ => break,
+                }
+            }
+
+            Err(unsupported())
+        }
+        _ => Err(unsupported()),
+    }
+}
+
+/// Validates a regex against the safe dialect supported by StringMatches.
+///
+/// The dialect is the PCRE syntax without the constructs which could make
+/// the matching time explode or depend on the rest of the subject:
+/// lookarounds, backreferences, subroutine calls and recursion, callouts,
+/// backtracking control verbs and the single code unit escape.
+fn validate(pattern: &str) -> Result<(), Error> {
+    if pattern.len() > MAX_REGEX_LENGTH {
+        return Err(Error::invalid_regex(
+            pattern,
+            format!("regex exceeds {} characters", MAX_REGEX_LENGTH),
+        ));
+    }
+
+    let mut chars = pattern.chars().peekable();
+    let mut in_class = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('Q') => {
+                    // Quoted sequence, up to \E.
+                    while let Some(c) = chars.next() {
+                        if c == '\\' && chars.peek() == Some(&'E') {
+                            chars.next();
+                            break;
+                        }
+                    }
+                }
+                Some(c) if !in_class && (matches!(c, 'g' | 'k') || ('1'..='9').contains(&c)) => {
+                    return Err(Error::invalid_regex(
+                        pattern,
+                        "backreferences and subroutine calls are not supported",
+                    ));
+                }
+                Some('C') => {
+                    return Err(Error::invalid_regex(
+                        pattern,
+                        "single code unit escape is not supported",
+                    ));
+                }
+                _ => {}
+            },
+            // POSIX class (ex: "[:alpha:]").
+            '[' if in_class && chars.peek() == Some(&':') => {
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                }
+            }
+            '[' if in_class => {}
+            '[' => {
+                in_class = true;
+                if chars.peek() == Some(&'^') {
+                    chars.next();
+                }
+
+                // A leading closing bracket is part of the class.
+                if chars.peek() == Some(&']') {
+                    chars.next();
+                }
+            }
+            ']' if in_class => in_class = false,
+            '(' if !in_class => check_group(pattern, &mut chars)?,
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Validates and compiles a StringMatches regex, matching whole values.
+pub(super) fn compile_regex(pattern: &str) -> Result<Regex, Error> {
+    validate(pattern)?;
+
+    RegexBuilder::new()
+        .utf(true)
+        .jit_if_available(true)
+        .build(format!(r"^(?:{})\z", pattern).as_str())
+        .map_err(|e| Error::invalid_regex(pattern, e))
+}
+
+#[inline]
+pub(super) fn make_string_matches(value: &Value, flags: Flags) -> Result<Vec<Condition>, Error> {
+    let mut result = vec![];
+    for (field, comp) in value.as_object().ok_or_else(|| {
+        Error::new(
+            ErrorKind::UnwrapNoneValueError,
+            "Conditions.StringMatches is not an object",
+        )
+    })? {
+        let comp = comp.as_str().ok_or_else(|| {
+            Error::new(
+                ErrorKind::UnwrapNoneValueError,
+                "Conditions.StringMatches value is not a string",
+            )
+        })?;
+
+        result.push(Condition::StringMatches(
+            field.clone(),
+            Pattern::regex(comp)?,
+            flags,
+        ));
+    }
+
+    Ok(result)
+}
+
+#[inline]
+pub(super) fn evaluate_string_matches(
+    value: &Map<String, Value>,
+    key: &str,
+    other: &Pattern,
+) -> bool {
+    value
+        .get(key)
+        .map(|v| eval_value_string_matches(v, other))
+        .unwrap_or(false)
+}
+
+#[inline]
+pub(super) fn eval_value_string_matches(value: &Value, other: &Pattern) -> bool {
+    value.as_str().map(|v| other.is_match(v)).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::err::ErrorKind;
+    use crate::policy::condition::flags::Flags;
+    use crate::policy::condition::string_matches::{compile_regex, make_string_matches};
+    use serde_json::json;
+
+    #[test]
+    fn should_build_string_matches_condition() {
+        let mut condition = make_string_matches(
+            &json!({ "userAgent": r"(?i)mozilla/[0-9.]+ \(.*\)" }),
+            Flags::None,
+        )
+        .unwrap();
+        assert_eq!(condition.len(), 1);
+
+        let cond = condition.pop().unwrap();
+        assert_eq!(
+            cond.matching(&json!({ "userAgent": "Mozilla/5.0 (X11; Linux x86_64)" })),
+            true
+        );
+        assert_eq!(cond.matching(&json!({ "userAgent": "curl/7.68.0" })), false);
+        assert_eq!(cond.matching(&json!({ "userAgent": 5 })), false);
+    }
+
+    #[test]
+    fn should_raise_err_if_malformed_object() {
+        make_string_matches(&json!(""), Flags::None).expect_err("Should raise error");
+        make_string_matches(&json!({ "FieldOne": [] }), Flags::None)
+            .expect_err("Should raise error");
+    }
+
+    #[test]
+    fn should_accept_safe_regexes() {
+        for pattern in [
+            r"[a-z]+",
+            r"(?:v|version)-(?<major>[0-9]+)\.(?P<minor>[0-9]+)",
+            r"[[:alpha:]](?i:abc)(?#comment)",
+            r"[]()\\1]+",
+            r"\Q(?=\1\E.*",
+            r"a(?>b+)c|\d{2,4}",
+        ] {
+            compile_regex(pattern).expect(pattern);
+        }
+    }
+
+    #[test]
+    fn should_reject_unsafe_regexes() {
+        for pattern in [
+            r"(a)\1",
+            r"(?<n>a)\k<n>",
+            r"(a)\g{1}",
+            r"a(?=b)",
+            r"a(?!b)",
+            r"(?<=a)b",
+            r"(?<!a)b",
+            r"(a|(?R))",
+            r"(a)(?1)",
+            r"(?P=n)",
+            r"(?C1)a",
+            r"(*ACCEPT)a",
+            r"\C",
+            r"[a-",
+        ] {
+            assert_eq!(
+                compile_regex(pattern).unwrap_err().kind(),
+                ErrorKind::InvalidRegexError,
+                "{}",
+                pattern
+            );
+        }
+
+        let long = "a".repeat(1025);
+        compile_regex(&long).expect_err("Should raise error");
+    }
+}
@@ -1,6 +1,6 @@
 use crate::err::{Error, ErrorKind};
 use crate::policy::condition::flags::Flags;
-use crate::policy::condition::Condition;
+use crate::policy::condition::{variable_flags, Condition};
 use serde_json::{Map, Value};
 use std::cmp::Ordering;
 
@@ -24,7 +24,9 @@ pub(super) fn make_string_not_equals(
             )
         })?;
 
-        let comp = if case_sensitive {
+        // Values with variables are lowercased once resolved.
+        let flags = variable_flags(comp, flags);
+        let comp = if case_sensitive || flags.intersects(Flags::Variables) {
             comp.to_string()
         } else {
             comp.to_string().to_lowercase()
@@ -0,0 +1,369 @@
+use crate::err::{Error, ErrorKind};
+use crate::policy::condition::flags::Flags;
+use crate::policy::condition::Condition;
+use chrono::{DateTime, Datelike, NaiveTime, Timelike, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// (De)serializes time zones by their IANA name.
+mod time_zone {
+    use chrono_tz::Tz;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(tz: &Tz, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(tz.name())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Tz, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+/// A daily time range, in the given time zone (ex: 08:00 to 18:00 Europe/Rome).
+/// Ranges whose end precedes the start span midnight (ex: 22:00 to 06:00).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TimeOfDayRange {
+    /// Seconds from midnight, inclusive.
+    from: u32,
+    /// Seconds from midnight, exclusive.
+    to: u32,
+    #[serde(with = "time_zone")]
+    time_zone: Tz,
+}
+
+impl TimeOfDayRange {
+    fn contains(&self, date: &DateTime<Utc>) -> bool {
+        let time = date
+            .with_timezone(&self.time_zone)
+            .num_seconds_from_midnight();
+        if self.from < self.to {
+            self.from <= time && time < self.to
+        } else {
+            self.from <= time || time < self.to
+        }
+    }
+}
+
+/// A set of weekdays, in the given time zone.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Weekdays {
+    /// Bit set of the days, indexed from monday.
+    days: u8,
+    #[serde(with = "time_zone")]
+    time_zone: Tz,
+}
+
+impl Weekdays {
+    fn contains(&self, date: &DateTime<Utc>) -> bool {
+        let day = date.with_timezone(&self.time_zone).weekday();
+        self.days & (1 << day.num_days_from_monday()) != 0
+    }
+}
+
+fn error(operator: &str, message: &str) -> Error {
+    Error::new(
+        ErrorKind::UnwrapNoneValueError,
+        format!("Conditions.{} {}", operator, message),
+    )
+}
+
+/// Gets the fields of a time operand object, checking that no unknown field is present.
+fn operand_fields<'a>(
+    operator: &str,
+    value: &'a Value,
+    fields: &[&str],
+) -> Result<&'a Map<String, Value>, Error> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| error(operator, "value is not an object"))?;
+    if let Some(field) = object.keys().find(|k| !fields.contains(&k.as_str())) {
+        return Err(error(
+            operator,
+            &format!(r#"has an unknown field "{}""#, field),
+        ));
+    }
+
+    Ok(object)
+}
+
+fn parse_time_zone(operator: &str, object: &Map<String, Value>) -> Result<Tz, Error> {
+    match object.get("TimeZone") {
+        Option::None => Ok(Tz::UTC),
+        Some(tz) => tz
+            .as_str()
+            .and_then(|tz| tz.parse().ok())
+            .ok_or_else(|| error(operator, "TimeZone is not a valid IANA time zone")),
+    }
+}
+
+fn parse_time(operator: &str, object: &Map<String, Value>, field: &str) -> Result<u32, Error> {
+    object
+        .get(field)
+        .and_then(Value::as_str)
+        .and_then(|s| {
+            NaiveTime::parse_from_str(s, "%H:%M:%S")
+                .or_else(|_| NaiveTime::parse_from_str(s, "%H:%M"))
+                .ok()
+        })
+        .map(|t| t.num_seconds_from_midnight())
+        .ok_or_else(|| error(operator, &format!("{} is not a valid time (HH:MM)", field)))
+}
+
+/// Parses a day name (ex: "Mon", "monday") or a range of days (ex: "Mon-Fri", "Sat-Mon").
+fn parse_days(operator: &str, days: &str) -> Result<u8, Error> {
+    let parse_day = |day: &str| {
+        day.trim()
+            .parse::<Weekday>()
+            .map_err(|_| error(operator, &format!(r#"has an invalid day "{}""#, day)))
+    };
+
+    let (first, last) = match days.split_once('-') {
+        Some((first, last)) => (parse_day(first)?, parse_day(last)?),
+        Option::None => {
+            let day = parse_day(days)?;
+            (day, day)
+        }
+    };
+
+    let mut result = 0;
+    let mut day = first;
+    loop {
+        result |= 1 << day.num_days_from_monday();
+        if day == last {
+            return Ok(result);
+        }
+
+        day = day.succ();
+    }
+}
+
+#[inline]
+pub(super) fn make_time_of_day(value: &Value, flags: Flags) -> Result<Vec<Condition>, Error> {
+    let mut result = vec![];
+    for (field, comp) in value
+        .as_object()
+        .ok_or_else(|| error("TimeOfDay", "is not an object"))?
+    {
+        let object = operand_fields("TimeOfDay", comp, &["From", "To", "TimeZone"])?;
+        let from = parse_time("TimeOfDay", object, "From")?;
+        let to = parse_time("TimeOfDay", object, "To")?;
+        if from == to {
+            return Err(error("TimeOfDay", "range cannot be empty"));
+        }
+
+        result.push(Condition::TimeOfDay(
+            field.clone(),
+            TimeOfDayRange {
+                from,
+                to,
+                time_zone: parse_time_zone("TimeOfDay", object)?,
+            },
+            flags,
+        ));
+    }
+
+    Ok(result)
+}
+
+#[inline]
+pub(super) fn make_weekday(value: &Value, flags: Flags) -> Result<Vec<Condition>, Error> {
+    let mut result = vec![];
+    for (field, comp) in value
+        .as_object()
+        .ok_or_else(|| error("Weekday", "is not an object"))?
+    {
+        let object = operand_fields("Weekday", comp, &["Days", "TimeZone"])?;
+        let days = object
+            .get("Days")
+            .and_then(Value::as_array)
+            .filter(|days| !days.is_empty())
+            .ok_or_else(|| error("Weekday", "Days is not a non-empty array"))?
+            .iter()
+            .map(|days| {
+                days.as_str()
+                    .ok_or_else(|| error("Weekday", "Days value is not a string"))
+                    .and_then(|days| parse_days("Weekday", days))
+            })
+            .collect::<Result<Vec<u8>, Error>>()?
+            .into_iter()
+            .fold(0, |all, days| all | days);
+
+        result.push(Condition::Weekday(
+            field.clone(),
+            Weekdays {
+                days,
+                time_zone: parse_time_zone("Weekday", object)?,
+            },
+            flags,
+        ));
+    }
+
+    Ok(result)
+}
+
+#[inline]
+fn value_to_date(value: &Value) -> Option<DateTime<Utc>> {
+    value
+        .as_str()
+        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        .map(|d| d.with_timezone(&Utc))
+}
+
+#[inline]
+pub(super) fn evaluate_time_of_day(
+    value: &Map<String, Value>,
+    key: &str,
+    other: &TimeOfDayRange,
+) -> bool {
+    value
+        .get(key)
+        .map(|v| eval_value_time_of_day(v, other))
+        .unwrap_or(false)
+}
+
+#[inline]
+pub(super) fn eval_value_time_of_day(value: &Value, other: &TimeOfDayRange) -> bool {
+    value_to_date(value)
+        .map(|d| other.contains(&d))
+        .unwrap_or(false)
+}
+
+#[inline]
+pub(super) fn evaluate_weekday(value: &Map<String, Value>, key: &str, other: &Weekdays) -> bool {
+    value
+        .get(key)
+        .map(|v| eval_value_weekday(v, other))
+        .unwrap_or(false)
+}
+
+#[inline]
+pub(super) fn eval_value_weekday(value: &Value, other: &Weekdays) -> bool {
+    value_to_date(value)
+        .map(|d| other.contains(&d))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::policy::condition::flags::Flags;
+    use crate::policy::condition::time_compare::{make_time_of_day, make_weekday};
+    use crate::policy::condition::Condition;
+    use serde_json::json;
+
+    fn at(time: &str) -> serde_json::Value {
+        json!({ "zephir:current_time": time })
+    }
+
+    #[test]
+    fn should_build_time_of_day_condition() {
+        let mut condition = make_time_of_day(
+            &json!({
+                "zephir:current_time": { "From": "08:00", "To": "18:00", "TimeZone": "Europe/Rome" },
+            }),
+            Flags::None,
+        )
+        .unwrap();
+        assert_eq!(condition.len(), 1);
+
+        // Europe/Rome is UTC+1 in winter, UTC+2 in summer.
+        let cond = condition.pop().unwrap();
+        assert_eq!(cond.matching(&at("2022-01-10T07:00:00Z")), true);
+        assert_eq!(cond.matching(&at("2022-01-10T06:59:59Z")), false);
+        assert_eq!(cond.matching(&at("2022-07-11T16:30:00Z")), false);
+        assert_eq!(cond.matching(&at("2022-07-11T15:59:00Z")), true);
+        assert_eq!(cond.matching(&at("not a date")), false);
+    }
+
+    #[test]
+    fn time_of_day_ranges_should_span_midnight() {
+        let cond = make_time_of_day(
+            &json!({ "zephir:current_time": { "From": "22:00", "To": "06:30" } }),
+            Flags::None,
+        )
+        .unwrap()
+        .pop()
+        .unwrap();
+
+        assert_eq!(cond.matching(&at("2022-01-10T23:00:00Z")), true);
+        assert_eq!(cond.matching(&at("2022-01-10T06:15:00Z")), true);
+        assert_eq!(cond.matching(&at("2022-01-10T12:00:00Z")), false);
+    }
+
+    #[test]
+    fn should_build_weekday_condition() {
+        let cond = make_weekday(
+            &json!({
+                "zephir:current_time": { "Days": ["Mon-Fri"], "TimeZone": "Asia/Tokyo" },
+            }),
+            Flags::None,
+        )
+        .unwrap()
+        .pop()
+        .unwrap();
+
+        // 2022-01-09 is a sunday, already monday in Tokyo at 16:00 UTC.
+        assert_eq!(cond.matching(&at("2022-01-09T16:00:00Z")), true);
+        assert_eq!(cond.matching(&at("2022-01-09T14:00:00Z")), false);
+
+        let cond = make_weekday(
+            &json!({ "zephir:current_time": { "Days": ["Sat-Sun", "wednesday"] } }),
+            Flags::None,
+        )
+        .unwrap()
+        .pop()
+        .unwrap();
+        assert_eq!(cond.matching(&at("2022-01-09T14:00:00Z")), true);
+        assert_eq!(cond.matching(&at("2022-01-12T14:00:00Z")), true);
+        assert_eq!(cond.matching(&at("2022-01-13T14:00:00Z")), false);
+    }
+
+    #[test]
+    fn should_raise_err_if_malformed_operand() {
+        for value in [
+            json!({ "key": "08:00-18:00" }),
+            json!({ "key": { "From": "08:00" } }),
+            json!({ "key": { "From": "8am", "To": "18:00" } }),
+            json!({ "key": { "From": "08:00", "To": "08:00" } }),
+            json!({ "key": { "From": "08:00", "To": "18:00", "TimeZone": "Mars/Olympus" } }),
+            json!({ "key": { "From": "08:00", "To": "18:00", "Days": ["Mon"] } }),
+        ] {
+            make_time_of_day(&value, Flags::None).expect_err("Should raise error");
+        }
+
+        for value in [
+            json!({ "key": { "Days": [] } }),
+            json!({ "key": { "Days": ["Mon-Funday"] } }),
+            json!({ "key": { "Days": "Mon" } }),
+        ] {
+            make_weekday(&value, Flags::None).expect_err("Should raise error");
+        }
+    }
+
+    #[test]
+    fn time_conditions_should_support_flags_and_serialization() {
+        let conditions = Condition::from_value(
+            &json!({
+                "ForAllValuesWeekday": { "dates": { "Days": ["Mon-Fri"] } },
+                "TimeOfDayIfExists": { "login_time": { "From": "09:00", "To": "17:00" } },
+            }),
+            false,
+        )
+        .unwrap();
+
+        let serialized = serde_json::to_string(&conditions).unwrap();
+        let conditions: Vec<Condition> = serde_json::from_str(&serialized).unwrap();
+
+        let params = json!({ "dates": ["2022-01-10T10:00:00Z", "2022-01-14T10:00:00Z"] });
+        assert!(conditions.iter().all(|c| c.matching(&params)));
+
+        let params = json!({
+            "dates": ["2022-01-10T10:00:00Z", "2022-01-15T10:00:00Z"],
+            "login_time": "2022-01-10T20:00:00Z",
+        });
+        assert!(conditions.iter().all(|c| !c.matching(&params)));
+    }
+}
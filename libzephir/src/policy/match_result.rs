@@ -1,5 +1,5 @@
 use crate::policy::policy::{MatchablePolicy, PartialPolicy};
-use crate::policy::PolicyVersion;
+use crate::policy::statement::PolicyStatement;
 use serde_json::Value;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -24,6 +24,9 @@ pub struct MatchResult {
     action_matches: Option<bool>,
     resource_matches: Option<bool>,
     conditions_match: Option<bool>,
+
+    /// The conditions which could not be decided, if known.
+    residual_conditions: Value,
 }
 
 impl Default for MatchResult {
@@ -48,6 +51,7 @@ impl MatchResult {
             action_matches: None,
             resource_matches: None,
             conditions_match: None,
+            residual_conditions: Value::Null,
         }
     }
 
@@ -66,6 +70,29 @@ impl MatchResult {
         self.conditions_match = Option::Some(result);
     }
 
+    /// Sets the conditions left undecided by a partial evaluation.
+    pub(super) fn update_residual_conditions(&mut self, conditions: Value) {
+        self.residual_conditions = conditions;
+    }
+
+    /// Gets the action match flag.
+    /// None if the action has not been evaluated.
+    pub fn action_matches(&self) -> Option<bool> {
+        self.action_matches
+    }
+
+    /// Gets the resource match flag.
+    /// None if the resource has not been evaluated.
+    pub fn resource_matches(&self) -> Option<bool> {
+        self.resource_matches
+    }
+
+    /// Gets the conditions match flag.
+    /// None if the conditions have not been evaluated.
+    pub fn conditions_match(&self) -> Option<bool> {
+        self.conditions_match
+    }
+
     /// Gets the partial policy.
     /// Has meaning only if result type is not full and outcome is "match"
     pub fn get_partial(self) -> PartialPolicy {
@@ -84,7 +111,7 @@ impl MatchResult {
     }
 
     /// Internal: updates the result
-    pub(super) fn _update(&mut self, policy: &impl MatchablePolicy) {
+    pub(super) fn _update(&mut self, policy: &PolicyStatement) {
         self.partial.reset();
         self.partial.effect = policy.get_effect();
 
@@ -112,20 +139,38 @@ impl MatchResult {
             self.result_type = ResultType::Full;
         } else {
             self.partial = PartialPolicy {
-                version: PolicyVersion::Version1,
+                sid: policy.sid.clone(),
+                version: policy.version.clone(),
                 effect: self.partial.effect,
-                actions: if self.action_matches.is_some() {
+                actions: if self.action_matches.is_some() || policy.targets().actions_negated() {
                     None
                 } else {
                     Option::Some(policy.get_actions().to_vec())
                 },
-                resources: if self.resource_matches.is_some() {
+                not_actions: if self.action_matches.is_some() || !policy.targets().actions_negated()
+                {
+                    None
+                } else {
+                    Option::Some(policy.get_not_actions().to_vec())
+                },
+                resources: if self.resource_matches.is_some()
+                    || policy.targets().resources_negated()
+                {
                     None
                 } else {
                     Option::Some(policy.get_resources().to_vec())
                 },
+                not_resources: if self.resource_matches.is_some()
+                    || !policy.targets().resources_negated()
+                {
+                    None
+                } else {
+                    Option::Some(policy.get_not_resources().to_vec())
+                },
                 conditions: if self.conditions_match.is_some() {
                     Value::Null
+                } else if !self.residual_conditions.is_null() {
+                    self.residual_conditions.clone()
                 } else {
                     policy.get_conditions().clone()
                 },
@@ -160,19 +205,19 @@ mod tests {
 
         let mut mr = MatchResult::new();
         mr.update_action(false);
-        mr._update(&policy);
+        mr._update(&policy.statements()[0]);
         assert_eq!(mr.is_full(), true);
         assert_eq!(mr.is_match(), false);
 
         let mut mr = MatchResult::new();
         mr.update_resource(false);
-        mr._update(&policy);
+        mr._update(&policy.statements()[0]);
         assert_eq!(mr.is_full(), true);
         assert_eq!(mr.is_match(), false);
 
         let mut mr = MatchResult::new();
         mr.update_conditions(false);
-        mr._update(&policy);
+        mr._update(&policy.statements()[0]);
         assert_eq!(mr.is_full(), true);
         assert_eq!(mr.is_match(), false);
     }
@@ -189,7 +234,7 @@ mod tests {
 
         let mut mr = MatchResult::new();
         mr.update_resource(true);
-        mr._update(&policy);
+        mr._update(&policy.statements()[0]);
 
         assert_eq!(mr.is_full(), false);
         assert_eq!(mr.is_match(), true);
@@ -216,7 +261,7 @@ mod tests {
 
         let mut mr = MatchResult::new();
         mr.update_action(true);
-        mr._update(&policy);
+        mr._update(&policy.statements()[0]);
 
         assert_eq!(mr.is_full(), false);
         assert_eq!(mr.is_match(), true);
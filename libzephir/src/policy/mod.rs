@@ -8,6 +8,9 @@ pub(crate) mod condition;
 pub mod match_result;
 pub mod policy;
 pub mod policy_set;
+pub mod sql_filter;
+pub mod statement;
+pub mod variables;
 
 /// Get a new policy object
 pub fn policy_new<A, R>(
@@ -27,7 +30,10 @@ where
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum PolicyVersion {
+    /// Single statement policies, with legacy (unanchored) glob matching.
     Version1 = 1,
+    /// Multi-statement policies, with strict (anchored) glob matching.
+    Version2 = 2,
 }
 
 impl TryFrom<i32> for PolicyVersion {
@@ -36,6 +42,7 @@ impl TryFrom<i32> for PolicyVersion {
     fn try_from(value: i32) -> Result<Self, Self::Error> {
         match value {
             1 => Ok(PolicyVersion::Version1),
+            2 => Ok(PolicyVersion::Version2),
             _ => Err(Error::unknown_policy_version(value)),
         }
     }
@@ -45,6 +52,7 @@ impl From<&PolicyVersion> for i32 {
     fn from(value: &PolicyVersion) -> Self {
         match value {
             PolicyVersion::Version1 => 1,
+            PolicyVersion::Version2 => 2,
         }
     }
 }
@@ -122,6 +130,7 @@ mod tests {
     fn number_can_be_converted_to_policy_version() {
         use std::convert::TryFrom;
         assert_eq!(PolicyVersion::try_from(1).unwrap(), PolicyVersion::Version1);
+        assert_eq!(PolicyVersion::try_from(2).unwrap(), PolicyVersion::Version2);
     }
 
     #[test]
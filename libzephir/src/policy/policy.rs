@@ -1,9 +1,7 @@
-use crate::compiler::compiled_policy::CompiledPolicy;
-use crate::compiler::compiler::Compiler;
 use crate::err::Error;
 use crate::identity::role::AllowedRequest;
-use crate::policy::condition::Condition;
 use crate::policy::match_result::MatchResult;
+use crate::policy::statement::{PolicyStatement, PolicyTargets, MAX_STATEMENTS};
 use crate::policy::{PolicyEffect, PolicyVersion};
 use serde_json::{Map, Value};
 use std::fmt::{Debug, Display};
@@ -46,8 +44,9 @@ pub trait Policy: ToJson + Eq + Hash {
 }
 
 /// Represents a policy that can be matched against
-/// action and resource identifiers
-pub trait MatchablePolicy: Policy {
+/// action and resource identifiers.
+/// Policy statements are matchable, without being stored policies themselves.
+pub trait MatchablePolicy: ToJson {
     /// Gets the policy effect
     fn get_effect(&self) -> PolicyEffect;
 
@@ -57,12 +56,26 @@ pub trait MatchablePolicy: Policy {
         T: ToString + Display,
         S: ToString + Display + Debug;
 
+    /// Calculate if this policy is matching, treating the condition keys missing
+    /// from the request params as unknown: the conditions which cannot be decided
+    /// are returned as residual conditions of the partial policy.
+    fn matching_partial<T, S>(&self, request: &AllowedRequest<T, S>) -> MatchResult
+    where
+        T: ToString + Display,
+        S: ToString + Display + Debug;
+
     /// Gets the action of the policy.
     fn get_actions(&self) -> &[String];
 
+    /// Gets the negated actions of the policy.
+    fn get_not_actions(&self) -> &[String];
+
     /// Gets the resources of the policy.
     fn get_resources(&self) -> &[String];
 
+    /// Gets the negated resources of the policy.
+    fn get_not_resources(&self) -> &[String];
+
     /// Gets the policy conditions.
     fn get_conditions(&self) -> &Value;
 }
@@ -71,10 +84,13 @@ pub trait MatchablePolicy: Policy {
 /// Actions and Resources can be optional
 #[derive(Clone, Debug)]
 pub struct PartialPolicy {
+    pub sid: Option<String>,
     pub version: PolicyVersion,
     pub effect: PolicyEffect,
     pub actions: Option<Vec<String>>,
+    pub not_actions: Option<Vec<String>>,
     pub resources: Option<Vec<String>>,
+    pub not_resources: Option<Vec<String>>,
     pub conditions: Value,
 }
 
@@ -88,19 +104,25 @@ impl PartialPolicy {
     /// Default partial policy.
     pub fn default() -> PartialPolicy {
         PartialPolicy {
+            sid: Option::None,
             version: PolicyVersion::Version1,
             effect: PolicyEffect::Allow,
             actions: Option::None,
+            not_actions: Option::None,
             resources: Option::None,
+            not_resources: Option::None,
             conditions: Value::Null,
         }
     }
 
     /// Resets the partial policy.
     pub fn reset(&mut self) {
+        self.sid = Option::None;
         self.version = PolicyVersion::Version1;
         self.actions = Option::None;
+        self.not_actions = Option::None;
         self.resources = Option::None;
+        self.not_resources = Option::None;
         self.conditions = Value::Null;
     }
 }
@@ -114,6 +136,10 @@ impl From<PartialPolicy> for Value {
 impl ToJson for PartialPolicy {
     fn to_json(&self) -> Map<String, Value> {
         let mut result = Map::new();
+        if let Some(sid) = &self.sid {
+            result.insert(String::from("sid"), Value::from(sid.as_str()));
+        }
+
         result.insert(String::from("version"), Value::from(&self.version));
         result.insert(String::from("effect"), Value::from(&self.effect));
 
@@ -121,10 +147,24 @@ impl ToJson for PartialPolicy {
             result.insert(String::from("actions"), Value::from(actions.as_slice()));
         }
 
+        if let Some(not_actions) = &self.not_actions {
+            result.insert(
+                String::from("not_actions"),
+                Value::from(not_actions.as_slice()),
+            );
+        }
+
         if let Some(resources) = &self.resources {
             result.insert(String::from("resources"), Value::from(resources.as_slice()));
         }
 
+        if let Some(not_resources) = &self.not_resources {
+            result.insert(
+                String::from("not_resources"),
+                Value::from(not_resources.as_slice()),
+            );
+        }
+
         result.insert(String::from("conditions"), self.conditions.clone());
 
         result
@@ -155,16 +195,11 @@ impl Hash for PartialPolicy {
 pub struct CompletePolicy {
     pub id: String,
     pub version: PolicyVersion,
-    pub effect: PolicyEffect,
-    actions: Vec<String>,
-    resources: Vec<String>,
-    conditions: Value,
-
-    compiled_policy: CompiledPolicy,
+    statements: Vec<PolicyStatement>,
 }
 
 impl CompletePolicy {
-    /// Get a new policy object
+    /// Get a new policy object, made of a single statement
     pub fn new<A, R>(
         id: String,
         version: PolicyVersion,
@@ -177,34 +212,117 @@ impl CompletePolicy {
         A: ToString,
         R: ToString,
     {
-        if actions.is_empty() {
-            return Err(Error::actions_cannot_be_empty());
-        }
-
-        let resources = if resources.is_empty() {
-            vec!["*".to_string()]
-        } else {
-            resources.into_iter().map(|s| s.to_string()).collect()
-        };
+        Self::with_targets(
+            id,
+            version,
+            effect,
+            PolicyTargets::new(actions, resources),
+            conditions,
+        )
+    }
 
-        let actions: Vec<String> = actions.into_iter().map(|s| s.to_string()).collect();
-        let compiled_policy = Compiler::get_instance().compile(
+    /// Get a new single statement policy object, with possibly negated actions or resources.
+    /// Each dimension must have either a positive or a negated list, not both.
+    pub fn with_targets(
+        id: String,
+        version: PolicyVersion,
+        effect: PolicyEffect,
+        targets: PolicyTargets,
+        conditions: Value,
+    ) -> Result<CompletePolicy, Error> {
+        let statement = PolicyStatement::new(
             &id,
-            &actions,
-            &resources,
-            Condition::from_value(&conditions)?,
-        );
+            0,
+            version.clone(),
+            Option::None,
+            effect,
+            targets,
+            conditions,
+        )?;
 
         Ok(CompletePolicy {
             id,
             version,
-            effect,
-            actions,
-            resources,
-            conditions,
-            compiled_policy,
+            statements: vec![statement],
+        })
+    }
+
+    /// Get a new version 2 policy object from the JSON list of its statements.
+    /// Statement sids, if present, must be unique.
+    pub fn from_statements(id: String, statements: &Value) -> Result<CompletePolicy, Error> {
+        let values = statements
+            .as_array()
+            .filter(|s| !s.is_empty() && s.len() <= MAX_STATEMENTS)
+            .ok_or_else(|| {
+                Error::from(format!(
+                    "Statements must be a list of 1 to {} statements",
+                    MAX_STATEMENTS
+                ))
+            })?;
+
+        let mut statements: Vec<PolicyStatement> = vec![];
+        for (index, value) in values.iter().enumerate() {
+            let statement = PolicyStatement::from_value(&id, index, value)?;
+            if let Some(sid) = &statement.sid {
+                if statements.iter().any(|s| s.sid.as_ref() == Some(sid)) {
+                    return Err(Error::from(format!(
+                        r#"Duplicated statement sid "{}""#,
+                        sid
+                    )));
+                }
+            }
+
+            statements.push(statement);
+        }
+
+        Ok(CompletePolicy {
+            id,
+            version: PolicyVersion::Version2,
+            statements,
         })
     }
+
+    /// Gets the statements of the policy.
+    /// Version 1 policies always have a single statement.
+    pub fn statements(&self) -> &[PolicyStatement] {
+        self.statements.as_slice()
+    }
+
+    /// Gets the result of the statement deciding the match (see `MatchablePolicy`).
+    fn decide<F>(&self, matching: F) -> MatchResult
+    where
+        F: Fn(&PolicyStatement) -> MatchResult,
+    {
+        let rank = |statement: &PolicyStatement, result: &MatchResult| match (
+            result.is_match(),
+            result.is_full(),
+            statement.effect,
+        ) {
+            (false, _, _) => 0,
+            (true, false, _) => 1,
+            (true, true, PolicyEffect::Allow) => 2,
+            (true, true, PolicyEffect::Deny) => 3,
+        };
+
+        let mut decided: Option<(MatchResult, i32)> = Option::None;
+        for statement in &self.statements {
+            let result = matching(statement);
+            let result_rank = rank(statement, &result);
+            if decided.as_ref().is_none_or(|(_, r)| result_rank > *r) {
+                decided = Some((result, result_rank));
+            }
+        }
+
+        decided.map(|(result, _)| result).unwrap()
+    }
+
+    /// Whether at least one statement could match the given action and resource,
+    /// regardless of their conditions (see `PolicyStatement::may_match`).
+    pub fn may_match(&self, action: &str, resource: Option<&str>) -> bool {
+        self.statements
+            .iter()
+            .any(|s| s.may_match(action, resource))
+    }
 }
 
 impl Policy for CompletePolicy {
@@ -239,30 +357,63 @@ impl ToJson for CompletePolicy {
         let mut result = Map::new();
         result.insert(String::from("id"), Value::from(self.id.as_str()));
         result.insert(String::from("version"), Value::from(&self.version));
+
+        if self.version != PolicyVersion::Version1 {
+            result.insert(
+                String::from("statements"),
+                Value::from(
+                    self.statements
+                        .iter()
+                        .map(|s| s.to_value())
+                        .collect::<Vec<Value>>(),
+                ),
+            );
+
+            return result;
+        }
+
+        let statement = &self.statements[0];
+        result.insert(String::from("effect"), Value::from(&statement.effect));
+        if statement.get_not_actions().is_empty() {
+            result.insert(
+                String::from("actions"),
+                Value::from(statement.get_actions()),
+            );
+        } else {
+            result.insert(
+                String::from("not_actions"),
+                Value::from(statement.get_not_actions()),
+            );
+        }
+
+        if statement.get_not_resources().is_empty() {
+            result.insert(
+                String::from("resources"),
+                Value::from(statement.get_resources()),
+            );
+        } else {
+            result.insert(
+                String::from("not_resources"),
+                Value::from(statement.get_not_resources()),
+            );
+        }
         result.insert(
-            String::from("effect"),
-            Value::from(match self.effect {
-                PolicyEffect::Allow => "ALLOW",
-                _ => "DENY",
-            }),
-        );
-        result.insert(
-            String::from("actions"),
-            Value::from(self.actions.as_slice()),
-        );
-        result.insert(
-            String::from("resources"),
-            Value::from(self.resources.as_slice()),
+            String::from("conditions"),
+            statement.get_conditions().clone(),
         );
-        result.insert(String::from("conditions"), self.conditions.clone());
 
         result
     }
 }
 
+/// Matches the policy as a whole: any statement can decide the result.
+/// A fully matching deny statement prevails over an allow one,
+/// which prevails over a partial match.
+///
+/// The getters refer to the first statement, which is the only one of version 1 policies.
 impl MatchablePolicy for CompletePolicy {
     fn get_effect(&self) -> PolicyEffect {
-        self.effect
+        self.statements[0].get_effect()
     }
 
     fn matching<T, S>(&self, request: &AllowedRequest<T, S>) -> MatchResult
@@ -270,42 +421,35 @@ impl MatchablePolicy for CompletePolicy {
         T: ToString + Display,
         S: ToString + Display + Debug,
     {
-        let mut result = MatchResult::new();
-        let compiled = &self.compiled_policy;
-
-        if let Some(action) = request.action {
-            result.update_action(compiled.match_action(&action));
-        }
-
-        if compiled.all_resources {
-            result.update_resource(true);
-        } else if request.resource.is_some() {
-            if let Some(is_match) = compiled.match_resource(request.resource) {
-                result.update_resource(is_match);
-            }
-        }
-
-        if compiled.no_conditions {
-            result.update_conditions(true);
-        } else {
-            let is_match = compiled.match_conditions(request.params);
-            result.update_conditions(is_match);
-        }
+        self.decide(|s| s.matching(request))
+    }
 
-        result._update(self);
-        result
+    fn matching_partial<T, S>(&self, request: &AllowedRequest<T, S>) -> MatchResult
+    where
+        T: ToString + Display,
+        S: ToString + Display + Debug,
+    {
+        self.decide(|s| s.matching_partial(request))
     }
 
     fn get_actions(&self) -> &[String] {
-        self.actions.as_slice()
+        self.statements[0].get_actions()
+    }
+
+    fn get_not_actions(&self) -> &[String] {
+        self.statements[0].get_not_actions()
     }
 
     fn get_resources(&self) -> &[String] {
-        self.resources.as_slice()
+        self.statements[0].get_resources()
+    }
+
+    fn get_not_resources(&self) -> &[String] {
+        self.statements[0].get_not_resources()
     }
 
     fn get_conditions(&self) -> &Value {
-        &self.conditions
+        self.statements[0].get_conditions()
     }
 }
 
@@ -345,8 +489,11 @@ macro_rules! zephir_policy {
 
 #[cfg(test)]
 mod tests {
+    use crate::compiler::compiler::cache;
+    use crate::err::ErrorKind;
     use crate::identity::role::AllowedRequest;
-    use crate::policy::policy::{MatchablePolicy, Policy, ToJson};
+    use crate::policy::policy::{CompletePolicy, MatchablePolicy, Policy, ToJson};
+    use crate::policy::statement::PolicyTargets;
     use crate::policy::{PolicyEffect, PolicyVersion};
     use crate::zephir_policy;
     use serde_json::Value;
@@ -362,8 +509,12 @@ mod tests {
         .unwrap();
 
         assert_eq!(p.complete(), true);
-        assert_eq!(p.resources, vec!["*"]);
-        assert_eq!(p.actions, vec!["core:GetVersion", "test:GetResource"]);
+        assert_eq!(p.statements().len(), 1);
+        assert_eq!(p.statements()[0].get_resources(), &["*"]);
+        assert_eq!(
+            p.statements()[0].get_actions(),
+            &["core:GetVersion", "test:GetResource"]
+        );
         assert_eq!(
             p.to_json_string(),
             "{\"id\":\"TestPolicy400\",\"version\":1,\"effect\":\"DENY\",\"actions\":[\"core:GetVersion\",\"test:GetResource\"],\"resources\":[\"*\"],\"conditions\":null}"
@@ -384,6 +535,21 @@ mod tests {
         assert_eq!(result.to_string(), "Actions set cannot be empty");
     }
 
+    #[test]
+    fn policy_creation_should_return_err_if_a_glob_is_invalid() {
+        let result = zephir_policy!(
+            "TestPolicyInvalidGlob",
+            PolicyVersion::Version1,
+            PolicyEffect::Allow,
+            vec!["core:Get"],
+            vec!["urn:bucket:[abc"]
+        )
+        .err()
+        .unwrap();
+
+        assert_eq!(result.kind(), ErrorKind::InvalidGlobError);
+    }
+
     #[test]
     fn policy_matching_should_work_if_policy_contains_all_actions() {
         let policy = zephir_policy!(
@@ -621,4 +787,501 @@ mod tests {
         assert_eq!(result.is_match(), false);
         assert_eq!(result.is_full(), true);
     }
+
+    #[test]
+    fn policy_matching_should_return_residual_conditions() {
+        let policy = zephir_policy!(
+            "TestPolicyResidual",
+            PolicyVersion::Version1,
+            PolicyEffect::Allow,
+            vec!["*Action"],
+            vec!["*"],
+            serde_json::json!({
+                "StringEquals": {
+                    "TargetResource": "ThisIsTheString",
+                    "Department": "Sales",
+                },
+                "Bool": { "mfa": true },
+                "StringEqualsIfExists": { "Team": "Red" },
+            })
+        )
+        .unwrap();
+
+        let request = AllowedRequest {
+            action: Some(&"FooAction"),
+            resource: Some(&"urn::resource:test"),
+            params: &serde_json::json!({
+                "TargetResource": "ThisIsTheString",
+            }),
+        };
+
+        let result = policy.matching(&request);
+        assert_eq!(result.is_match(), false);
+        assert_eq!(result.is_full(), true);
+
+        let result = policy.matching_partial(&request);
+        assert_eq!(result.is_match(), true);
+        assert_eq!(result.is_full(), false);
+        assert_eq!(result.conditions_match(), None);
+        assert_eq!(
+            result.get_partial().conditions,
+            serde_json::json!({
+                "StringEquals": { "Department": "Sales" },
+                "Bool": { "mfa": true },
+            })
+        );
+
+        let request = AllowedRequest {
+            action: Some(&"FooAction"),
+            resource: Some(&"urn::resource:test"),
+            params: &serde_json::json!({
+                "TargetResource": "ThisIsAnotherString",
+            }),
+        };
+
+        let result = policy.matching_partial(&request);
+        assert_eq!(result.is_match(), false);
+        assert_eq!(result.is_full(), true);
+    }
+
+    #[test]
+    fn policy_matching_should_return_residual_condition_blocks() {
+        let policy = zephir_policy!(
+            "TestPolicyResidualBlocks",
+            PolicyVersion::Version1,
+            PolicyEffect::Allow,
+            vec!["*Action"],
+            vec!["*"],
+            serde_json::json!({
+                "AnyOf": [
+                    { "Bool": { "mfa": true } },
+                    { "IpAddress": { "source": "10.0.0.0/8" } },
+                ],
+                "Not": { "Bool": { "guest": true } },
+            })
+        )
+        .unwrap();
+
+        let request = AllowedRequest {
+            action: Some(&"FooAction"),
+            resource: Some(&"urn::resource:test"),
+            params: &serde_json::json!({ "mfa": false, "guest": false }),
+        };
+
+        let result = policy.matching_partial(&request);
+        assert_eq!(result.is_match(), true);
+        assert_eq!(result.is_full(), false);
+        assert_eq!(
+            result.get_partial().conditions,
+            serde_json::json!({
+                "AnyOf": [
+                    { "Bool": { "mfa": true } },
+                    { "IpAddress": { "source": "10.0.0.0/8" } },
+                ],
+            })
+        );
+
+        let request = AllowedRequest {
+            action: Some(&"FooAction"),
+            resource: Some(&"urn::resource:test"),
+            params: &serde_json::json!({ "mfa": true, "guest": false }),
+        };
+
+        let result = policy.matching_partial(&request);
+        assert_eq!(result.is_match(), true);
+        assert_eq!(result.is_full(), true);
+
+        let request = AllowedRequest {
+            action: Some(&"FooAction"),
+            resource: Some(&"urn::resource:test"),
+            params: &serde_json::json!({ "mfa": true, "guest": true }),
+        };
+
+        let result = policy.matching_partial(&request);
+        assert_eq!(result.is_match(), false);
+    }
+
+    #[test]
+    fn may_match_should_ignore_conditions() {
+        let policy = zephir_policy!(
+            "TestMayMatchPolicy",
+            PolicyVersion::Version1,
+            PolicyEffect::Allow,
+            vec!["core:Get*"],
+            vec!["urn:resource:*"],
+            serde_json::json!({ "Bool": { "mfa": true } })
+        )
+        .unwrap();
+
+        assert_eq!(
+            policy.may_match("core:GetVersion", Some("urn:resource:a")),
+            true
+        );
+        assert_eq!(policy.may_match("core:GetVersion", None), true);
+        assert_eq!(
+            policy.may_match("core:GetVersion", Some("urn:other:a")),
+            false
+        );
+        assert_eq!(
+            policy.may_match("core:Delete", Some("urn:resource:a")),
+            false
+        );
+    }
+
+    #[test]
+    fn policy_variables_should_be_resolved_on_evaluation() {
+        let policy = CompletePolicy::from_statements(
+            "TestVariablesPolicy".to_string(),
+            &serde_json::json!([{
+                "Effect": "Allow",
+                "Action": "docs:${operation}",
+                "Resource": "urn:docs:${zephir:subject}:*",
+                "Condition": { "StringEquals": { "owner": "${zephir:subject}" } },
+            }]),
+        )
+        .unwrap();
+        let statement = &policy.statements()[0];
+
+        let params = serde_json::json!({
+            "zephir:subject": "alice",
+            "operation": "Get",
+            "owner": "alice",
+        });
+        let request = AllowedRequest {
+            action: Some(&"docs:Get"),
+            resource: Some(&"urn:docs:alice:report"),
+            params: &params,
+        };
+        assert_eq!(statement.matching(&request).is_full(), true);
+
+        let request = AllowedRequest {
+            action: Some(&"docs:Get"),
+            resource: Some(&"urn:docs:bob:report"),
+            params: &params,
+        };
+        assert_eq!(statement.matching(&request).is_match(), false);
+
+        let params = serde_json::json!({
+            "zephir:subject": "bob",
+            "operation": "Get",
+            "owner": "alice",
+        });
+        let request = AllowedRequest {
+            action: Some(&"docs:Get"),
+            resource: Some(&"urn:docs:bob:report"),
+            params: &params,
+        };
+        assert_eq!(statement.matching(&request).is_match(), false);
+
+        let params = serde_json::json!({ "operation": "Get", "owner": "alice" });
+        let request = AllowedRequest {
+            action: Some(&"docs:Get"),
+            resource: None as Option<&String>,
+            params: &params,
+        };
+        assert_eq!(statement.matching(&request).is_match(), false);
+
+        let result = statement.matching_partial(&request);
+        assert_eq!(result.is_full(), false);
+        assert_eq!(
+            result.get_partial().conditions,
+            serde_json::json!({ "StringEquals": { "owner": "${zephir:subject}" } })
+        );
+
+        assert_eq!(policy.may_match("docs:Delete", Option::None), true);
+    }
+
+    #[test]
+    fn negated_policy_variables_should_be_possible_matches() {
+        let policy = CompletePolicy::from_statements(
+            "TestNegatedVariablesPolicy".to_string(),
+            &serde_json::json!([{
+                "Effect": "Allow",
+                "NotAction": ["${zephir:subject}:*"],
+                "NotResource": ["urn:docs:${zephir:subject}:*"],
+            }]),
+        )
+        .unwrap();
+
+        assert_eq!(policy.may_match("alice:Read", Option::None), true);
+        assert_eq!(
+            policy.may_match("alice:Read", Option::Some("urn:docs:alice:report")),
+            true
+        );
+
+        let params = serde_json::json!({ "zephir:subject": "alice" });
+        let request = |action, resource| AllowedRequest {
+            action: Some(action),
+            resource: Some(resource),
+            params: &params,
+        };
+        assert_eq!(
+            policy
+                .matching(&request(&"alice:Read", &"urn:docs:bob:report"))
+                .is_match(),
+            false
+        );
+        assert_eq!(
+            policy
+                .matching(&request(&"bob:Read", &"urn:docs:alice:report"))
+                .is_match(),
+            false
+        );
+        assert_eq!(
+            policy
+                .matching(&request(&"bob:Read", &"urn:docs:bob:report"))
+                .is_full(),
+            true
+        );
+    }
+
+    #[test]
+    fn version_1_policies_should_not_resolve_variables() {
+        let policy = zephir_policy!(
+            "TestLegacyVariablesPolicy",
+            PolicyVersion::Version1,
+            PolicyEffect::Allow,
+            vec!["docs:${operation}"]
+        )
+        .unwrap();
+
+        let request = AllowedRequest {
+            action: Some(&"docs:Get"),
+            resource: None as Option<&String>,
+            params: &serde_json::json!({ "operation": "Get" }),
+        };
+        assert_eq!(policy.matching(&request).is_match(), false);
+    }
+
+    #[test]
+    fn version_2_policy_could_be_created_from_statements() {
+        let policy = CompletePolicy::from_statements(
+            "TestStatementsPolicy".to_string(),
+            &serde_json::json!([
+                {
+                    "Sid": "ReadDocuments",
+                    "Effect": "Allow",
+                    "Action": ["docs:Get*"],
+                    "Resource": "urn:doc:*",
+                },
+                {
+                    "Sid": "NoSecrets",
+                    "Effect": "Deny",
+                    "Action": "docs:*",
+                    "Resource": "urn:doc:secret",
+                },
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(policy.complete(), true);
+        assert_eq!(policy.version, PolicyVersion::Version2);
+        assert_eq!(policy.statements().len(), 2);
+        assert_eq!(policy.statements()[1].effect, PolicyEffect::Deny);
+        assert_eq!(
+            policy.to_value(),
+            serde_json::json!({
+                "id": "TestStatementsPolicy",
+                "version": 2,
+                "statements": [
+                    {
+                        "Sid": "ReadDocuments",
+                        "Effect": "ALLOW",
+                        "Action": ["docs:Get*"],
+                        "Resource": ["urn:doc:*"],
+                        "Condition": null,
+                    },
+                    {
+                        "Sid": "NoSecrets",
+                        "Effect": "DENY",
+                        "Action": ["docs:*"],
+                        "Resource": ["urn:doc:secret"],
+                        "Condition": null,
+                    },
+                ],
+            })
+        );
+
+        let request = AllowedRequest {
+            action: Some(&"docs:GetContent"),
+            resource: None as Option<&String>,
+            params: &Value::Null,
+        };
+
+        let partial = policy.matching(&request).get_partial();
+        assert_eq!(partial.sid, Some("ReadDocuments".to_string()));
+        assert_eq!(
+            partial.to_json_string(),
+            "{\"sid\":\"ReadDocuments\",\"version\":2,\"effect\":\"ALLOW\",\"resources\":[\"urn:doc:*\"],\"conditions\":null}"
+        );
+    }
+
+    #[test]
+    fn any_statement_should_decide_the_policy_match() {
+        let policy = CompletePolicy::from_statements(
+            "TestDecidingStatement".to_string(),
+            &serde_json::json!([
+                { "Effect": "Allow", "Action": "docs:*" },
+                { "Effect": "Deny", "Action": "docs:Delete*" },
+                { "Effect": "Allow", "Action": "media:*", "Resource": "urn:media:*" },
+            ]),
+        )
+        .unwrap();
+
+        let request = |action| AllowedRequest {
+            action: Some(action),
+            resource: None as Option<&String>,
+            params: &Value::Null,
+        };
+
+        let result = policy.matching(&request(&"docs:DeleteContent"));
+        assert_eq!(result.is_full(), true);
+        assert_eq!(result.get_partial().effect, PolicyEffect::Deny);
+
+        let result = policy.matching(&request(&"docs:GetContent"));
+        assert_eq!(result.is_full(), true);
+        assert_eq!(result.get_partial().effect, PolicyEffect::Allow);
+
+        let result = policy.matching(&request(&"media:GetImage"));
+        assert_eq!(result.is_match(), true);
+        assert_eq!(result.is_full(), false);
+
+        assert_eq!(
+            policy.matching(&request(&"users:GetUser")).is_match(),
+            false
+        );
+    }
+
+    #[test]
+    fn flushed_version_2_statements_should_be_recompiled() {
+        let statements = |action: &str| {
+            serde_json::json!([
+                { "Effect": "Deny", "Action": "docs:Delete*" },
+                { "Effect": "Allow", "Action": action },
+            ])
+        };
+        let request = AllowedRequest {
+            action: Some(&"docs:GetContent"),
+            resource: None as Option<&String>,
+            params: &Value::Null,
+        };
+
+        let id = "TestFlushedStatements".to_string();
+        let policy = CompletePolicy::from_statements(id.clone(), &statements("docs:Get*")).unwrap();
+        assert_eq!(policy.statements()[1].matching(&request).is_match(), true);
+
+        cache::flush_policy(&id);
+        let policy = CompletePolicy::from_statements(id, &statements("docs:List*")).unwrap();
+        assert_eq!(policy.statements()[1].matching(&request).is_match(), false);
+    }
+
+    #[test]
+    fn invalid_statement_lists_should_be_rejected() {
+        let statement = serde_json::json!({ "Sid": "Read", "Effect": "Allow", "Action": "a" });
+        for statements in [
+            serde_json::json!([]),
+            serde_json::json!({}),
+            serde_json::json!([statement.clone(), statement]),
+            serde_json::json!(vec![
+                serde_json::json!({ "Effect": "Allow", "Action": "a" });
+                101
+            ]),
+        ] {
+            assert_eq!(
+                CompletePolicy::from_statements("TestInvalidStatements".to_string(), &statements)
+                    .is_err(),
+                true
+            );
+        }
+    }
+
+    #[test]
+    fn negated_actions_and_resources_should_be_matched() {
+        let policy = CompletePolicy::with_targets(
+            "TestNotActionPolicy".to_string(),
+            PolicyVersion::Version1,
+            PolicyEffect::Deny,
+            PolicyTargets::default().not_actions(vec!["iam:ChangePassword"]),
+            Value::Null,
+        )
+        .unwrap();
+
+        let statement = &policy.statements()[0];
+        let request = AllowedRequest {
+            action: Some(&"iam:ChangePassword"),
+            resource: Some(&"urn:user:me"),
+            params: &Value::Null,
+        };
+        assert_eq!(statement.matching(&request).is_match(), false);
+
+        let request = AllowedRequest {
+            action: Some(&"iam:DeleteUser"),
+            resource: Some(&"urn:user:me"),
+            params: &Value::Null,
+        };
+        assert_eq!(statement.matching(&request).is_match(), true);
+        assert_eq!(
+            policy.to_json_string(),
+            "{\"id\":\"TestNotActionPolicy\",\"version\":1,\"effect\":\"DENY\",\"not_actions\":[\"iam:ChangePassword\"],\"resources\":[\"*\"],\"conditions\":null}"
+        );
+
+        let policy = CompletePolicy::with_targets(
+            "TestNotResourcePolicy".to_string(),
+            PolicyVersion::Version1,
+            PolicyEffect::Allow,
+            PolicyTargets::new(vec!["s3:*"], vec![] as Vec<String>)
+                .not_resources(vec!["urn:bucket:audit"]),
+            Value::Null,
+        )
+        .unwrap();
+
+        let statement = &policy.statements()[0];
+        let request = AllowedRequest {
+            action: Some(&"s3:GetObject"),
+            resource: Some(&"urn:bucket:audit"),
+            params: &Value::Null,
+        };
+        assert_eq!(statement.matching(&request).is_match(), false);
+
+        let request = AllowedRequest {
+            action: Some(&"s3:GetObject"),
+            resource: Some(&"urn:bucket:logs"),
+            params: &Value::Null,
+        };
+        assert_eq!(statement.matching(&request).is_match(), true);
+
+        let request = AllowedRequest {
+            action: Some(&"s3:GetObject"),
+            resource: None as Option<&String>,
+            params: &Value::Null,
+        };
+        let partial = statement.matching(&request).get_partial();
+        assert_eq!(partial.resources, None);
+        assert_eq!(
+            partial.not_resources,
+            Some(vec!["urn:bucket:audit".to_string()])
+        );
+    }
+
+    #[test]
+    fn positive_and_negated_lists_should_not_be_both_set() {
+        let result = CompletePolicy::with_targets(
+            "TestBothActionsPolicy".to_string(),
+            PolicyVersion::Version1,
+            PolicyEffect::Allow,
+            PolicyTargets::new(vec!["s3:*"], vec![] as Vec<String>).not_actions(vec!["s3:Put*"]),
+            Value::Null,
+        );
+        assert_eq!(result.is_err(), true);
+
+        let result = CompletePolicy::with_targets(
+            "TestBothResourcesPolicy".to_string(),
+            PolicyVersion::Version1,
+            PolicyEffect::Allow,
+            PolicyTargets::new(vec!["s3:*"], vec!["urn:bucket:*"])
+                .not_resources(vec!["urn:bucket:audit"]),
+            Value::Null,
+        );
+        assert_eq!(result.is_err(), true);
+    }
 }
@@ -0,0 +1,493 @@
+use crate::err::{Error, ErrorKind};
+use crate::policy::allowed_result::{AllowedOutcome, AllowedResult};
+use crate::policy::policy::PartialPolicy;
+use crate::policy::{PolicyEffect, PolicyVersion};
+use crate::utils::glob_to_regex;
+
+const ALWAYS_TRUE: &str = "1 = 1";
+const ALWAYS_FALSE: &str = "1 = 0";
+
+/// The SQL dialect the predicates are generated for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SqlDialect {
+    Postgres,
+    MySql,
+    Sqlite,
+}
+
+impl SqlDialect {
+    fn placeholder(self, index: usize) -> String {
+        match self {
+            SqlDialect::MySql => String::from("?"),
+            _ => format!("${}", index),
+        }
+    }
+
+    fn regex_operator(self) -> &'static str {
+        match self {
+            SqlDialect::Postgres => "~",
+            _ => "REGEXP",
+        }
+    }
+}
+
+/// A parameterized SQL predicate.
+/// Parameters must be bound in order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SqlFilter {
+    pub predicate: String,
+    pub params: Vec<String>,
+}
+
+impl SqlFilter {
+    fn always_false() -> Self {
+        SqlFilter {
+            predicate: ALWAYS_FALSE.to_string(),
+            params: vec![],
+        }
+    }
+}
+
+/// Translates the partial policies of an allowed result into a SQL predicate
+/// over a resource id column.
+///
+/// Allow partials are OR-ed together, while each deny partial is negated
+/// and AND-ed to the result (negated resources being translated the other way round).
+/// Version 2 globs without wildcards are compared by equality, other version 2 globs
+/// are translated into anchored regexes. Version 1 globs are always translated
+/// into unanchored regexes, as they are evaluated (REGEXP function is required on SQLite).
+#[derive(Clone, Debug)]
+pub struct SqlFilterBuilder {
+    column: String,
+    dialect: SqlDialect,
+    first_param: usize,
+}
+
+/// Whether the given string is a (possibly qualified) SQL identifier.
+fn is_identifier(column: &str) -> bool {
+    !column.is_empty()
+        && column.split('.').all(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+}
+
+/// Whether the glob would only match itself.
+fn is_literal(glob: &str) -> bool {
+    !glob.contains(['*', '?', '{', '[', '\\'])
+}
+
+impl SqlFilterBuilder {
+    pub fn new(column: &str, dialect: SqlDialect) -> Result<Self, Error> {
+        if !is_identifier(column) {
+            return Err(Error::new(
+                ErrorKind::UnknownError,
+                format!(r#"Invalid column name "{}""#, column),
+            ));
+        }
+
+        Ok(SqlFilterBuilder {
+            column: column.to_string(),
+            dialect,
+            first_param: 1,
+        })
+    }
+
+    /// Sets the index of the first placeholder, to append the predicate
+    /// to a query already having parameters. Ignored on MySQL.
+    pub fn first_param(mut self, index: usize) -> Self {
+        self.first_param = index;
+        self
+    }
+
+    /// Builds the predicate of the given result.
+    /// Fails if a partial policy cannot be expressed as a resource filter
+    /// (ex: it has not been evaluated against an action).
+    pub fn build(&self, result: &AllowedResult) -> Result<SqlFilter, Error> {
+        let fully_allowed = result.outcome() == AllowedOutcome::Allowed;
+        if result.outcome() == AllowedOutcome::Denied {
+            return Ok(SqlFilter::always_false());
+        }
+
+        let mut allowed = vec![];
+        let mut denied = vec![];
+        for partial in result.get_partials() {
+            let scope = resource_scope(partial)?;
+            match (partial.effect, scope) {
+                (PolicyEffect::Deny, ResourceScope::All) => return Ok(SqlFilter::always_false()),
+                (PolicyEffect::Deny, scope) => denied.push(scope),
+                (PolicyEffect::Allow, scope) => allowed.push(scope),
+            }
+        }
+
+        if !fully_allowed && allowed.is_empty() {
+            // Only deny partials: nothing grants the access.
+            return Ok(SqlFilter::always_false());
+        }
+
+        let mut params = vec![];
+        let mut clauses = vec![];
+        if !fully_allowed && !allowed.iter().any(|s| matches!(s, ResourceScope::All)) {
+            if allowed.iter().all(|s| matches!(s, ResourceScope::Only(..))) {
+                let globs: Vec<(&String, &PolicyVersion)> = allowed
+                    .iter()
+                    .filter_map(|s| match s {
+                        ResourceScope::Only(resources, version) => {
+                            Some(resources.iter().map(move |r| (r, *version)))
+                        }
+                        _ => None,
+                    })
+                    .flatten()
+                    .collect();
+                clauses.push(self.globs_predicate(globs, &mut params)?);
+            } else {
+                let predicates: Vec<String> = allowed
+                    .iter()
+                    .map(|scope| self.scope_predicate(scope, false, &mut params))
+                    .collect::<Result<_, _>>()?;
+                clauses.push(format!("({})", predicates.join(" OR ")));
+            }
+        }
+
+        for scope in denied {
+            clauses.push(self.scope_predicate(&scope, true, &mut params)?);
+        }
+
+        Ok(SqlFilter {
+            predicate: if clauses.is_empty() {
+                ALWAYS_TRUE.to_string()
+            } else {
+                clauses.join(" AND ")
+            },
+            params,
+        })
+    }
+
+    /// Gets the predicate matching the resources in the given scope (or out of it, if negated).
+    fn scope_predicate(
+        &self,
+        scope: &ResourceScope,
+        negated: bool,
+        params: &mut Vec<String>,
+    ) -> Result<String, Error> {
+        let (resources, version, negated) = match scope {
+            ResourceScope::Only(resources, version) => (resources, version, negated),
+            ResourceScope::Except(resources, version) => (resources, version, !negated),
+            ResourceScope::All => return Ok(ALWAYS_TRUE.to_string()),
+        };
+
+        let globs = resources.iter().map(|r| (r, *version)).collect();
+        let predicate = self.globs_predicate(globs, params)?;
+        Ok(if negated {
+            format!("NOT {}", predicate)
+        } else {
+            predicate
+        })
+    }
+
+    /// Gets the predicate matching any of the given globs, wrapped in parenthesis.
+    /// Globs are parsed with the grammar of the version of their policy.
+    fn globs_predicate(
+        &self,
+        globs: Vec<(&String, &PolicyVersion)>,
+        params: &mut Vec<String>,
+    ) -> Result<String, Error> {
+        let mut predicates = vec![];
+        for (glob, version) in globs {
+            let placeholder = self.dialect.placeholder(self.first_param + params.len());
+            if *version == PolicyVersion::Version1 {
+                // Legacy globs match any resource containing them, even without wildcards.
+                predicates.push(self.regex_predicate(&placeholder));
+                params.push(glob_to_regex::to_legacy_pattern(glob));
+            } else if is_literal(glob) {
+                predicates.push(format!("{} = {}", self.column, placeholder));
+                params.push(glob.clone());
+            } else {
+                predicates.push(self.regex_predicate(&placeholder));
+                params.push(format!("^({})$", glob_to_regex::to_pattern(glob)?));
+            }
+        }
+
+        Ok(format!("({})", predicates.join(" OR ")))
+    }
+
+    /// Gets the predicate matching the column against the regex bound to the given placeholder.
+    fn regex_predicate(&self, placeholder: &str) -> String {
+        format!(
+            "{} {} {}",
+            self.column,
+            self.dialect.regex_operator(),
+            placeholder
+        )
+    }
+}
+
+/// The resources a partial policy applies to.
+enum ResourceScope<'a> {
+    All,
+    Only(&'a [String], &'a PolicyVersion),
+    Except(&'a [String], &'a PolicyVersion),
+}
+
+/// Gets the resources a partial policy applies to.
+fn resource_scope(partial: &PartialPolicy) -> Result<ResourceScope<'_>, Error> {
+    if partial.actions.is_some() || partial.not_actions.is_some() || !partial.conditions.is_null() {
+        return Err(Error::new(
+            ErrorKind::UnknownError,
+            "Partial policy cannot be translated: only resources can be filtered",
+        ));
+    }
+
+    if let Some(not_resources) = partial.not_resources.as_deref() {
+        if !not_resources.is_empty() {
+            return Ok(ResourceScope::Except(not_resources, &partial.version));
+        }
+    }
+
+    Ok(match partial.resources.as_deref() {
+        Some(resources) if !resources.is_empty() && !resources.iter().any(|r| r == "*") => {
+            ResourceScope::Only(resources, &partial.version)
+        }
+        _ => ResourceScope::All,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::identity::role::AllowedRequest;
+    use crate::policy::allowed_result::{AllowedOutcome, AllowedResult};
+    use crate::policy::policy::{MatchablePolicy, PartialPolicy};
+    use crate::policy::sql_filter::{SqlDialect, SqlFilterBuilder};
+    use crate::policy::{PolicyEffect, PolicyVersion};
+    use crate::zephir_policy;
+    use pcre2::bytes::Regex;
+    use serde_json::Value;
+
+    fn partial(effect: PolicyEffect, resources: Vec<&str>) -> PartialPolicy {
+        PartialPolicy {
+            version: PolicyVersion::Version2,
+            effect,
+            resources: Some(resources.into_iter().map(String::from).collect()),
+            ..PartialPolicy::default()
+        }
+    }
+
+    #[test]
+    fn column_name_should_be_validated() {
+        assert_eq!(
+            SqlFilterBuilder::new("t.resource_id", SqlDialect::Postgres).is_ok(),
+            true
+        );
+        assert_eq!(
+            SqlFilterBuilder::new("id; DROP", SqlDialect::Postgres).is_err(),
+            true
+        );
+        assert_eq!(
+            SqlFilterBuilder::new("", SqlDialect::Postgres).is_err(),
+            true
+        );
+    }
+
+    #[test]
+    fn denied_result_should_filter_everything() {
+        let filter = SqlFilterBuilder::new("id", SqlDialect::Postgres)
+            .unwrap()
+            .build(&AllowedResult::denied())
+            .unwrap();
+
+        assert_eq!(filter.predicate, "1 = 0");
+        assert_eq!(filter.params.is_empty(), true);
+    }
+
+    #[test]
+    fn partials_should_be_translated() {
+        let result = AllowedResult::new(
+            AllowedOutcome::Abstain,
+            vec![
+                partial(
+                    PolicyEffect::Allow,
+                    vec!["urn:doc:public", "urn:doc:team:*"],
+                ),
+                partial(PolicyEffect::Allow, vec!["urn:image:*"]),
+                partial(PolicyEffect::Deny, vec!["urn:doc:team:secret"]),
+            ],
+        );
+
+        let filter = SqlFilterBuilder::new("resource_id", SqlDialect::Postgres)
+            .unwrap()
+            .first_param(3)
+            .build(&result)
+            .unwrap();
+
+        assert_eq!(
+            filter.predicate,
+            "(resource_id = $3 OR resource_id ~ $4 OR resource_id ~ $5) AND NOT (resource_id = $6)"
+        );
+        assert_eq!(
+            filter.params,
+            vec![
+                "urn:doc:public",
+                "^(urn:doc:team:[^:]*)$",
+                "^(urn:image:[^:]*)$",
+                "urn:doc:team:secret"
+            ]
+        );
+
+        let filter = SqlFilterBuilder::new("resource_id", SqlDialect::MySql)
+            .unwrap()
+            .build(&result)
+            .unwrap();
+        assert_eq!(
+            filter.predicate,
+            "(resource_id = ? OR resource_id REGEXP ? OR resource_id REGEXP ?) AND NOT (resource_id = ?)"
+        );
+    }
+
+    #[test]
+    fn allowed_result_should_only_apply_deny_partials() {
+        let mut result = AllowedResult::new(AllowedOutcome::Allowed, vec![]);
+        result.merge(AllowedResult::new(
+            AllowedOutcome::Abstain,
+            vec![partial(PolicyEffect::Deny, vec!["urn:doc:*"])],
+        ));
+
+        let filter = SqlFilterBuilder::new("id", SqlDialect::Sqlite)
+            .unwrap()
+            .build(&result)
+            .unwrap();
+
+        assert_eq!(filter.predicate, "NOT (id REGEXP $1)");
+        assert_eq!(filter.params, vec!["^(urn:doc:[^:]*)$"]);
+
+        let result = AllowedResult::new(
+            AllowedOutcome::Abstain,
+            vec![
+                partial(PolicyEffect::Allow, vec!["urn:doc:a"]),
+                partial(PolicyEffect::Allow, vec!["*"]),
+                partial(PolicyEffect::Deny, vec!["urn:doc:b"]),
+            ],
+        );
+        let filter = SqlFilterBuilder::new("id", SqlDialect::Sqlite)
+            .unwrap()
+            .build(&result)
+            .unwrap();
+        assert_eq!(filter.predicate, "NOT (id = $1)");
+        assert_eq!(filter.params, vec!["urn:doc:b"]);
+
+        let result = AllowedResult::new(AllowedOutcome::Allowed, vec![]);
+        let filter = SqlFilterBuilder::new("id", SqlDialect::Sqlite)
+            .unwrap()
+            .build(&result)
+            .unwrap();
+        assert_eq!(filter.predicate, "1 = 1");
+    }
+
+    #[test]
+    fn deny_all_partial_should_filter_everything() {
+        let result = AllowedResult::new(
+            AllowedOutcome::Abstain,
+            vec![
+                partial(PolicyEffect::Allow, vec!["urn:doc:*"]),
+                partial(PolicyEffect::Deny, vec!["*"]),
+            ],
+        );
+
+        let filter = SqlFilterBuilder::new("id", SqlDialect::Postgres)
+            .unwrap()
+            .build(&result)
+            .unwrap();
+        assert_eq!(filter.predicate, "1 = 0");
+        assert_eq!(filter.params.is_empty(), true);
+
+        let result = AllowedResult::new(
+            AllowedOutcome::Abstain,
+            vec![partial(PolicyEffect::Deny, vec!["urn:doc:*"])],
+        );
+        let filter = SqlFilterBuilder::new("id", SqlDialect::Postgres)
+            .unwrap()
+            .build(&result)
+            .unwrap();
+        assert_eq!(filter.predicate, "1 = 0");
+    }
+
+    #[test]
+    fn negated_resources_should_be_translated() {
+        let mut allow = partial(PolicyEffect::Allow, vec![]);
+        allow.resources = None;
+        allow.not_resources = Some(vec!["urn:bucket:audit".to_string()]);
+
+        let result = AllowedResult::new(
+            AllowedOutcome::Abstain,
+            vec![
+                allow.clone(),
+                partial(PolicyEffect::Allow, vec!["urn:doc:*"]),
+            ],
+        );
+        let filter = SqlFilterBuilder::new("id", SqlDialect::Postgres)
+            .unwrap()
+            .build(&result)
+            .unwrap();
+        assert_eq!(filter.predicate, "(NOT (id = $1) OR (id ~ $2))");
+        assert_eq!(filter.params, vec!["urn:bucket:audit", "^(urn:doc:[^:]*)$"]);
+
+        let mut deny = allow;
+        deny.effect = PolicyEffect::Deny;
+        let result = AllowedResult::new(
+            AllowedOutcome::Abstain,
+            vec![partial(PolicyEffect::Allow, vec!["urn:bucket:*"]), deny],
+        );
+        let filter = SqlFilterBuilder::new("id", SqlDialect::Postgres)
+            .unwrap()
+            .build(&result)
+            .unwrap();
+        assert_eq!(filter.predicate, "(id ~ $1) AND (id = $2)");
+    }
+
+    #[test]
+    fn legacy_globs_should_be_translated_as_evaluated() {
+        let policy = zephir_policy!(
+            "TestSqlFilterLegacy",
+            PolicyVersion::Version1,
+            PolicyEffect::Deny,
+            vec!["doc:Read"],
+            vec!["urn:doc:team:secret"]
+        )
+        .unwrap();
+
+        let mut deny = partial(PolicyEffect::Deny, vec!["urn:doc:team:secret"]);
+        deny.version = PolicyVersion::Version1;
+        let result = AllowedResult::new(
+            AllowedOutcome::Abstain,
+            vec![partial(PolicyEffect::Allow, vec!["urn:doc:**"]), deny],
+        );
+        let filter = SqlFilterBuilder::new("id", SqlDialect::Postgres)
+            .unwrap()
+            .build(&result)
+            .unwrap();
+        assert_eq!(filter.predicate, "(id ~ $1) AND NOT (id ~ $2)");
+        assert_eq!(filter.params[1], "urn:doc:team:secret");
+
+        let regex = Regex::new(&filter.params[1]).unwrap();
+        for resource in [
+            "urn:doc:team:secret",
+            "urn:doc:team:secret2",
+            "urn:doc:public",
+        ] {
+            let request = AllowedRequest {
+                action: Some(&"doc:Read"),
+                resource: Some(&resource),
+                params: &Value::Null,
+            };
+
+            assert_eq!(
+                regex.is_match(resource.as_bytes()).unwrap(),
+                policy.matching(&request).is_match(),
+                "{}",
+                resource
+            );
+        }
+    }
+}
@@ -0,0 +1,484 @@
+use crate::compiler::compiled_policy::CompiledPolicy;
+use crate::compiler::compiler::{cache, Compiler};
+use crate::err::Error;
+use crate::identity::role::AllowedRequest;
+use crate::policy::condition::Condition;
+use crate::policy::match_result::MatchResult;
+use crate::policy::policy::{MatchablePolicy, ToJson};
+use crate::policy::variables;
+use crate::policy::{PolicyEffect, PolicyVersion};
+use serde_json::{Map, Value};
+use std::convert::TryFrom;
+use std::fmt::{Debug, Display};
+use std::hash::{Hash, Hasher};
+
+/// Maximum number of statements of a policy document.
+pub const MAX_STATEMENTS: usize = 100;
+
+/// The actions and resources a statement applies to.
+///
+/// Each dimension is either a positive list (actions, resources) or a negated one
+/// (not_actions, not_resources), which applies to everything but the given globs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PolicyTargets {
+    pub actions: Vec<String>,
+    pub not_actions: Vec<String>,
+    pub resources: Vec<String>,
+    pub not_resources: Vec<String>,
+}
+
+impl PolicyTargets {
+    /// Creates positive targets from the given actions and resources.
+    pub fn new<A, R>(actions: Vec<A>, resources: Vec<R>) -> Self
+    where
+        A: ToString,
+        R: ToString,
+    {
+        PolicyTargets {
+            actions: actions.into_iter().map(|s| s.to_string()).collect(),
+            resources: resources.into_iter().map(|s| s.to_string()).collect(),
+            ..PolicyTargets::default()
+        }
+    }
+
+    /// Sets the negated actions.
+    pub fn not_actions<A: ToString>(mut self, not_actions: Vec<A>) -> Self {
+        self.not_actions = not_actions.into_iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    /// Sets the negated resources.
+    pub fn not_resources<R: ToString>(mut self, not_resources: Vec<R>) -> Self {
+        self.not_resources = not_resources.into_iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    /// Whether the actions are negated.
+    pub fn actions_negated(&self) -> bool {
+        !self.not_actions.is_empty()
+    }
+
+    /// Whether the resources are negated.
+    pub fn resources_negated(&self) -> bool {
+        !self.not_resources.is_empty()
+    }
+
+    /// Checks that each dimension has either a positive or a negated list.
+    /// Targets without resources are applied to all the resources.
+    fn validate(mut self) -> Result<Self, Error> {
+        if !self.actions.is_empty() && self.actions_negated() {
+            return Err(Error::from("Actions and not actions cannot be both set"));
+        }
+
+        if self.actions.is_empty() && !self.actions_negated() {
+            return Err(Error::actions_cannot_be_empty());
+        }
+
+        if !self.resources.is_empty() && self.resources_negated() {
+            return Err(Error::from(
+                "Resources and not resources cannot be both set",
+            ));
+        }
+
+        if self.resources.is_empty() && !self.resources_negated() {
+            self.resources = vec!["*".to_string()];
+        }
+
+        Ok(self)
+    }
+}
+
+/// A statement of a policy: the unit matched against the requests.
+///
+/// Version 1 policies are made of a single statement without sid, while
+/// version 2 documents hold a list of statements, each compiled separately.
+#[derive(Clone, Debug)]
+pub struct PolicyStatement {
+    pub sid: Option<String>,
+    pub version: PolicyVersion,
+    pub effect: PolicyEffect,
+    targets: PolicyTargets,
+    conditions: Value,
+
+    compiled_policy: CompiledPolicy,
+}
+
+/// Reads a list of strings from a statement field.
+/// A single string is accepted as a list of one element.
+fn string_list(statement: &Map<String, Value>, key: &str) -> Result<Vec<String>, Error> {
+    match statement.get(key) {
+        None | Some(Value::Null) => Ok(vec![]),
+        Some(Value::String(value)) => Ok(vec![value.clone()]),
+        Some(Value::Array(values)) => values
+            .iter()
+            .map(|v| {
+                v.as_str()
+                    .map(String::from)
+                    .ok_or_else(|| Error::from(format!("Statement.{} is not a string list", key)))
+            })
+            .collect(),
+        Some(_) => Err(Error::from(format!(
+            "Statement.{} is not a string list",
+            key
+        ))),
+    }
+}
+
+impl PolicyStatement {
+    /// Creates and compiles a new statement of the given policy,
+    /// at the given index of its statements.
+    pub(crate) fn new(
+        policy_id: &str,
+        index: usize,
+        version: PolicyVersion,
+        sid: Option<String>,
+        effect: PolicyEffect,
+        targets: PolicyTargets,
+        conditions: Value,
+    ) -> Result<Self, Error> {
+        let targets = targets.validate()?;
+
+        // Version 1 statements are cached under the policy id, version 2 ones under the id
+        // and their index: both are flushed by the storage when the policy is updated.
+        let cache_key = if policy_id.is_empty() || version == PolicyVersion::Version1 {
+            policy_id.to_string()
+        } else {
+            cache::statement_key(policy_id, index)
+        };
+
+        // Version 1 globs keep their legacy (unanchored) matching, without policy variables.
+        let compiled_policy = Compiler::get_instance().compile(
+            &cache_key,
+            &targets,
+            version != PolicyVersion::Version1,
+            Condition::from_value(&conditions, version != PolicyVersion::Version1)?,
+        )?;
+
+        Ok(PolicyStatement {
+            sid,
+            version,
+            effect,
+            targets,
+            conditions,
+            compiled_policy,
+        })
+    }
+
+    /// Builds a statement of a version 2 document from its JSON value
+    /// (with Sid, Effect, Action or NotAction, Resource or NotResource and Condition fields).
+    pub(crate) fn from_value(policy_id: &str, index: usize, value: &Value) -> Result<Self, Error> {
+        let statement = value
+            .as_object()
+            .ok_or_else(|| Error::from("Statement is not an object"))?;
+
+        let sid = match statement.get("Sid") {
+            None | Some(Value::Null) => Option::None,
+            Some(Value::String(sid)) if !sid.is_empty() => Option::Some(sid.clone()),
+            Some(_) => return Err(Error::from("Statement.Sid is not a valid string")),
+        };
+
+        let effect = match statement.get("Effect") {
+            Some(Value::String(effect)) => PolicyEffect::try_from(effect)?,
+            _ => return Err(Error::from("Statement.Effect is not a valid effect")),
+        };
+
+        let targets = PolicyTargets::new(
+            string_list(statement, "Action")?,
+            string_list(statement, "Resource")?,
+        )
+        .not_actions(string_list(statement, "NotAction")?)
+        .not_resources(string_list(statement, "NotResource")?);
+
+        Self::new(
+            policy_id,
+            index,
+            PolicyVersion::Version2,
+            sid,
+            effect,
+            targets,
+            statement.get("Condition").cloned().unwrap_or(Value::Null),
+        )
+    }
+
+    /// Gets the actions and resources of the statement.
+    pub fn targets(&self) -> &PolicyTargets {
+        &self.targets
+    }
+
+    /// Gets the conditions which cannot be decided with the given params:
+    /// the ones on keys missing from the params, except the IfExists ones,
+    /// and the ones on values with unresolved policy variables.
+    /// Condition blocks (AnyOf, AllOf, Not) are returned whole, if undecided.
+    pub fn residual_conditions(&self, params: &Value) -> Value {
+        let unresolved_str = |value: &Value| match value.as_str() {
+            Some(value) if self.version != PolicyVersion::Version1 => {
+                variables::has_variables(value) && !variables::is_resolvable(value, params)
+            }
+            _ => false,
+        };
+        let unresolved = |value: &Value| match value.as_array() {
+            Some(operands) => operands.iter().any(&unresolved_str),
+            None => unresolved_str(value),
+        };
+
+        let conditions = match self.conditions.as_object() {
+            Some(conditions) => conditions,
+            None => return Value::Null,
+        };
+
+        let mut residual = Map::new();
+        for (operator, block) in conditions {
+            if operator == "Script" || operator.ends_with("IfExists") {
+                continue;
+            }
+
+            if let "AnyOf" | "AllOf" | "Not" = operator.as_str() {
+                let mut single = Map::new();
+                single.insert(operator.clone(), block.clone());
+                let decided = Condition::from_value(
+                    &Value::Object(single),
+                    self.version != PolicyVersion::Version1,
+                )
+                .map(|c| c.iter().all(|c| c.is_decidable(params)))
+                .unwrap_or(true);
+                if !decided {
+                    residual.insert(operator.clone(), block.clone());
+                }
+
+                continue;
+            }
+
+            let block: Map<String, Value> = block
+                .as_object()
+                .into_iter()
+                .flatten()
+                .filter(|(key, value)| params.get(key.as_str()).is_none() || unresolved(value))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect();
+
+            if !block.is_empty() {
+                residual.insert(operator.clone(), Value::Object(block));
+            }
+        }
+
+        Value::Object(residual)
+    }
+
+    /// Matches the statement against a request.
+    /// On partial matching, the conditions which cannot be decided with the
+    /// request params are returned in the partial policy, instead of not matching.
+    fn match_request<T, S>(&self, request: &AllowedRequest<T, S>, partial: bool) -> MatchResult
+    where
+        T: ToString + Display,
+        S: ToString + Display + Debug,
+    {
+        let mut result = MatchResult::new();
+        let compiled = &self.compiled_policy;
+
+        if let Some(action) = request.action {
+            if let Some(is_match) = compiled.match_action(&action, Some(request.params)) {
+                result.update_action(is_match);
+            }
+        }
+
+        if compiled.all_resources {
+            result.update_resource(true);
+        } else if request.resource.is_some() {
+            if let Some(is_match) = compiled.match_resource(request.resource, Some(request.params))
+            {
+                result.update_resource(is_match);
+            }
+        }
+
+        if compiled.no_conditions {
+            result.update_conditions(true);
+        } else if !partial {
+            result.update_conditions(compiled.match_conditions(request.params));
+        } else {
+            match compiled.match_conditions_partial(request.params) {
+                Some(is_match) => result.update_conditions(is_match),
+                None => result.update_residual_conditions(self.residual_conditions(request.params)),
+            }
+        }
+
+        result._update(self);
+        result
+    }
+
+    /// Whether the statement could match the given action and resource,
+    /// using its compiled regexes and regardless of its conditions.
+    /// A missing resource, or a policy variable (even negated), is considered a possible match.
+    pub fn may_match(&self, action: &str, resource: Option<&str>) -> bool {
+        self.compiled_policy
+            .match_action(&action, Option::None)
+            .unwrap_or(true)
+            && self
+                .compiled_policy
+                .match_resource(resource, Option::None)
+                .unwrap_or(true)
+    }
+}
+
+impl ToJson for PolicyStatement {
+    fn to_json(&self) -> Map<String, Value> {
+        let mut result = Map::new();
+        if let Some(sid) = &self.sid {
+            result.insert(String::from("Sid"), Value::from(sid.as_str()));
+        }
+
+        result.insert(String::from("Effect"), Value::from(&self.effect));
+        if self.targets.actions_negated() {
+            result.insert(
+                String::from("NotAction"),
+                Value::from(self.targets.not_actions.as_slice()),
+            );
+        } else {
+            result.insert(
+                String::from("Action"),
+                Value::from(self.targets.actions.as_slice()),
+            );
+        }
+
+        if self.targets.resources_negated() {
+            result.insert(
+                String::from("NotResource"),
+                Value::from(self.targets.not_resources.as_slice()),
+            );
+        } else {
+            result.insert(
+                String::from("Resource"),
+                Value::from(self.targets.resources.as_slice()),
+            );
+        }
+
+        result.insert(String::from("Condition"), self.conditions.clone());
+
+        result
+    }
+}
+
+impl Eq for PolicyStatement {}
+impl PartialEq for PolicyStatement {
+    fn eq(&self, other: &Self) -> bool {
+        self.sid == other.sid
+            && self.effect == other.effect
+            && self.targets == other.targets
+            && self.conditions == other.conditions
+    }
+}
+
+impl Hash for PolicyStatement {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.sid.hash(state);
+        self.targets.actions.hash(state);
+        self.targets.not_actions.hash(state);
+        self.targets.resources.hash(state);
+        self.targets.not_resources.hash(state);
+    }
+}
+
+impl MatchablePolicy for PolicyStatement {
+    fn get_effect(&self) -> PolicyEffect {
+        self.effect
+    }
+
+    fn matching<T, S>(&self, request: &AllowedRequest<T, S>) -> MatchResult
+    where
+        T: ToString + Display,
+        S: ToString + Display + Debug,
+    {
+        self.match_request(request, false)
+    }
+
+    fn matching_partial<T, S>(&self, request: &AllowedRequest<T, S>) -> MatchResult
+    where
+        T: ToString + Display,
+        S: ToString + Display + Debug,
+    {
+        self.match_request(request, true)
+    }
+
+    fn get_actions(&self) -> &[String] {
+        self.targets.actions.as_slice()
+    }
+
+    fn get_not_actions(&self) -> &[String] {
+        self.targets.not_actions.as_slice()
+    }
+
+    fn get_resources(&self) -> &[String] {
+        self.targets.resources.as_slice()
+    }
+
+    fn get_not_resources(&self) -> &[String] {
+        self.targets.not_resources.as_slice()
+    }
+
+    fn get_conditions(&self) -> &Value {
+        &self.conditions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::identity::role::AllowedRequest;
+    use crate::policy::policy::{MatchablePolicy, ToJson};
+    use crate::policy::statement::PolicyStatement;
+    use crate::policy::PolicyEffect;
+    use serde_json::json;
+
+    #[test]
+    fn statement_should_be_built_from_value() {
+        let statement = PolicyStatement::from_value(
+            "TestStatementPolicy",
+            0,
+            &json!({
+                "Sid": "ReadObjects",
+                "Effect": "Allow",
+                "Action": "s3:GetObject",
+                "Resource": ["urn:bucket:*"],
+            }),
+        )
+        .unwrap();
+
+        assert_eq!(statement.sid, Some("ReadObjects".to_string()));
+        assert_eq!(statement.effect, PolicyEffect::Allow);
+        assert_eq!(statement.get_actions(), &["s3:GetObject".to_string()]);
+        assert_eq!(
+            statement.to_value(),
+            json!({
+                "Sid": "ReadObjects",
+                "Effect": "ALLOW",
+                "Action": ["s3:GetObject"],
+                "Resource": ["urn:bucket:*"],
+                "Condition": null,
+            })
+        );
+
+        let request = AllowedRequest {
+            action: Some(&"s3:GetObject"),
+            resource: Some(&"urn:bucket:logs"),
+            params: &json!({}),
+        };
+        let result = statement.matching(&request);
+        assert_eq!(result.is_match(), true);
+        assert_eq!(result.is_full(), true);
+    }
+
+    #[test]
+    fn invalid_statements_should_not_be_built() {
+        for value in [
+            json!("statement"),
+            json!({ "Action": "s3:GetObject" }),
+            json!({ "Effect": "Maybe", "Action": "s3:GetObject" }),
+            json!({ "Effect": "Allow" }),
+            json!({ "Effect": "Allow", "Action": [1] }),
+            json!({ "Sid": 1, "Effect": "Allow", "Action": "s3:GetObject" }),
+        ] {
+            assert_eq!(
+                PolicyStatement::from_value("TestInvalidStatement", 0, &value).is_err(),
+                true
+            );
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use crate::err::Error;
+use crate::utils::glob_to_regex;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{Map, Value};
+
+/// Prefix of the context keys provided by zephir itself.
+/// Request params with this prefix are discarded before the evaluation.
+pub const CONTEXT_PREFIX: &str = "zephir:";
+
+/// The id of the identity the request is evaluated for.
+pub const SUBJECT: &str = "zephir:subject";
+
+/// The time of the evaluation (RFC 3339), used by the relative date conditions (ex: "now-15m").
+pub const CURRENT_TIME: &str = "zephir:current_time";
+
+/// Prefix of the identity attributes (ex: "zephir:identity.department").
+pub const IDENTITY_ATTRIBUTE_PREFIX: &str = "zephir:identity.";
+
+/// Prefix of the attributes of the group whose policies are evaluated
+/// (ex: "zephir:group.cost_center").
+pub const GROUP_ATTRIBUTE_PREFIX: &str = "zephir:group.";
+
+/// Whether the value contains `${name}` variables.
+pub fn has_variables(value: &str) -> bool {
+    value.contains("${")
+}
+
+/// Gets the names of the variables of a template.
+/// Returns an error if a variable is not closed.
+pub fn names(template: &str) -> Result<Vec<&str>, Error> {
+    let mut names = vec![];
+    let mut rest = template;
+    while let Some(start) = rest.find("${") {
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| Error::from(format!(r#"Unclosed variable in "{}""#, template)))?
+            + start;
+
+        names.push(&rest[start + 2..end]);
+        rest = &rest[end + 1..];
+    }
+
+    Ok(names)
+}
+
+/// Gets the value of a variable from the evaluation context (the request params).
+/// Only strings, numbers and booleans can be used as variable values.
+pub fn value_of(params: &Value, name: &str) -> Option<String> {
+    match params.get(name)? {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        Value::Bool(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/// Replaces the variables of a template with the given values,
+/// transformed with the escape function.
+/// Returns None if a variable is not closed or has no value.
+fn replace<V, E>(template: &str, value_of: V, escape: E) -> Option<String>
+where
+    V: Fn(&str) -> Option<String>,
+    E: Fn(&str) -> String,
+{
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("${") {
+        let end = rest[start..].find('}')? + start;
+        let value = value_of(&rest[start + 2..end])?;
+
+        result.push_str(&rest[..start]);
+        result.push_str(&escape(&value));
+        rest = &rest[end + 1..];
+    }
+
+    result.push_str(rest);
+    Some(result)
+}
+
+/// Resolves the variables of a string template (ex: a condition value).
+pub fn resolve(template: &str, params: &Value) -> Option<String> {
+    replace(
+        template,
+        |name| value_of(params, name),
+        |s: &str| s.to_string(),
+    )
+}
+
+/// Resolves the variables of a glob: values are escaped,
+/// so that they are matched literally.
+pub fn resolve_glob(template: &str, params: &Value) -> Option<String> {
+    replace(
+        template,
+        |name| value_of(params, name),
+        glob_to_regex::escape,
+    )
+}
+
+/// Gets the glob surrounding the variables of a template, used to validate it.
+pub fn strip(template: &str) -> Result<String, Error> {
+    replace(template, |_| Some(String::new()), |s: &str| s.to_string())
+        .ok_or_else(|| Error::invalid_glob(template, "unclosed variable"))
+}
+
+/// Whether all the variables of the template can be resolved with the given params.
+pub fn is_resolvable(template: &str, params: &Value) -> bool {
+    match names(template) {
+        Ok(names) => names.iter().all(|name| value_of(params, name).is_some()),
+        Err(_) => false,
+    }
+}
+
+fn insert_attributes(
+    context: &mut Map<String, Value>,
+    prefix: &str,
+    attributes: &Map<String, Value>,
+) {
+    for (name, value) in attributes {
+        context.insert(format!("{}{}", prefix, name), value.clone());
+    }
+}
+
+/// Gets the time of the evaluation from the context, or the actual current time if missing.
+pub fn current_time(params: &Value) -> DateTime<Utc> {
+    params
+        .get(CURRENT_TIME)
+        .and_then(Value::as_str)
+        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        .map(|d| d.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now)
+}
+
+/// Builds the evaluation context of a request for the given subject:
+/// the request params, with the zephir context keys replaced by their actual values
+/// (the subject id, the identity attributes and the given evaluation time).
+pub fn context_params(
+    params: &Value,
+    subject: &str,
+    attributes: &Map<String, Value>,
+    now: DateTime<Utc>,
+) -> Value {
+    let mut context: Map<String, Value> = params
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(key, _)| !key.starts_with(CONTEXT_PREFIX))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+
+    context.insert(String::from(SUBJECT), Value::from(subject));
+    context.insert(
+        String::from(CURRENT_TIME),
+        Value::from(now.to_rfc3339_opts(SecondsFormat::Millis, true)),
+    );
+    insert_attributes(&mut context, IDENTITY_ATTRIBUTE_PREFIX, attributes);
+
+    Value::Object(context)
+}
+
+/// Adds the attributes of a group to an evaluation context (see `context_params`).
+pub fn group_context_params(context: &Value, attributes: &Map<String, Value>) -> Value {
+    let mut context = context.as_object().cloned().unwrap_or_default();
+    insert_attributes(&mut context, GROUP_ATTRIBUTE_PREFIX, attributes);
+
+    Value::Object(context)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::policy::variables::{
+        context_params, current_time, group_context_params, names, resolve, resolve_glob, strip,
+    };
+    use chrono::{TimeZone, Utc};
+    use serde_json::{json, Map};
+
+    #[test]
+    fn variables_should_be_resolved_from_params() {
+        let params = json!({ "zephir:subject": "alice", "team": "a*", "level": 3 });
+        assert_eq!(
+            resolve("home/${zephir:subject}/${level}", &params),
+            Some("home/alice/3".to_string())
+        );
+        assert_eq!(
+            resolve_glob("team-${team}", &params),
+            Some("team-a\\*".to_string())
+        );
+        assert_eq!(resolve("${missing}", &params), None);
+        assert_eq!(resolve("${team", &params), None);
+    }
+
+    #[test]
+    fn variable_names_should_be_parsed() {
+        assert_eq!(names("a${b}c${d}").unwrap(), vec!["b", "d"]);
+        assert!(names("a${b").is_err());
+        assert_eq!(strip("urn:${a}:*").unwrap(), "urn::*");
+    }
+
+    #[test]
+    fn context_keys_should_not_be_overridden_by_the_request() {
+        let params = context_params(
+            &json!({ "zephir:subject": "admin", "zephir:other": 1, "ip": "10.0.0.1" }),
+            "alice",
+            &Map::new(),
+            Utc.with_ymd_and_hms(2022, 3, 1, 10, 30, 0).unwrap(),
+        );
+
+        assert_eq!(
+            params,
+            json!({
+                "zephir:subject": "alice",
+                "zephir:current_time": "2022-03-01T10:30:00.000Z",
+                "ip": "10.0.0.1",
+            })
+        );
+        assert_eq!(
+            current_time(&params),
+            Utc.with_ymd_and_hms(2022, 3, 1, 10, 30, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn attributes_should_be_merged_into_the_context() {
+        let identity = json!({ "department": "sales", "clearance": 2 });
+        let group = json!({ "cost_center": "cc-42" });
+        let params = context_params(
+            &json!({ "zephir:identity.department": "admin" }),
+            "alice",
+            identity.as_object().unwrap(),
+            Utc.with_ymd_and_hms(2022, 3, 1, 10, 30, 0).unwrap(),
+        );
+        let params = group_context_params(&params, group.as_object().unwrap());
+
+        assert_eq!(
+            params,
+            json!({
+                "zephir:subject": "alice",
+                "zephir:current_time": "2022-03-01T10:30:00.000Z",
+                "zephir:identity.department": "sales",
+                "zephir:identity.clearance": 2,
+                "zephir:group.cost_center": "cc-42",
+            })
+        );
+    }
+}
@@ -0,0 +1,340 @@
+use crate::err::Error;
+use crate::policy::policy::{CompletePolicy, MatchablePolicy};
+use crate::storage::{Storage, MAX_COLLECTED_POLICIES};
+use crate::utils::glob_to_regex;
+use pcre2::bytes::Regex;
+
+/// Sort order of the list operations.
+/// Items are always sorted by id.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+impl SortOrder {
+    pub(super) fn to_sql(self) -> &'static str {
+        match self {
+            SortOrder::Ascending => "ASC",
+            SortOrder::Descending => "DESC",
+        }
+    }
+
+    /// The comparison operator selecting the ids after the cursor.
+    pub(super) fn cursor_operator(self) -> &'static str {
+        match self {
+            SortOrder::Ascending => ">",
+            SortOrder::Descending => "<",
+        }
+    }
+}
+
+/// Options of the list operations.
+///
+/// Lists are paginated through a cursor: the id of the last item
+/// of the previous page.
+#[derive(Clone, Debug)]
+pub struct ListOptions {
+    pub prefix: Option<String>,
+    pub cursor: Option<String>,
+    pub limit: usize,
+    pub order: SortOrder,
+}
+
+impl Default for ListOptions {
+    fn default() -> Self {
+        ListOptions {
+            prefix: Option::None,
+            cursor: Option::None,
+            limit: 50,
+            order: SortOrder::Ascending,
+        }
+    }
+}
+
+impl ListOptions {
+    /// Whether the given id should be included according to the prefix and the cursor.
+    pub(super) fn accepts(&self, id: &str) -> bool {
+        if let Some(prefix) = &self.prefix {
+            if !id.starts_with(prefix.as_str()) {
+                return false;
+            }
+        }
+
+        match (&self.cursor, self.order) {
+            (Option::None, _) => true,
+            (Some(cursor), SortOrder::Ascending) => id > cursor.as_str(),
+            (Some(cursor), SortOrder::Descending) => id < cursor.as_str(),
+        }
+    }
+
+    /// Gets the prefix as a LIKE pattern, escaping the wildcards with a backslash.
+    pub(super) fn like_prefix(&self) -> Option<String> {
+        self.prefix.as_deref().map(like_prefix)
+    }
+
+    /// Gets the number of rows to be fetched to detect whether a next page exists.
+    pub(super) fn fetch_limit(&self) -> i64 {
+        self.limit as i64 + 1
+    }
+}
+
+/// Escapes the given prefix to be used as a LIKE pattern.
+pub(super) fn like_prefix(prefix: &str) -> String {
+    let mut pattern = String::with_capacity(prefix.len() + 1);
+    for c in prefix.chars() {
+        if c == '%' || c == '_' || c == '\\' {
+            pattern.push('\\');
+        }
+
+        pattern.push(c);
+    }
+
+    pattern.push('%');
+    pattern
+}
+
+/// A page of a list operation.
+/// `next_cursor` is set if more items are available.
+#[derive(Clone, Debug)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// Builds a page from at most `limit + 1` rows.
+    /// The exceeding row (if any) is discarded and signals the presence of a next page.
+    pub(super) fn from_rows<F>(mut rows: Vec<T>, limit: usize, id: F) -> Self
+    where
+        F: Fn(&T) -> &str,
+    {
+        let next_cursor = if rows.len() > limit {
+            rows.truncate(limit);
+            rows.last().map(|r| id(r).to_string())
+        } else {
+            Option::None
+        };
+
+        Page {
+            items: rows,
+            next_cursor,
+        }
+    }
+}
+
+/// Loads all the policies (linked and inline) accepted by the given function, page by page.
+/// Fails if the storage holds more than MAX_COLLECTED_POLICIES policies.
+pub(super) async fn collect_policies<F>(
+    storage: &dyn Storage,
+    mut accept: F,
+) -> Result<Vec<CompletePolicy>, Error>
+where
+    F: FnMut(&CompletePolicy) -> bool,
+{
+    let mut policies = vec![];
+    let mut scanned = 0;
+    for inline in [false, true] {
+        let mut options = ListOptions {
+            limit: 500,
+            ..ListOptions::default()
+        };
+
+        loop {
+            let page = if inline {
+                storage.list_inline_policies(&options).await?
+            } else {
+                storage.list_policies(&options).await?
+            };
+
+            scanned += page.items.len();
+            if scanned > MAX_COLLECTED_POLICIES {
+                return Err(Error::from(format!(
+                    "Cannot load more than {} policies",
+                    MAX_COLLECTED_POLICIES
+                )));
+            }
+
+            policies.extend(page.items.into_iter().filter(|p| accept(p)));
+
+            match page.next_cursor {
+                Option::None => break,
+                cursor => options.cursor = cursor,
+            }
+        }
+    }
+
+    Ok(policies)
+}
+
+/// Filters policies by the action and resource patterns they mention.
+/// Filters are globs, matched against each pattern of the policy.
+#[derive(Default)]
+pub struct PolicyFilter {
+    action: Option<Regex>,
+    resource: Option<Regex>,
+}
+
+impl PolicyFilter {
+    /// Creates a filter from the given globs.
+    /// Fails if a glob cannot be parsed.
+    pub fn new(action: Option<&str>, resource: Option<&str>) -> Result<Self, Error> {
+        Ok(PolicyFilter {
+            action: action.map(glob_to_regex::from_str).transpose()?,
+            resource: resource.map(glob_to_regex::from_str).transpose()?,
+        })
+    }
+
+    /// Whether the filter accepts every policy.
+    pub fn is_empty(&self) -> bool {
+        self.action.is_none() && self.resource.is_none()
+    }
+
+    /// Whether a statement of the given policy mentions the filtered action and resource,
+    /// in its positive or negated lists.
+    pub fn matches(&self, policy: &CompletePolicy) -> bool {
+        let mentions = |filter: &Option<Regex>, patterns: &[String]| match filter {
+            Option::None => true,
+            Option::Some(regex) => patterns
+                .iter()
+                .any(|p| glob_to_regex::matches_whole(regex, p)),
+        };
+
+        policy.statements().iter().any(|s| {
+            (mentions(&self.action, s.get_actions()) || mentions(&self.action, s.get_not_actions()))
+                && (mentions(&self.resource, s.get_resources())
+                    || mentions(&self.resource, s.get_not_resources()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::policy::{PolicyEffect, PolicyVersion};
+    use crate::storage::list::{
+        collect_policies, like_prefix, ListOptions, Page, PolicyFilter, SortOrder,
+    };
+    use crate::storage::{MemoryStorage, Storage, MAX_COLLECTED_POLICIES};
+    use crate::zephir_policy;
+
+    #[test]
+    fn like_prefix_should_escape_wildcards() {
+        assert_eq!(like_prefix("abc"), "abc%");
+        assert_eq!(like_prefix("a_b%c\\"), "a\\_b\\%c\\\\%");
+    }
+
+    #[test]
+    fn list_options_should_filter_by_prefix_and_cursor() {
+        let options = ListOptions {
+            prefix: Some("Test".to_string()),
+            cursor: Some("TestB".to_string()),
+            ..ListOptions::default()
+        };
+
+        assert_eq!(options.accepts("TestA"), false);
+        assert_eq!(options.accepts("TestB"), false);
+        assert_eq!(options.accepts("TestC"), true);
+        assert_eq!(options.accepts("OtherC"), false);
+
+        let options = ListOptions {
+            order: SortOrder::Descending,
+            ..options
+        };
+
+        assert_eq!(options.accepts("TestA"), true);
+        assert_eq!(options.accepts("TestC"), false);
+    }
+
+    #[test]
+    fn page_should_be_built_from_rows() {
+        let page = Page::from_rows(vec!["a", "b", "c"], 2, |r| *r);
+        assert_eq!(page.items, vec!["a", "b"]);
+        assert_eq!(page.next_cursor, Some("b".to_string()));
+
+        let page = Page::from_rows(vec!["a", "b"], 2, |r| *r);
+        assert_eq!(page.items, vec!["a", "b"]);
+        assert_eq!(page.next_cursor, None);
+    }
+
+    #[test]
+    fn policy_filter_should_match_mentioned_patterns() {
+        let policy = zephir_policy!(
+            "TestPolicyFilter",
+            PolicyVersion::Version1,
+            PolicyEffect::Allow,
+            vec!["s3:GetObject", "ec2:*"],
+            vec!["urn:bucket:*"]
+        )
+        .unwrap();
+
+        assert_eq!(PolicyFilter::default().matches(&policy), true);
+        assert_eq!(
+            PolicyFilter::new(Some("s3:*"), None)
+                .unwrap()
+                .matches(&policy),
+            true
+        );
+        assert_eq!(
+            PolicyFilter::new(Some("ec2:*"), None)
+                .unwrap()
+                .matches(&policy),
+            true
+        );
+        assert_eq!(
+            PolicyFilter::new(Some("iam:*"), None)
+                .unwrap()
+                .matches(&policy),
+            false
+        );
+        assert_eq!(
+            PolicyFilter::new(Some("GetObject"), None)
+                .unwrap()
+                .matches(&policy),
+            false
+        );
+        assert_eq!(
+            PolicyFilter::new(Some("s3:*"), Some("urn:bucket:*"))
+                .unwrap()
+                .matches(&policy),
+            true
+        );
+        assert_eq!(
+            PolicyFilter::new(Some("s3:*"), Some("urn:table:*"))
+                .unwrap()
+                .matches(&policy),
+            false
+        );
+    }
+
+    #[async_std::test]
+    async fn collected_policies_should_be_bounded() {
+        let storage = MemoryStorage::new();
+        for i in 0..MAX_COLLECTED_POLICIES {
+            let policy = zephir_policy!(
+                &format!("CollectPolicy{:05}", i),
+                PolicyVersion::Version1,
+                PolicyEffect::Allow,
+                vec!["s3:GetObject"]
+            )
+            .unwrap();
+            storage.save_policy(&policy).await.unwrap();
+        }
+
+        let policies = collect_policies(&storage, |p| p.id.ends_with('0'))
+            .await
+            .unwrap();
+        assert_eq!(policies.len(), MAX_COLLECTED_POLICIES / 10);
+
+        let policy = zephir_policy!(
+            "CollectPolicyExceeding",
+            PolicyVersion::Version1,
+            PolicyEffect::Allow,
+            vec!["s3:GetObject"]
+        )
+        .unwrap();
+        storage.save_policy(&policy).await.unwrap();
+        collect_policies(&storage, |_| true)
+            .await
+            .expect_err("Policies exceeding the limit should not be loaded");
+    }
+}
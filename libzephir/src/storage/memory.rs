@@ -0,0 +1,897 @@
+use crate::compiler::compiler::cache;
+use crate::err::Error;
+use crate::identity::group::Group;
+use crate::identity::identity::Identity;
+use crate::identity::role::Role;
+use crate::policy::policy::CompletePolicy;
+use crate::policy::policy_set::PolicySetTrait;
+use crate::storage::types::{group_policy_id, identity_policy_id, is_inline_policy_id};
+use crate::storage::{
+    AttachmentCounts, AttachmentKind, ListOptions, MigrationStatus, Page, PolicyAttachment,
+    PolicyReferences, SortOrder, Storage,
+};
+use async_trait::async_trait;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Stored representation of an identity or a group.
+/// Policies and members are referenced by id, as in a relational storage.
+#[derive(Default)]
+struct StoredSubject {
+    policy_id: Option<String>,
+    linked_policies: Vec<String>,
+    identities: Vec<String>,
+    attributes: Map<String, Value>,
+}
+
+#[derive(Default)]
+struct MemoryData {
+    policies: BTreeMap<String, CompletePolicy>,
+    identities: BTreeMap<String, StoredSubject>,
+    groups: BTreeMap<String, StoredSubject>,
+}
+
+impl MemoryData {
+    fn load_identity(&self, id: &str) -> Option<Identity> {
+        let stored = self.identities.get(id)?;
+        let inline_policy = stored
+            .policy_id
+            .as_ref()
+            .and_then(|p| self.policies.get(p))
+            .cloned();
+
+        let mut identity =
+            Identity::new(id, inline_policy).set_attributes(stored.attributes.clone());
+        for policy in stored
+            .linked_policies
+            .iter()
+            .filter_map(|p| self.policies.get(p))
+        {
+            identity = identity.add_policy(policy.clone());
+        }
+
+        Some(identity)
+    }
+
+    fn load_group(&self, id: &str, load_identities: bool) -> Option<Group> {
+        let stored = self.groups.get(id)?;
+        let inline_policy = stored
+            .policy_id
+            .as_ref()
+            .and_then(|p| self.policies.get(p))
+            .cloned();
+
+        let mut group = Group::new(id, inline_policy).set_attributes(stored.attributes.clone());
+        for policy in stored
+            .linked_policies
+            .iter()
+            .filter_map(|p| self.policies.get(p))
+        {
+            group = group.add_policy(policy.clone());
+        }
+
+        if load_identities {
+            for identity in stored
+                .identities
+                .iter()
+                .filter_map(|i| self.load_identity(i))
+            {
+                group = group.add_identity(identity);
+            }
+        }
+
+        Some(group)
+    }
+
+    /// Gets the ids of the subjects linking the given policy.
+    fn linked_subjects<'a>(
+        subjects: &'a BTreeMap<String, StoredSubject>,
+        policy_id: &'a str,
+    ) -> impl DoubleEndedIterator<Item = &'a String> + 'a {
+        subjects
+            .iter()
+            .filter(move |(_, s)| s.linked_policies.iter().any(|p| p == policy_id))
+            .map(|(id, _)| id)
+    }
+
+    /// Gets the identities and groups linking the given policy.
+    fn references(&self, policy_id: &str) -> PolicyReferences {
+        PolicyReferences {
+            identities: Self::linked_subjects(&self.identities, policy_id)
+                .cloned()
+                .collect(),
+            groups: Self::linked_subjects(&self.groups, policy_id)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Checks that all the referenced ids exist, as enforced by the foreign keys
+    /// of the SQL storages.
+    fn check_references<'a, T>(
+        table: &str,
+        rows: &BTreeMap<String, T>,
+        ids: impl IntoIterator<Item = &'a String>,
+    ) -> Result<(), Error> {
+        match ids.into_iter().find(|id| !rows.contains_key(*id)) {
+            Option::None => Ok(()),
+            Option::Some(id) => Err(Error::from(format!(
+                r#"Foreign key violation: {} "{}" does not exist"#,
+                table, id
+            ))),
+        }
+    }
+
+    /// Removes a policy, unlinking it from all the identities and groups.
+    fn remove_policy(&mut self, id: &str) -> Result<(), Error> {
+        if self
+            .identities
+            .values()
+            .chain(self.groups.values())
+            .any(|s| s.policy_id.as_deref() == Some(id))
+        {
+            return Err(Error::from(format!(
+                r#"Policy "{}" is used as inline policy"#,
+                id
+            )));
+        }
+
+        self.policies.remove(id);
+        for subject in self.identities.values_mut().chain(self.groups.values_mut()) {
+            subject.linked_policies.retain(|p| p != id);
+        }
+
+        cache::flush_policy(id);
+        Ok(())
+    }
+
+    /// Gets the identities inheriting the given policy, with the groups they inherit it from.
+    fn inherited_identities(&self, policy_id: &str) -> BTreeMap<String, Vec<String>> {
+        let mut inherited: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for group_id in Self::linked_subjects(&self.groups, policy_id) {
+            for identity_id in &self.groups[group_id].identities {
+                inherited
+                    .entry(identity_id.clone())
+                    .or_default()
+                    .push(group_id.clone());
+            }
+        }
+
+        inherited
+    }
+
+    fn save_inline_policy(
+        &mut self,
+        policy_id: String,
+        policy: Option<&mut CompletePolicy>,
+    ) -> Option<String> {
+        cache::flush_policy(&policy_id);
+        match policy {
+            Option::None => {
+                self.policies.remove(&policy_id);
+                Option::None
+            }
+            Option::Some(policy) => {
+                policy.id = policy_id.clone();
+                self.policies.insert(policy_id.clone(), policy.clone());
+                Option::Some(policy_id)
+            }
+        }
+    }
+}
+
+/// Lists the ids of a map, according to the list options.
+fn list_ids<'a, I>(ids: I, options: &ListOptions) -> Page<String>
+where
+    I: DoubleEndedIterator<Item = &'a String> + 'a,
+{
+    let ids: Box<dyn Iterator<Item = &'a String> + 'a> = match options.order {
+        SortOrder::Ascending => Box::new(ids),
+        SortOrder::Descending => Box::new(ids.rev()),
+    };
+
+    let rows = ids
+        .filter(|id| options.accepts(id))
+        .take(options.limit + 1)
+        .cloned()
+        .collect();
+
+    Page::from_rows(rows, options.limit, |id| id.as_str())
+}
+
+/// Storage backend keeping all the data in memory.
+///
+/// Nothing is persisted: all the data is lost when the storage is dropped.
+/// Useful for tests and when embedding zephir into another application.
+#[derive(Default)]
+pub struct MemoryStorage {
+    data: RwLock<MemoryData>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn read(&self) -> Result<RwLockReadGuard<'_, MemoryData>, Error> {
+        self.data.read().map_err(|e| Error::from(e.to_string()))
+    }
+
+    fn write(&self) -> Result<RwLockWriteGuard<'_, MemoryData>, Error> {
+        self.data.write().map_err(|e| Error::from(e.to_string()))
+    }
+
+    fn list_policies_by_kind(
+        &self,
+        options: &ListOptions,
+        inline: bool,
+    ) -> Result<Page<CompletePolicy>, Error> {
+        let data = self.read()?;
+        let page = list_ids(
+            data.policies
+                .keys()
+                .filter(move |id| is_inline_policy_id(id) == inline),
+            options,
+        );
+
+        Ok(Page {
+            items: page
+                .items
+                .iter()
+                .filter_map(|id| data.policies.get(id))
+                .cloned()
+                .collect(),
+            next_cursor: page.next_cursor,
+        })
+    }
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn ping(&self) -> Result<(), Error> {
+        drop(self.read()?);
+        Ok(())
+    }
+
+    async fn migrate_up(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn migrate_down(&self, _target: Option<i64>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn migration_status(&self) -> Result<Vec<MigrationStatus>, Error> {
+        Ok(vec![])
+    }
+
+    async fn find_policy(&self, id: &str) -> Result<Option<CompletePolicy>, Error> {
+        Ok(self.read()?.policies.get(id).cloned())
+    }
+
+    async fn save_policy(&self, policy: &CompletePolicy) -> Result<(), Error> {
+        self.write()?
+            .policies
+            .insert(policy.id.clone(), policy.clone());
+
+        cache::flush_policy(&policy.id);
+        Ok(())
+    }
+
+    async fn delete_policy(&self, id: &str) -> Result<(), Error> {
+        self.write()?.remove_policy(id)
+    }
+
+    async fn delete_unlinked_policy(&self, id: &str) -> Result<PolicyReferences, Error> {
+        let mut data = self.write()?;
+        let references = data.references(id);
+        if references.is_empty() {
+            data.remove_policy(id)?;
+        }
+
+        Ok(references)
+    }
+
+    async fn list_policies(&self, options: &ListOptions) -> Result<Page<CompletePolicy>, Error> {
+        self.list_policies_by_kind(options, false)
+    }
+
+    async fn list_inline_policies(
+        &self,
+        options: &ListOptions,
+    ) -> Result<Page<CompletePolicy>, Error> {
+        self.list_policies_by_kind(options, true)
+    }
+
+    async fn find_policy_references(&self, id: &str) -> Result<PolicyReferences, Error> {
+        Ok(self.read()?.references(id))
+    }
+
+    async fn count_policy_attachments(&self, id: &str) -> Result<AttachmentCounts, Error> {
+        let data = self.read()?;
+        Ok(AttachmentCounts {
+            identities: MemoryData::linked_subjects(&data.identities, id).count() as i64,
+            groups: MemoryData::linked_subjects(&data.groups, id).count() as i64,
+            inherited_identities: data.inherited_identities(id).len() as i64,
+        })
+    }
+
+    async fn list_policy_attachments(
+        &self,
+        id: &str,
+        kind: AttachmentKind,
+        options: &ListOptions,
+    ) -> Result<Page<PolicyAttachment>, Error> {
+        let data = self.read()?;
+        let mut inherited = BTreeMap::new();
+        let page = match kind {
+            AttachmentKind::Identity => {
+                list_ids(MemoryData::linked_subjects(&data.identities, id), options)
+            }
+            AttachmentKind::Group => {
+                list_ids(MemoryData::linked_subjects(&data.groups, id), options)
+            }
+            AttachmentKind::InheritedIdentity => {
+                inherited = data.inherited_identities(id);
+                list_ids(inherited.keys(), options)
+            }
+        };
+
+        Ok(Page {
+            items: page
+                .items
+                .into_iter()
+                .map(|subject_id| PolicyAttachment {
+                    groups: inherited.remove(&subject_id).unwrap_or_default(),
+                    id: subject_id,
+                })
+                .collect(),
+            next_cursor: page.next_cursor,
+        })
+    }
+
+    async fn find_identity(&self, id: &str) -> Result<Option<Identity>, Error> {
+        Ok(self.read()?.load_identity(id))
+    }
+
+    async fn find_identities(&self, ids: &[String]) -> Result<Vec<Identity>, Error> {
+        let data = self.read()?;
+        Ok(ids.iter().filter_map(|id| data.load_identity(id)).collect())
+    }
+
+    async fn list_identities(&self, options: &ListOptions) -> Result<Page<Identity>, Error> {
+        let data = self.read()?;
+        let page = list_ids(data.identities.keys(), options);
+
+        Ok(Page {
+            items: page
+                .items
+                .iter()
+                .filter_map(|id| data.load_identity(id))
+                .collect(),
+            next_cursor: page.next_cursor,
+        })
+    }
+
+    async fn save_identity(&self, identity: &mut Identity) -> Result<(), Error> {
+        let mut data = self.write()?;
+        let linked_policies: Vec<String> = identity
+            .linked_policies()
+            .into_iter()
+            .map(|p| p.id.clone())
+            .collect();
+        MemoryData::check_references("policy", &data.policies, &linked_policies)?;
+
+        let policy_id = data.save_inline_policy(
+            identity_policy_id(&identity.id),
+            identity.inline_policy.as_mut(),
+        );
+
+        data.identities.insert(
+            identity.id.clone(),
+            StoredSubject {
+                policy_id,
+                linked_policies,
+                identities: vec![],
+                attributes: identity.attributes.clone(),
+            },
+        );
+
+        Ok(())
+    }
+
+    async fn delete_identity(&self, id: &str) -> Result<(), Error> {
+        let mut data = self.write()?;
+        data.identities.remove(id);
+        data.save_inline_policy(identity_policy_id(id), Option::None);
+        for group in data.groups.values_mut() {
+            group.identities.retain(|i| i != id);
+        }
+
+        Ok(())
+    }
+
+    async fn find_group(&self, id: &str) -> Result<Option<Group>, Error> {
+        Ok(self.read()?.load_group(id, true))
+    }
+
+    async fn list_groups(&self, options: &ListOptions) -> Result<Page<Group>, Error> {
+        let data = self.read()?;
+        let page = list_ids(data.groups.keys(), options);
+
+        Ok(Page {
+            items: page
+                .items
+                .iter()
+                .filter_map(|id| data.load_group(id, false))
+                .collect(),
+            next_cursor: page.next_cursor,
+        })
+    }
+
+    async fn find_groups_for_identity(
+        &self,
+        identity: &Identity,
+        load_identities: bool,
+    ) -> Result<Vec<Group>, Error> {
+        let data = self.read()?;
+        Ok(data
+            .groups
+            .iter()
+            .filter(|(_, g)| g.identities.contains(&identity.id))
+            .filter_map(|(id, _)| data.load_group(id, load_identities))
+            .collect())
+    }
+
+    async fn find_groups_for_identities(
+        &self,
+        ids: &[String],
+        load_identities: bool,
+    ) -> Result<HashMap<String, Vec<Group>>, Error> {
+        let data = self.read()?;
+        let mut groups: HashMap<String, Vec<Group>> = HashMap::new();
+        for (group_id, stored) in &data.groups {
+            for id in ids.iter().filter(|id| stored.identities.contains(*id)) {
+                if let Some(group) = data.load_group(group_id, load_identities) {
+                    groups.entry(id.clone()).or_default().push(group);
+                }
+            }
+        }
+
+        Ok(groups)
+    }
+
+    async fn save_group(&self, group: &mut Group) -> Result<(), Error> {
+        let mut data = self.write()?;
+        let linked_policies: Vec<String> = group
+            .linked_policies()
+            .into_iter()
+            .map(|p| p.id.clone())
+            .collect();
+        let identities: Vec<String> = group.identities.into_iter().map(|i| i.id.clone()).collect();
+        MemoryData::check_references("policy", &data.policies, &linked_policies)?;
+        MemoryData::check_references("identity", &data.identities, &identities)?;
+
+        let policy_id =
+            data.save_inline_policy(group_policy_id(&group.name), group.inline_policy.as_mut());
+
+        data.groups.insert(
+            group.name.clone(),
+            StoredSubject {
+                policy_id,
+                linked_policies,
+                identities,
+                attributes: group.attributes.clone(),
+            },
+        );
+
+        Ok(())
+    }
+
+    async fn delete_group(&self, id: &str) -> Result<(), Error> {
+        let mut data = self.write()?;
+        data.groups.remove(id);
+        data.save_inline_policy(group_policy_id(id), Option::None);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::identity::group::Group;
+    use crate::identity::identity::Identity;
+    use crate::identity::role::Role;
+    use crate::identity::subject::Subject;
+    use crate::policy::policy_set::PolicySetTrait;
+    use crate::policy::{PolicyEffect, PolicyVersion};
+    use crate::storage::{
+        AttachmentKind, ListOptions, MemoryStorage, PolicyFilter, SortOrder, Storage,
+        MAX_SCANNED_POLICIES,
+    };
+    use crate::zephir_policy;
+    use serde_json::json;
+
+    #[async_std::test]
+    async fn policies_can_be_saved_and_deleted() {
+        let storage = MemoryStorage::new();
+        let policy = zephir_policy!(
+            "MemoryStoragePolicy",
+            PolicyVersion::Version1,
+            PolicyEffect::Allow,
+            vec!["core:GetVersion"]
+        )
+        .unwrap();
+
+        storage.save_policy(&policy).await.unwrap();
+        let found = storage.find_policy("MemoryStoragePolicy").await.unwrap();
+        assert_eq!(found.is_some(), true);
+        assert_eq!(found.unwrap().id, "MemoryStoragePolicy");
+
+        storage.delete_policy("MemoryStoragePolicy").await.unwrap();
+        let found = storage.find_policy("MemoryStoragePolicy").await.unwrap();
+        assert_eq!(found.is_none(), true);
+    }
+
+    #[async_std::test]
+    async fn identities_should_be_loaded_with_policies() {
+        let storage = MemoryStorage::new();
+        let policy = zephir_policy!(
+            "MemoryStorageLinkedPolicy",
+            PolicyVersion::Version1,
+            PolicyEffect::Allow,
+            vec!["core:GetVersion"]
+        )
+        .unwrap();
+        storage.save_policy(&policy).await.unwrap();
+
+        let inline_policy = zephir_policy!(
+            "",
+            PolicyVersion::Version1,
+            PolicyEffect::Deny,
+            vec!["core:Delete"]
+        )
+        .unwrap();
+
+        let mut identity = Identity::new("MemoryStorageIdentity", None)
+            .set_inline_policy(inline_policy)
+            .set_attributes(
+                json!({ "department": "sales" })
+                    .as_object()
+                    .unwrap()
+                    .clone(),
+            )
+            .add_policy(policy);
+        storage.save_identity(&mut identity).await.unwrap();
+
+        let identity = storage
+            .find_identity("MemoryStorageIdentity")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(identity.linked_policies().len(), 1);
+        assert_eq!(identity.get_attributes()["department"], "sales");
+
+        let references = storage
+            .find_policy_references("MemoryStorageLinkedPolicy")
+            .await
+            .unwrap();
+        assert_eq!(references.identities, vec!["MemoryStorageIdentity"]);
+        assert_eq!(references.groups.is_empty(), true);
+
+        let references = storage
+            .delete_unlinked_policy("MemoryStorageLinkedPolicy")
+            .await
+            .unwrap();
+        assert_eq!(references.identities, vec!["MemoryStorageIdentity"]);
+        assert_eq!(
+            storage
+                .find_policy("MemoryStorageLinkedPolicy")
+                .await
+                .unwrap()
+                .is_some(),
+            true
+        );
+
+        assert_eq!(
+            identity.get_inline_policy().unwrap().id,
+            "__embedded_policy_identity_MemoryStorageIdentity__"
+        );
+
+        storage
+            .delete_policy("MemoryStorageLinkedPolicy")
+            .await
+            .unwrap();
+        let identity = storage
+            .find_identity("MemoryStorageIdentity")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(identity.linked_policies().len(), 0);
+
+        storage
+            .delete_policy("__embedded_policy_identity_MemoryStorageIdentity__")
+            .await
+            .expect_err("Inline policies cannot be deleted");
+
+        storage
+            .delete_identity("MemoryStorageIdentity")
+            .await
+            .unwrap();
+        assert_eq!(
+            storage
+                .find_policy("__embedded_policy_identity_MemoryStorageIdentity__")
+                .await
+                .unwrap()
+                .is_none(),
+            true
+        );
+    }
+
+    #[async_std::test]
+    async fn subjects_linking_unknown_policies_should_not_be_saved() {
+        let storage = MemoryStorage::new();
+        let policy = zephir_policy!(
+            "MemoryStorageUnsavedPolicy",
+            PolicyVersion::Version1,
+            PolicyEffect::Allow,
+            vec!["core:GetVersion"]
+        )
+        .unwrap();
+
+        let mut identity = Identity::new("MemoryStorageUnsavedIdentity", None).add_policy(policy);
+        storage
+            .save_identity(&mut identity)
+            .await
+            .expect_err("Linked policies must exist");
+        assert_eq!(
+            storage
+                .find_identity("MemoryStorageUnsavedIdentity")
+                .await
+                .unwrap()
+                .is_none(),
+            true
+        );
+
+        let mut group = Group::new("MemoryStorageUnsavedGroup", None)
+            .add_identity(Identity::new("MemoryStorageUnsavedMember", None));
+        storage
+            .save_group(&mut group)
+            .await
+            .expect_err("Member identities must exist");
+        assert_eq!(
+            storage
+                .find_group("MemoryStorageUnsavedGroup")
+                .await
+                .unwrap()
+                .is_none(),
+            true
+        );
+    }
+
+    #[async_std::test]
+    async fn groups_should_be_found_by_identity() {
+        let storage = MemoryStorage::new();
+        let mut identity = Identity::new("MemoryStorageGroupMember", None);
+        storage.save_identity(&mut identity).await.unwrap();
+
+        let mut group = Group::new("MemoryStorageGroup", None).add_identity(identity);
+        storage.save_group(&mut group).await.unwrap();
+
+        let mut other_group = Group::new("MemoryStorageOtherGroup", None);
+        storage.save_group(&mut other_group).await.unwrap();
+
+        let identity = storage
+            .find_identity("MemoryStorageGroupMember")
+            .await
+            .unwrap()
+            .unwrap();
+        let groups = storage
+            .find_groups_for_identity(&identity, false)
+            .await
+            .unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].get_name(), "MemoryStorageGroup");
+
+        storage
+            .delete_identity("MemoryStorageGroupMember")
+            .await
+            .unwrap();
+        let group = storage
+            .find_group("MemoryStorageGroup")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(group.identities.len(), 0);
+    }
+
+    #[async_std::test]
+    async fn policies_should_be_listed_by_page() {
+        let storage = MemoryStorage::new();
+        for (id, action) in [
+            ("ListPolicyA", "s3:GetObject"),
+            ("ListPolicyB", "ec2:StartInstance"),
+            ("ListPolicyC", "s3:PutObject"),
+            ("ListPolicyD", "s3:DeleteObject"),
+        ] {
+            let policy = zephir_policy!(
+                id,
+                PolicyVersion::Version1,
+                PolicyEffect::Allow,
+                vec![action]
+            )
+            .unwrap();
+            storage.save_policy(&policy).await.unwrap();
+        }
+
+        let mut identity = Identity::new("ListIdentity", None).set_inline_policy(
+            zephir_policy!(
+                "",
+                PolicyVersion::Version1,
+                PolicyEffect::Allow,
+                vec!["s3:ListBucket"]
+            )
+            .unwrap(),
+        );
+        storage.save_identity(&mut identity).await.unwrap();
+
+        let options = ListOptions {
+            limit: 2,
+            ..ListOptions::default()
+        };
+        let page = storage.list_policies(&options).await.unwrap();
+        assert_eq!(page.items.len(), 2);
+        assert_eq!(page.items[0].id, "ListPolicyA");
+        assert_eq!(page.next_cursor, Some("ListPolicyB".to_string()));
+
+        let page = storage
+            .list_policies(&ListOptions {
+                cursor: page.next_cursor,
+                ..options.clone()
+            })
+            .await
+            .unwrap();
+        assert_eq!(page.items.len(), 2);
+        assert_eq!(page.items[1].id, "ListPolicyD");
+        assert_eq!(page.next_cursor, None);
+
+        let page = storage
+            .list_policies(&ListOptions {
+                order: SortOrder::Descending,
+                ..options.clone()
+            })
+            .await
+            .unwrap();
+        assert_eq!(page.items[0].id, "ListPolicyD");
+
+        let filter = PolicyFilter::new(Some("s3:*"), None).unwrap();
+        let page = storage.search_policies(&options, &filter).await.unwrap();
+        assert_eq!(page.items.len(), 2);
+        assert_eq!(page.items[1].id, "ListPolicyC");
+        assert_eq!(page.next_cursor, Some("ListPolicyC".to_string()));
+
+        let page = storage
+            .search_policies(
+                &ListOptions {
+                    cursor: page.next_cursor,
+                    ..options.clone()
+                },
+                &filter,
+            )
+            .await
+            .unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].id, "ListPolicyD");
+        assert_eq!(page.next_cursor, None);
+
+        let page = storage.list_identities(&options).await.unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].id, "ListIdentity");
+    }
+
+    #[async_std::test]
+    async fn policy_search_should_be_bounded() {
+        let storage = MemoryStorage::new();
+        for i in 0..=MAX_SCANNED_POLICIES {
+            let action = if i == MAX_SCANNED_POLICIES {
+                "s3:GetObject"
+            } else {
+                "ec2:StartInstance"
+            };
+            let policy = zephir_policy!(
+                &format!("ScanPolicy{:04}", i),
+                PolicyVersion::Version1,
+                PolicyEffect::Allow,
+                vec![action]
+            )
+            .unwrap();
+            storage.save_policy(&policy).await.unwrap();
+        }
+
+        let options = ListOptions {
+            limit: 10,
+            ..ListOptions::default()
+        };
+        let filter = PolicyFilter::new(Some("s3:*"), None).unwrap();
+        let page = storage.search_policies(&options, &filter).await.unwrap();
+        assert_eq!(page.items.len(), 0);
+        assert_eq!(page.next_cursor, Some("ScanPolicy0999".to_string()));
+
+        let page = storage
+            .search_policies(
+                &ListOptions {
+                    cursor: page.next_cursor,
+                    ..options
+                },
+                &filter,
+            )
+            .await
+            .unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].id, "ScanPolicy1000");
+        assert_eq!(page.next_cursor, None);
+    }
+
+    #[async_std::test]
+    async fn policy_attachments_should_include_inherited_identities() {
+        let storage = MemoryStorage::new();
+        let policy = zephir_policy!(
+            "AttachedPolicy",
+            PolicyVersion::Version1,
+            PolicyEffect::Allow,
+            vec!["core:GetVersion"]
+        )
+        .unwrap();
+        storage.save_policy(&policy).await.unwrap();
+
+        let mut direct = Identity::new("AttachedIdentity", None).add_policy(policy.clone());
+        storage.save_identity(&mut direct).await.unwrap();
+
+        let mut member = Identity::new("AttachedMember", None);
+        storage.save_identity(&mut member).await.unwrap();
+
+        for group_id in ["AttachedGroupA", "AttachedGroupB"] {
+            let mut group = Group::new(group_id, None)
+                .add_policy(policy.clone())
+                .add_identity(Identity::new("AttachedMember", None))
+                .add_identity(Identity::new("AttachedIdentity", None));
+            storage.save_group(&mut group).await.unwrap();
+        }
+
+        let counts = storage
+            .count_policy_attachments("AttachedPolicy")
+            .await
+            .unwrap();
+        assert_eq!(counts.identities, 1);
+        assert_eq!(counts.groups, 2);
+        assert_eq!(counts.inherited_identities, 2);
+
+        let options = ListOptions {
+            limit: 1,
+            ..ListOptions::default()
+        };
+        let page = storage
+            .list_policy_attachments(
+                "AttachedPolicy",
+                AttachmentKind::InheritedIdentity,
+                &options,
+            )
+            .await
+            .unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].id, "AttachedIdentity");
+        assert_eq!(
+            page.items[0].groups,
+            vec!["AttachedGroupA", "AttachedGroupB"]
+        );
+        assert_eq!(page.next_cursor, Some("AttachedIdentity".to_string()));
+
+        let page = storage
+            .list_policy_attachments("AttachedPolicy", AttachmentKind::Group, &options)
+            .await
+            .unwrap();
+        assert_eq!(page.items[0].id, "AttachedGroupA");
+        assert_eq!(page.items[0].groups.is_empty(), true);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::err::Error;
+use sqlx::migrate::{Migrate, Migrator};
+use sqlx::Acquire;
+use std::ops::Deref;
+
+/// Represents the state of a schema migration.
+#[derive(Clone, Debug)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+}
+
+/// Gets the (sorted) versions of the migrations applied to the database.
+pub(super) async fn applied_versions<'a, A>(conn: A) -> Result<Vec<i64>, Error>
+where
+    A: Acquire<'a>,
+    <A::Connection as Deref>::Target: Migrate,
+{
+    let mut conn = conn.acquire().await?;
+    conn.ensure_migrations_table().await?;
+
+    let mut versions: Vec<i64> = conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|m| m.version)
+        .collect();
+
+    versions.sort_unstable();
+    Ok(versions)
+}
+
+/// Lists the migrations known by the migrator, marking the applied ones.
+pub(super) fn status(migrator: &Migrator, applied: &[i64]) -> Vec<MigrationStatus> {
+    migrator
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration())
+        .map(|m| MigrationStatus {
+            version: m.version,
+            description: m.description.to_string(),
+            applied: applied.contains(&m.version),
+        })
+        .collect()
+}
+
+/// Computes the version to revert the database to.
+/// If no target is given, only the last applied migration is reverted.
+pub(super) fn down_target(applied: &[i64], target: Option<i64>) -> i64 {
+    target.unwrap_or_else(|| match applied.len() {
+        0 | 1 => 0,
+        len => applied[len - 2],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::migrations::{down_target, status};
+    use sqlx::migrate::{Migration, MigrationType, Migrator};
+    use std::borrow::Cow;
+
+    #[test]
+    fn down_target_should_revert_last_migration_by_default() {
+        assert_eq!(down_target(&[], None), 0);
+        assert_eq!(down_target(&[1], None), 0);
+        assert_eq!(down_target(&[1, 2, 3], None), 2);
+        assert_eq!(down_target(&[1, 2, 3], Some(1)), 1);
+    }
+
+    #[test]
+    fn status_should_list_up_migrations_only() {
+        let migration = |version, migration_type| {
+            Migration::new(
+                version,
+                Cow::Borrowed("test migration"),
+                migration_type,
+                Cow::Borrowed("SELECT 1"),
+            )
+        };
+
+        let migrator = Migrator {
+            migrations: Cow::Owned(vec![
+                migration(1, MigrationType::ReversibleUp),
+                migration(1, MigrationType::ReversibleDown),
+                migration(2, MigrationType::ReversibleUp),
+                migration(2, MigrationType::ReversibleDown),
+            ]),
+            ignore_missing: false,
+        };
+
+        let result = status(&migrator, &[1]);
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].version, 1);
+        assert_eq!(result[0].applied, true);
+        assert_eq!(result[1].version, 2);
+        assert_eq!(result[1].applied, false);
+    }
+}
@@ -1,17 +1,228 @@
-mod group_manager;
-mod identity_manager;
-mod policy_manager;
+mod list;
+mod memory;
+mod migrations;
+mod mysql;
+mod postgres;
+mod sql;
+mod sqlite;
+mod strict_matching;
 mod types;
+mod who_can;
 
-use sqlx::{Pool, Postgres};
+use crate::err::Error;
+use crate::identity::group::Group;
+use crate::identity::identity::Identity;
+use crate::policy::policy::CompletePolicy;
+use async_trait::async_trait;
+use std::collections::HashMap;
 
-#[derive(Clone)]
-pub struct StorageManager {
-    pool: Pool<Postgres>,
+pub use list::{ListOptions, Page, PolicyFilter, SortOrder};
+pub use memory::MemoryStorage;
+pub use migrations::MigrationStatus;
+pub use mysql::MySqlStorage;
+pub use postgres::PostgresStorage;
+pub use sqlite::SqliteStorage;
+pub use strict_matching::{strict_matching_report, StrictMatchingChange};
+pub use types::is_inline_policy_id;
+pub use who_can::{who_can, WhoCanHit};
+
+/// Maximum number of policies scanned by a single policy search.
+const MAX_SCANNED_POLICIES: usize = 1000;
+
+/// Maximum number of policies loaded by the queries walking the whole storage,
+/// as the reverse authorization query and the strict matching report.
+const MAX_COLLECTED_POLICIES: usize = 10000;
+
+/// Holds the ids of the subjects a policy is linked to.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PolicyReferences {
+    pub identities: Vec<String>,
+    pub groups: Vec<String>,
 }
 
-impl StorageManager {
-    pub fn new(pool: Pool<Postgres>) -> Self {
-        StorageManager { pool }
+impl PolicyReferences {
+    /// Whether the policy is not linked to any identity or group.
+    pub fn is_empty(&self) -> bool {
+        self.identities.is_empty() && self.groups.is_empty()
     }
 }
+
+/// Kind of the subjects a policy can be attached to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AttachmentKind {
+    /// Identities linking the policy directly.
+    Identity,
+    /// Groups linking the policy.
+    Group,
+    /// Identities inheriting the policy through their groups.
+    InheritedIdentity,
+}
+
+/// A subject a policy is attached to.
+/// For inherited identities, `groups` holds the groups the policy is inherited from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PolicyAttachment {
+    pub id: String,
+    pub groups: Vec<String>,
+}
+
+/// Number of the subjects a policy is attached to, by kind.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AttachmentCounts {
+    pub identities: i64,
+    pub groups: i64,
+    pub inherited_identities: i64,
+}
+
+/// Represents a storage backend for policies, identities and groups.
+///
+/// Inline policies are stored as `__embedded_policy_*` policies linked
+/// to their owner, while linked policies and group members are stored
+/// as references and re-loaded on every find operation.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Checks that the storage backend is reachable.
+    async fn ping(&self) -> Result<(), Error>;
+
+    /// Applies all the pending schema migrations.
+    async fn migrate_up(&self) -> Result<(), Error>;
+
+    /// Reverts the applied schema migrations newer than the given version.
+    /// If no version is given, only the last applied migration is reverted.
+    async fn migrate_down(&self, target: Option<i64>) -> Result<(), Error>;
+
+    /// Lists the known schema migrations and whether they have been applied.
+    async fn migration_status(&self) -> Result<Vec<MigrationStatus>, Error>;
+
+    /// Finds a policy by id.
+    async fn find_policy(&self, id: &str) -> Result<Option<CompletePolicy>, Error>;
+
+    /// Inserts or updates a policy.
+    async fn save_policy(&self, policy: &CompletePolicy) -> Result<(), Error>;
+
+    /// Removes a policy, unlinking it from all the identities and groups.
+    async fn delete_policy(&self, id: &str) -> Result<(), Error>;
+
+    /// Removes a policy, unless it is still linked to an identity or a group.
+    /// The links are checked and the policy removed atomically.
+    ///
+    /// # Returns
+    ///
+    /// The identities and groups still linking the policy, empty if it has been removed.
+    async fn delete_unlinked_policy(&self, id: &str) -> Result<PolicyReferences, Error>;
+
+    /// Lists the policies, excluding the inline ones.
+    async fn list_policies(&self, options: &ListOptions) -> Result<Page<CompletePolicy>, Error>;
+
+    /// Lists the inline policies embedded into the identities and the groups.
+    async fn list_inline_policies(
+        &self,
+        options: &ListOptions,
+    ) -> Result<Page<CompletePolicy>, Error>;
+
+    /// Lists the policies mentioning the filtered actions and resources.
+    /// Policies are filtered after being loaded: pages are fetched until
+    /// the requested number of matching policies is reached.
+    ///
+    /// At most MAX_SCANNED_POLICIES policies are scanned per call: the returned
+    /// page may then hold fewer matches than requested, with a cursor to resume the search.
+    async fn search_policies(
+        &self,
+        options: &ListOptions,
+        filter: &PolicyFilter,
+    ) -> Result<Page<CompletePolicy>, Error> {
+        if filter.is_empty() {
+            return self.list_policies(options).await;
+        }
+
+        let mut options = options.clone();
+        let mut items = vec![];
+        let mut scanned = 0;
+        loop {
+            let page = self.list_policies(&options).await?;
+            let count = page.items.len();
+            for (i, policy) in page.items.into_iter().enumerate() {
+                let id = policy.id.clone();
+                if filter.matches(&policy) {
+                    items.push(policy);
+                }
+
+                scanned += 1;
+                if items.len() == options.limit || scanned == MAX_SCANNED_POLICIES {
+                    let exhausted = i + 1 == count && page.next_cursor.is_none();
+                    return Ok(Page {
+                        items,
+                        next_cursor: if exhausted { Option::None } else { Some(id) },
+                    });
+                }
+            }
+
+            match page.next_cursor {
+                Option::None => {
+                    return Ok(Page {
+                        items,
+                        next_cursor: Option::None,
+                    })
+                }
+                cursor => options.cursor = cursor,
+            }
+        }
+    }
+
+    /// Finds the identities and groups the given policy is linked to.
+    async fn find_policy_references(&self, id: &str) -> Result<PolicyReferences, Error>;
+
+    /// Finds an identity by id, loading its inline and linked policies.
+    async fn find_identity(&self, id: &str) -> Result<Option<Identity>, Error>;
+
+    /// Finds the identities with the given ids, loading their inline and linked policies.
+    /// Unknown ids are skipped.
+    async fn find_identities(&self, ids: &[String]) -> Result<Vec<Identity>, Error>;
+
+    /// Counts the identities and groups the given policy is attached to,
+    /// including the identities inheriting it through their groups.
+    async fn count_policy_attachments(&self, id: &str) -> Result<AttachmentCounts, Error>;
+
+    /// Lists the subjects of the given kind the policy is attached to.
+    async fn list_policy_attachments(
+        &self,
+        id: &str,
+        kind: AttachmentKind,
+        options: &ListOptions,
+    ) -> Result<Page<PolicyAttachment>, Error>;
+
+    /// Lists the identities, loading their inline and linked policies.
+    async fn list_identities(&self, options: &ListOptions) -> Result<Page<Identity>, Error>;
+
+    /// Inserts or updates an identity, its inline policy and its policy links.
+    async fn save_identity(&self, identity: &mut Identity) -> Result<(), Error>;
+
+    /// Removes an identity and its inline policy.
+    async fn delete_identity(&self, id: &str) -> Result<(), Error>;
+
+    /// Finds a group by id, loading its policies and its identities.
+    async fn find_group(&self, id: &str) -> Result<Option<Group>, Error>;
+
+    /// Lists the groups, loading their policies but not their identities.
+    async fn list_groups(&self, options: &ListOptions) -> Result<Page<Group>, Error>;
+
+    /// Finds all the groups the given identity is member of.
+    async fn find_groups_for_identity(
+        &self,
+        identity: &Identity,
+        load_identities: bool,
+    ) -> Result<Vec<Group>, Error>;
+
+    /// Finds the groups each of the given identities is member of, by identity id.
+    async fn find_groups_for_identities(
+        &self,
+        ids: &[String],
+        load_identities: bool,
+    ) -> Result<HashMap<String, Vec<Group>>, Error>;
+
+    /// Inserts or updates a group, its inline policy, its policy links and its members.
+    async fn save_group(&self, group: &mut Group) -> Result<(), Error>;
+
+    /// Removes a group and its inline policy.
+    async fn delete_group(&self, id: &str) -> Result<(), Error>;
+}
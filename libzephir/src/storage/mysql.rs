@@ -0,0 +1,38 @@
+use crate::storage::sql::{SqlBackend, SqlStorage};
+use sqlx::migrate::Migrator;
+use sqlx::mysql::MySqlQueryResult;
+use sqlx::MySql;
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations/mysql");
+
+/// Storage backend persisting data into a MySQL or MariaDB database.
+pub type MySqlStorage = SqlStorage<MySql>;
+
+impl SqlBackend for MySql {
+    fn migrator() -> &'static Migrator {
+        &MIGRATOR
+    }
+
+    fn rows_affected(result: &MySqlQueryResult) -> u64 {
+        result.rows_affected()
+    }
+
+    fn group_table() -> &'static str {
+        "`group`"
+    }
+
+    /// Backslash is already the default escape character,
+    /// and it would escape the closing quote of the clause.
+    fn like_escape() -> &'static str {
+        ""
+    }
+
+    fn upsert(columns: &[&str]) -> String {
+        let updates: Vec<String> = columns
+            .iter()
+            .map(|c| format!("{} = VALUES({})", c, c))
+            .collect();
+
+        format!("ON DUPLICATE KEY UPDATE {}", updates.join(", "))
+    }
+}
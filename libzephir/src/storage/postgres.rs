@@ -0,0 +1,23 @@
+use crate::storage::sql::{SqlBackend, SqlStorage};
+use sqlx::migrate::Migrator;
+use sqlx::postgres::PgQueryResult;
+use sqlx::Postgres;
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations/postgres");
+
+/// Storage backend persisting data into a PostgreSQL database.
+pub type PostgresStorage = SqlStorage<Postgres>;
+
+impl SqlBackend for Postgres {
+    fn migrator() -> &'static Migrator {
+        &MIGRATOR
+    }
+
+    fn rows_affected(result: &PgQueryResult) -> u64 {
+        result.rows_affected()
+    }
+
+    fn placeholder(index: usize) -> String {
+        format!("${}", index)
+    }
+}
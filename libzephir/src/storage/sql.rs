@@ -0,0 +1,1174 @@
+use crate::compiler::compiler::cache;
+use crate::err::Error;
+use crate::identity::group::Group;
+use crate::identity::identity::Identity;
+use crate::identity::role::Role;
+use crate::identity::subject::Subject;
+use crate::policy::policy::CompletePolicy;
+use crate::policy::policy_set::PolicySetTrait;
+use crate::storage::list::like_prefix;
+use crate::storage::migrations;
+use crate::storage::types::{
+    attributes_param, group_policy_id, identity_policy_id, DbIdentity, DbPolicy, PolicyColumns,
+    INLINE_POLICY_PREFIX,
+};
+use crate::storage::{
+    AttachmentCounts, AttachmentKind, ListOptions, MigrationStatus, Page, PolicyAttachment,
+    PolicyReferences, Storage,
+};
+use async_trait::async_trait;
+use serde_json::Value;
+use sqlx::database::HasArguments;
+use sqlx::migrate::{Migrate, Migrator};
+use sqlx::{Database, Encode, Executor, FromRow, IntoArguments, Pool, Type};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+const POLICY_COLUMNS: &str =
+    "id, version, effect, actions, not_actions, resources, not_resources, conditions, statements";
+
+/// The SQL syntax of a database supported as storage backend.
+///
+/// Queries are written with `?` placeholders, and the standard syntax
+/// is used unless overridden by the database.
+pub trait SqlBackend: Database {
+    /// Gets the schema migrations of the database.
+    fn migrator() -> &'static Migrator;
+
+    /// Gets the placeholder of the query parameter at the given (1-based) index.
+    fn placeholder(_index: usize) -> String {
+        String::from("?")
+    }
+
+    /// The quoted name of the group table ("group" being a reserved word).
+    fn group_table() -> &'static str {
+        r#""group""#
+    }
+
+    /// The clause declaring the escape character of the LIKE patterns.
+    fn like_escape() -> &'static str {
+        r"ESCAPE '\'"
+    }
+
+    /// Gets the number of rows affected by a query.
+    fn rows_affected(result: &Self::QueryResult) -> u64;
+
+    /// Gets the clause updating the given columns if the inserted id already exists.
+    fn upsert(columns: &[&str]) -> String {
+        let updates: Vec<String> = columns
+            .iter()
+            .map(|c| format!("{} = excluded.{}", c, c))
+            .collect();
+
+        format!("ON CONFLICT (id) DO UPDATE SET {}", updates.join(", "))
+    }
+
+    /// Replaces the `?` placeholders of the given query with the ones of the database.
+    fn sql(query: &str) -> String {
+        let mut sql = String::with_capacity(query.len());
+        let mut index = 0;
+        for c in query.chars() {
+            if c == '?' {
+                index += 1;
+                sql += &Self::placeholder(index);
+            } else {
+                sql.push(c);
+            }
+        }
+
+        sql
+    }
+}
+
+/// Storage backend persisting data into a SQL database.
+/// Only the SQL syntax differs between the supported databases.
+#[derive(Clone)]
+pub struct SqlStorage<DB: Database> {
+    pool: Pool<DB>,
+}
+
+impl<DB: Database> SqlStorage<DB> {
+    pub fn new(pool: Pool<DB>) -> Self {
+        SqlStorage { pool }
+    }
+}
+
+impl<DB> SqlStorage<DB>
+where
+    DB: SqlBackend,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as HasArguments<'q>>::Arguments: IntoArguments<'q, DB>,
+    for<'r> DbPolicy: FromRow<'r, DB::Row>,
+    for<'r> DbIdentity: FromRow<'r, DB::Row>,
+    for<'r> (String,): FromRow<'r, DB::Row>,
+    for<'r> (String, String): FromRow<'r, DB::Row>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    for<'q> i32: Encode<'q, DB> + Type<DB>,
+    for<'q> i64: Encode<'q, DB> + Type<DB>,
+    for<'q> bool: Encode<'q, DB> + Type<DB>,
+    for<'q> Value: Encode<'q, DB> + Type<DB>,
+    for<'q> Option<String>: Encode<'q, DB>,
+    for<'q> Option<Value>: Encode<'q, DB>,
+{
+    async fn _save_policy(
+        &self,
+        p: &CompletePolicy,
+        connection: &mut DB::Connection,
+    ) -> Result<(), Error> {
+        let columns = PolicyColumns::from(p);
+        let query = DB::sql(&format!(
+            r#"
+            INSERT INTO policy({})
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            {}
+        "#,
+            POLICY_COLUMNS,
+            DB::upsert(&[
+                "version",
+                "effect",
+                "actions",
+                "not_actions",
+                "resources",
+                "not_resources",
+                "conditions",
+                "statements",
+            ])
+        ));
+
+        sqlx::query(&query)
+            .bind(&p.id)
+            .bind(columns.version)
+            .bind(columns.effect)
+            .bind(columns.actions)
+            .bind(columns.not_actions)
+            .bind(columns.resources)
+            .bind(columns.not_resources)
+            .bind(columns.conditions)
+            .bind(columns.statements)
+            .execute(connection)
+            .await?;
+
+        cache::flush_policy(&p.id);
+        Ok(())
+    }
+
+    async fn _list_policies(
+        &self,
+        options: &ListOptions,
+        inline: bool,
+    ) -> Result<Page<CompletePolicy>, Error> {
+        let query = DB::sql(&format!(
+            r#"
+            SELECT {columns}
+            FROM policy
+            WHERE id {not}LIKE ? {escape}
+              AND (? IS NULL OR id LIKE ? {escape})
+              AND (? IS NULL OR id {operator} ?)
+            ORDER BY id {order}
+            LIMIT ?
+        "#,
+            columns = POLICY_COLUMNS,
+            not = if inline { "" } else { "NOT " },
+            escape = DB::like_escape(),
+            operator = options.order.cursor_operator(),
+            order = options.order.to_sql(),
+        ));
+
+        let rows = sqlx::query_as::<_, DbPolicy>(&query)
+            .bind(like_prefix(INLINE_POLICY_PREFIX))
+            .bind(options.like_prefix())
+            .bind(options.like_prefix())
+            .bind(options.cursor.clone())
+            .bind(options.cursor.clone())
+            .bind(options.fetch_limit())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let page = Page::from_rows(rows, options.limit, |p| p.id.as_str());
+        let mut items = vec![];
+        for db_policy in page.items {
+            items.push(CompletePolicy::try_from(db_policy)?);
+        }
+
+        Ok(Page {
+            items,
+            next_cursor: page.next_cursor,
+        })
+    }
+
+    /// Finds a policy by id.
+    async fn _find_policy(&self, id: &str) -> Result<Option<CompletePolicy>, Error> {
+        let query = DB::sql(&format!(
+            "SELECT {} FROM policy WHERE id = ?",
+            POLICY_COLUMNS
+        ));
+        let policy = sqlx::query_as::<_, DbPolicy>(&query)
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(match policy {
+            Option::None => Option::None,
+            Option::Some(policy) => Option::Some(CompletePolicy::try_from(policy)?),
+        })
+    }
+
+    /// Finds the identities and groups the given policy is linked to.
+    async fn _find_policy_references(
+        &self,
+        id: &str,
+        connection: &mut DB::Connection,
+    ) -> Result<PolicyReferences, Error> {
+        let identities = sqlx::query_scalar::<_, String>(&DB::sql(
+            "SELECT identity_id FROM identity_policy WHERE policy_id = ? ORDER BY identity_id",
+        ))
+        .bind(id.to_string())
+        .fetch_all(&mut *connection)
+        .await?;
+
+        let groups = sqlx::query_scalar::<_, String>(&DB::sql(
+            "SELECT group_id FROM group_policy WHERE policy_id = ? ORDER BY group_id",
+        ))
+        .bind(id.to_string())
+        .fetch_all(&mut *connection)
+        .await?;
+
+        Ok(PolicyReferences { identities, groups })
+    }
+
+    /// Finds an identity by id, loading its inline and linked policies.
+    async fn _find_identity(&self, id: &str) -> Result<Option<Identity>, Error> {
+        let identity = sqlx::query_as::<_, DbIdentity>(&DB::sql(
+            "SELECT id, policy_id, attributes FROM identity WHERE id = ?",
+        ))
+        .bind(id.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(match identity {
+            Option::None => Option::None,
+            Option::Some(identity) => self._load_identities(vec![identity]).await?.pop(),
+        })
+    }
+
+    /// Fetches the rows matching any of the given values, in a single query.
+    /// The query must end with an IN clause, to be filled with the placeholders.
+    async fn _fetch_in<O>(&self, query: &str, values: Vec<String>) -> Result<Vec<O>, Error>
+    where
+        O: for<'r> FromRow<'r, DB::Row> + Send + Unpin,
+    {
+        self._fetch_in_with(query, vec![], values).await
+    }
+
+    /// Same as `_fetch_in`, binding the given parameters before the IN clause values.
+    async fn _fetch_in_with<O>(
+        &self,
+        query: &str,
+        params: Vec<String>,
+        values: Vec<String>,
+    ) -> Result<Vec<O>, Error>
+    where
+        O: for<'r> FromRow<'r, DB::Row> + Send + Unpin,
+    {
+        if values.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let placeholders = vec!["?"; values.len()].join(", ");
+        let query = DB::sql(&format!("{} ({})", query, placeholders));
+        let mut query = sqlx::query_as::<_, O>(&query);
+        for value in params.into_iter().chain(values) {
+            query = query.bind(value);
+        }
+
+        Ok(query.fetch_all(&self.pool).await?)
+    }
+
+    /// Loads the inline and linked policies of the given identities (or groups)
+    /// with one query for the links and one for the policies.
+    ///
+    /// # Returns
+    ///
+    /// The loaded policies by id, and the ids of the policies linked to each subject.
+    async fn _load_policies(
+        &self,
+        link_table: &str,
+        subject_column: &str,
+        rows: &[DbIdentity],
+    ) -> Result<
+        (
+            HashMap<String, CompletePolicy>,
+            HashMap<String, Vec<String>>,
+        ),
+        Error,
+    > {
+        let links: Vec<(String, String)> = self
+            ._fetch_in(
+                &format!(
+                    "SELECT {column}, policy_id FROM {table} WHERE {column} IN",
+                    column = subject_column,
+                    table = link_table,
+                ),
+                rows.iter().map(|r| r.id.clone()).collect(),
+            )
+            .await?;
+
+        let mut linked: HashMap<String, Vec<String>> = HashMap::new();
+        for (subject_id, policy_id) in links {
+            linked.entry(subject_id).or_default().push(policy_id);
+        }
+
+        let mut policy_ids: Vec<String> = rows
+            .iter()
+            .filter_map(|r| r.policy_id.clone())
+            .chain(linked.values().flatten().cloned())
+            .collect();
+        policy_ids.sort_unstable();
+        policy_ids.dedup();
+
+        let query = format!("SELECT {} FROM policy WHERE id IN", POLICY_COLUMNS);
+        let mut policies = HashMap::new();
+        for db_policy in self._fetch_in::<DbPolicy>(&query, policy_ids).await? {
+            policies.insert(db_policy.id.clone(), CompletePolicy::try_from(db_policy)?);
+        }
+
+        Ok((policies, linked))
+    }
+
+    /// Loads the given identities with their inline and linked policies.
+    async fn _load_identities(&self, rows: Vec<DbIdentity>) -> Result<Vec<Identity>, Error> {
+        let (policies, linked) = self
+            ._load_policies("identity_policy", "identity_id", &rows)
+            .await?;
+
+        let mut identities = vec![];
+        for row in rows {
+            let inline_policy = row.policy_id.as_ref().and_then(|p| policies.get(p));
+            let mut identity =
+                Identity::new(&row.id, inline_policy.cloned()).set_attributes(row.attributes());
+            for policy in linked.get(&row.id).into_iter().flatten() {
+                identity = identity.add_policy(policies[policy].clone());
+            }
+
+            identities.push(identity);
+        }
+
+        Ok(identities)
+    }
+
+    /// Loads the given groups with their inline and linked policies,
+    /// and optionally with their identities.
+    async fn _load_groups(
+        &self,
+        rows: Vec<DbIdentity>,
+        load_identities: bool,
+    ) -> Result<Vec<Group>, Error> {
+        let (policies, linked) = self
+            ._load_policies("group_policy", "group_id", &rows)
+            .await?;
+
+        let mut members: HashMap<String, Vec<Identity>> = HashMap::new();
+        if load_identities {
+            let links: Vec<(String, String)> = self
+                ._fetch_in(
+                    "SELECT group_id, identity_id FROM group_identity WHERE group_id IN",
+                    rows.iter().map(|r| r.id.clone()).collect(),
+                )
+                .await?;
+
+            let mut identity_ids: Vec<String> = links.iter().map(|(_, i)| i.clone()).collect();
+            identity_ids.sort_unstable();
+            identity_ids.dedup();
+
+            let identity_rows: HashMap<String, DbIdentity> = self
+                ._fetch_in(
+                    "SELECT id, policy_id, attributes FROM identity WHERE id IN",
+                    identity_ids,
+                )
+                .await?
+                .into_iter()
+                .map(|r: DbIdentity| (r.id.clone(), r))
+                .collect();
+
+            // An identity may be a member of several groups of the page: it is
+            // built once per membership, its rows being loaded only once.
+            let (group_ids, member_rows): (Vec<String>, Vec<DbIdentity>) = links
+                .into_iter()
+                .filter_map(|(g, i)| identity_rows.get(&i).map(|r| (g, r.clone())))
+                .unzip();
+            let identities = self._load_identities(member_rows).await?;
+            for (group_id, identity) in group_ids.into_iter().zip(identities) {
+                members.entry(group_id).or_default().push(identity);
+            }
+        }
+
+        let mut groups = vec![];
+        for row in rows {
+            let inline_policy = row.policy_id.as_ref().and_then(|p| policies.get(p));
+            let mut group =
+                Group::new(&row.id, inline_policy.cloned()).set_attributes(row.attributes());
+            for policy in linked.get(&row.id).into_iter().flatten() {
+                group = group.add_policy(policies[policy].clone());
+            }
+
+            for identity in members.remove(&row.id).into_iter().flatten() {
+                group = group.add_identity(identity);
+            }
+
+            groups.push(group);
+        }
+
+        Ok(groups)
+    }
+
+    /// Lists the identities (or groups) of the given table, without loading them.
+    async fn _list_subjects(
+        &self,
+        table: &str,
+        options: &ListOptions,
+    ) -> Result<Page<DbIdentity>, Error> {
+        let query = DB::sql(&format!(
+            r#"
+            SELECT id, policy_id, attributes
+            FROM {table}
+            WHERE (? IS NULL OR id LIKE ? {escape})
+              AND (? IS NULL OR id {operator} ?)
+            ORDER BY id {order}
+            LIMIT ?
+        "#,
+            table = table,
+            escape = DB::like_escape(),
+            operator = options.order.cursor_operator(),
+            order = options.order.to_sql(),
+        ));
+
+        let rows = sqlx::query_as::<_, DbIdentity>(&query)
+            .bind(options.like_prefix())
+            .bind(options.like_prefix())
+            .bind(options.cursor.clone())
+            .bind(options.cursor.clone())
+            .bind(options.fetch_limit())
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(Page::from_rows(rows, options.limit, |r| r.id.as_str()))
+    }
+
+    /// Saves the inline policy of an identity (or group) of the given table,
+    /// or removes it if the subject has none.
+    ///
+    /// # Returns
+    ///
+    /// The id of the inline policy to be stored into the subject row, if any.
+    async fn _save_inline_policy(
+        &self,
+        table: &str,
+        subject_id: &str,
+        policy_id: String,
+        inline_policy: Option<&mut CompletePolicy>,
+        connection: &mut DB::Connection,
+    ) -> Result<Option<String>, Error> {
+        if let Some(inline_policy) = inline_policy {
+            inline_policy.id = policy_id.clone();
+            self._save_policy(inline_policy, connection).await?;
+
+            return Ok(Some(policy_id));
+        }
+
+        sqlx::query(&DB::sql(&format!(
+            "UPDATE {} SET policy_id = NULL WHERE id = ?",
+            table
+        )))
+        .bind(subject_id.to_string())
+        .execute(&mut *connection)
+        .await?;
+
+        sqlx::query(&DB::sql("DELETE FROM policy WHERE id = ?"))
+            .bind(policy_id)
+            .execute(&mut *connection)
+            .await?;
+
+        Ok(Option::None)
+    }
+
+    /// Inserts or updates the row of an identity (or group) of the given table.
+    async fn _save_subject(
+        &self,
+        table: &str,
+        id: &str,
+        policy_id: Option<String>,
+        attributes: Option<Value>,
+        connection: &mut DB::Connection,
+    ) -> Result<(), Error> {
+        let query = DB::sql(&format!(
+            r#"
+            INSERT INTO {}(id, policy_id, attributes)
+            VALUES (?, ?, ?)
+            {}
+        "#,
+            table,
+            DB::upsert(&["policy_id", "attributes"])
+        ));
+
+        sqlx::query(&query)
+            .bind(id.to_string())
+            .bind(policy_id)
+            .bind(attributes)
+            .execute(connection)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Replaces the links of the given subject into a link table
+    /// (ex: the policies linked to an identity).
+    async fn _save_links(
+        &self,
+        table: &str,
+        columns: (&str, &str),
+        subject_id: &str,
+        linked_ids: Vec<String>,
+        connection: &mut DB::Connection,
+    ) -> Result<(), Error> {
+        let (subject_column, linked_column) = columns;
+        sqlx::query(&DB::sql(&format!(
+            "DELETE FROM {} WHERE {} = ?",
+            table, subject_column
+        )))
+        .bind(subject_id.to_string())
+        .execute(&mut *connection)
+        .await?;
+
+        let query = DB::sql(&format!(
+            "INSERT INTO {} ({}, {}) VALUES (?, ?)",
+            table, subject_column, linked_column
+        ));
+        for linked_id in linked_ids {
+            sqlx::query(&query)
+                .bind(subject_id.to_string())
+                .bind(linked_id)
+                .execute(&mut *connection)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes an identity (or group) of the given table and its inline policy.
+    async fn _delete_subject(&self, table: &str, id: &str, policy_id: &str) -> Result<(), Error> {
+        let mut transaction = self.pool.begin().await?;
+
+        sqlx::query(&DB::sql(&format!("DELETE FROM {} WHERE id = ?", table)))
+            .bind(id.to_string())
+            .execute(&mut *transaction)
+            .await?;
+
+        sqlx::query(&DB::sql("DELETE FROM policy WHERE id = ?"))
+            .bind(policy_id.to_string())
+            .execute(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+        cache::flush_policy(policy_id);
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<DB> Storage for SqlStorage<DB>
+where
+    DB: SqlBackend,
+    DB::Connection: Migrate,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as HasArguments<'q>>::Arguments: IntoArguments<'q, DB>,
+    for<'r> DbPolicy: FromRow<'r, DB::Row>,
+    for<'r> DbIdentity: FromRow<'r, DB::Row>,
+    for<'r> (String,): FromRow<'r, DB::Row>,
+    for<'r> (String, String): FromRow<'r, DB::Row>,
+    for<'r> (i64, i64, i64): FromRow<'r, DB::Row>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    for<'q> i32: Encode<'q, DB> + Type<DB>,
+    for<'q> i64: Encode<'q, DB> + Type<DB>,
+    for<'q> bool: Encode<'q, DB> + Type<DB>,
+    for<'q> Value: Encode<'q, DB> + Type<DB>,
+    for<'q> Option<String>: Encode<'q, DB>,
+    for<'q> Option<Value>: Encode<'q, DB>,
+{
+    async fn ping(&self) -> Result<(), Error> {
+        sqlx::query("SELECT 1").fetch_one(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn migrate_up(&self) -> Result<(), Error> {
+        DB::migrator().run(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn migrate_down(&self, target: Option<i64>) -> Result<(), Error> {
+        let applied = migrations::applied_versions(&self.pool).await?;
+        DB::migrator()
+            .undo(&self.pool, migrations::down_target(&applied, target))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn migration_status(&self) -> Result<Vec<MigrationStatus>, Error> {
+        let applied = migrations::applied_versions(&self.pool).await?;
+        Ok(migrations::status(DB::migrator(), &applied))
+    }
+
+    async fn find_policy(&self, id: &str) -> Result<Option<CompletePolicy>, Error> {
+        self._find_policy(id).await
+    }
+
+    async fn save_policy(&self, p: &CompletePolicy) -> Result<(), Error> {
+        let mut transaction = self.pool.begin().await?;
+        self._save_policy(p, &mut transaction).await?;
+
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn delete_policy(&self, id: &str) -> Result<(), Error> {
+        sqlx::query(&DB::sql("DELETE FROM policy WHERE id = ?"))
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        cache::flush_policy(id);
+        Ok(())
+    }
+
+    async fn delete_unlinked_policy(&self, id: &str) -> Result<PolicyReferences, Error> {
+        let mut transaction = self.pool.begin().await?;
+        let result = sqlx::query(&DB::sql(
+            r#"
+            DELETE FROM policy
+            WHERE id = ?
+              AND NOT EXISTS (SELECT 1 FROM identity_policy WHERE policy_id = ?)
+              AND NOT EXISTS (SELECT 1 FROM group_policy WHERE policy_id = ?)
+        "#,
+        ))
+        .bind(id.to_string())
+        .bind(id.to_string())
+        .bind(id.to_string())
+        .execute(&mut *transaction)
+        .await?;
+
+        let references = if DB::rows_affected(&result) > 0 {
+            PolicyReferences::default()
+        } else {
+            self._find_policy_references(id, &mut transaction).await?
+        };
+
+        transaction.commit().await?;
+        cache::flush_policy(id);
+
+        Ok(references)
+    }
+
+    async fn list_policies(&self, options: &ListOptions) -> Result<Page<CompletePolicy>, Error> {
+        self._list_policies(options, false).await
+    }
+
+    async fn list_inline_policies(
+        &self,
+        options: &ListOptions,
+    ) -> Result<Page<CompletePolicy>, Error> {
+        self._list_policies(options, true).await
+    }
+
+    async fn find_policy_references(&self, id: &str) -> Result<PolicyReferences, Error> {
+        let mut connection = self.pool.acquire().await?;
+        self._find_policy_references(id, &mut connection).await
+    }
+
+    async fn find_identity(&self, id: &str) -> Result<Option<Identity>, Error> {
+        self._find_identity(id).await
+    }
+
+    async fn find_identities(&self, ids: &[String]) -> Result<Vec<Identity>, Error> {
+        let rows = self
+            ._fetch_in(
+                "SELECT id, policy_id, attributes FROM identity WHERE id IN",
+                ids.to_vec(),
+            )
+            .await?;
+
+        self._load_identities(rows).await
+    }
+
+    async fn count_policy_attachments(&self, id: &str) -> Result<AttachmentCounts, Error> {
+        let (identities, groups, inherited_identities) =
+            sqlx::query_as::<_, (i64, i64, i64)>(&DB::sql(
+                r#"
+            SELECT
+                (SELECT COUNT(*) FROM identity_policy WHERE policy_id = ?),
+                (SELECT COUNT(*) FROM group_policy WHERE policy_id = ?),
+                (
+                    SELECT COUNT(DISTINCT gi.identity_id)
+                    FROM group_identity gi
+                    INNER JOIN group_policy gp ON gp.group_id = gi.group_id
+                    WHERE gp.policy_id = ?
+                )
+        "#,
+            ))
+            .bind(id.to_string())
+            .bind(id.to_string())
+            .bind(id.to_string())
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(AttachmentCounts {
+            identities,
+            groups,
+            inherited_identities,
+        })
+    }
+
+    async fn list_policy_attachments(
+        &self,
+        id: &str,
+        kind: AttachmentKind,
+        options: &ListOptions,
+    ) -> Result<Page<PolicyAttachment>, Error> {
+        let (source, column) = match kind {
+            AttachmentKind::Identity => ("identity_policy", "identity_id"),
+            AttachmentKind::Group => ("group_policy", "group_id"),
+            AttachmentKind::InheritedIdentity => (
+                "group_identity gi INNER JOIN group_policy gp ON gp.group_id = gi.group_id",
+                "gi.identity_id",
+            ),
+        };
+
+        let query = DB::sql(&format!(
+            r#"
+            SELECT DISTINCT {column}
+            FROM {source}
+            WHERE policy_id = ?
+              AND (? IS NULL OR {column} LIKE ? {escape})
+              AND (? IS NULL OR {column} {operator} ?)
+            ORDER BY {column} {order}
+            LIMIT ?
+        "#,
+            column = column,
+            source = source,
+            escape = DB::like_escape(),
+            operator = options.order.cursor_operator(),
+            order = options.order.to_sql(),
+        ));
+
+        let rows = sqlx::query_scalar::<_, String>(&query)
+            .bind(id.to_string())
+            .bind(options.like_prefix())
+            .bind(options.like_prefix())
+            .bind(options.cursor.clone())
+            .bind(options.cursor.clone())
+            .bind(options.fetch_limit())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let page = Page::from_rows(rows, options.limit, |r| r.as_str());
+        let mut groups: HashMap<String, Vec<String>> = HashMap::new();
+        if kind == AttachmentKind::InheritedIdentity {
+            let links: Vec<(String, String)> = self
+                ._fetch_in_with(
+                    r#"
+                    SELECT gi.identity_id, gi.group_id
+                    FROM group_identity gi
+                    INNER JOIN group_policy gp ON gp.group_id = gi.group_id
+                    WHERE gp.policy_id = ? AND gi.identity_id IN"#,
+                    vec![id.to_string()],
+                    page.items.clone(),
+                )
+                .await?;
+
+            for (identity_id, group_id) in links {
+                groups.entry(identity_id).or_default().push(group_id);
+            }
+        }
+
+        let items = page
+            .items
+            .into_iter()
+            .map(|subject_id| {
+                let mut groups = groups.remove(&subject_id).unwrap_or_default();
+                groups.sort_unstable();
+
+                PolicyAttachment {
+                    id: subject_id,
+                    groups,
+                }
+            })
+            .collect();
+
+        Ok(Page {
+            items,
+            next_cursor: page.next_cursor,
+        })
+    }
+
+    async fn list_identities(&self, options: &ListOptions) -> Result<Page<Identity>, Error> {
+        let page = self._list_subjects("identity", options).await?;
+        Ok(Page {
+            items: self._load_identities(page.items).await?,
+            next_cursor: page.next_cursor,
+        })
+    }
+
+    async fn save_identity(&self, i: &mut Identity) -> Result<(), Error> {
+        let mut transaction = self.pool.begin().await?;
+        let policy_id = self
+            ._save_inline_policy(
+                "identity",
+                &i.id,
+                identity_policy_id(&i.id),
+                i.inline_policy.as_mut(),
+                &mut transaction,
+            )
+            .await?;
+
+        let attributes = attributes_param(&i.attributes);
+        let linked_ids = i
+            .linked_policies()
+            .into_iter()
+            .map(|p| p.id.clone())
+            .collect();
+        self._save_subject("identity", &i.id, policy_id, attributes, &mut transaction)
+            .await?;
+
+        self._save_links(
+            "identity_policy",
+            ("identity_id", "policy_id"),
+            &i.id,
+            linked_ids,
+            &mut transaction,
+        )
+        .await?;
+
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn delete_identity(&self, id: &str) -> Result<(), Error> {
+        self._delete_subject("identity", id, &identity_policy_id(id))
+            .await
+    }
+
+    async fn find_group(&self, id: &str) -> Result<Option<Group>, Error> {
+        let query = DB::sql(&format!(
+            "SELECT id, policy_id, attributes FROM {} WHERE id = ?",
+            DB::group_table()
+        ));
+        let group = sqlx::query_as::<_, DbIdentity>(&query)
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(match group {
+            Option::None => Option::None,
+            Option::Some(group) => self._load_groups(vec![group], true).await?.pop(),
+        })
+    }
+
+    async fn list_groups(&self, options: &ListOptions) -> Result<Page<Group>, Error> {
+        let page = self._list_subjects(DB::group_table(), options).await?;
+        Ok(Page {
+            items: self._load_groups(page.items, false).await?,
+            next_cursor: page.next_cursor,
+        })
+    }
+
+    async fn find_groups_for_identity(
+        &self,
+        target: &Identity,
+        load_identities: bool,
+    ) -> Result<Vec<Group>, Error> {
+        let query = DB::sql(&format!(
+            r#"
+            SELECT id, policy_id, attributes
+            FROM {group}
+            INNER JOIN group_identity gi ON {group}.id = gi.group_id AND gi.identity_id = ?
+        "#,
+            group = DB::group_table()
+        ));
+        let groups = sqlx::query_as::<_, DbIdentity>(&query)
+            .bind(&target.id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        self._load_groups(groups, load_identities).await
+    }
+
+    async fn find_groups_for_identities(
+        &self,
+        ids: &[String],
+        load_identities: bool,
+    ) -> Result<HashMap<String, Vec<Group>>, Error> {
+        let links: Vec<(String, String)> = self
+            ._fetch_in(
+                "SELECT identity_id, group_id FROM group_identity WHERE identity_id IN",
+                ids.to_vec(),
+            )
+            .await?;
+
+        let mut group_ids: Vec<String> = links.iter().map(|(_, g)| g.clone()).collect();
+        group_ids.sort_unstable();
+        group_ids.dedup();
+
+        let group_rows: HashMap<String, DbIdentity> = self
+            ._fetch_in(
+                &format!(
+                    "SELECT id, policy_id, attributes FROM {} WHERE id IN",
+                    DB::group_table()
+                ),
+                group_ids,
+            )
+            .await?
+            .into_iter()
+            .map(|r: DbIdentity| (r.id.clone(), r))
+            .collect();
+
+        // A group shared by several identities is built once per membership,
+        // its rows being loaded only once.
+        let (identity_ids, rows): (Vec<String>, Vec<DbIdentity>) = links
+            .into_iter()
+            .filter_map(|(i, g)| group_rows.get(&g).map(|r| (i, r.clone())))
+            .unzip();
+
+        let mut groups: HashMap<String, Vec<Group>> = HashMap::new();
+        let loaded = self._load_groups(rows, load_identities).await?;
+        for (identity_id, group) in identity_ids.into_iter().zip(loaded) {
+            groups.entry(identity_id).or_default().push(group);
+        }
+
+        Ok(groups)
+    }
+
+    async fn save_group(&self, g: &mut Group) -> Result<(), Error> {
+        let group_name = g.name.clone();
+        let mut transaction = self.pool.begin().await?;
+        let policy_id = self
+            ._save_inline_policy(
+                DB::group_table(),
+                &group_name,
+                group_policy_id(&group_name),
+                g.get_inline_policy_mut(),
+                &mut transaction,
+            )
+            .await?;
+
+        let attributes = attributes_param(&g.attributes);
+        let linked_ids = g
+            .linked_policies()
+            .into_iter()
+            .map(|p| p.id.clone())
+            .collect();
+        let member_ids = g.identities.into_iter().map(|i| i.id.clone()).collect();
+        self._save_subject(
+            DB::group_table(),
+            &group_name,
+            policy_id,
+            attributes,
+            &mut transaction,
+        )
+        .await?;
+
+        self._save_links(
+            "group_policy",
+            ("group_id", "policy_id"),
+            &group_name,
+            linked_ids,
+            &mut transaction,
+        )
+        .await?;
+
+        self._save_links(
+            "group_identity",
+            ("group_id", "identity_id"),
+            &group_name,
+            member_ids,
+            &mut transaction,
+        )
+        .await?;
+
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn delete_group(&self, id: &str) -> Result<(), Error> {
+        self._delete_subject(DB::group_table(), id, &group_policy_id(id))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::identity::group::Group;
+    use crate::identity::identity::Identity;
+    use crate::identity::role::Role;
+    use crate::identity::subject::Subject;
+    use crate::policy::policy_set::PolicySetTrait;
+    use crate::policy::{PolicyEffect, PolicyVersion};
+    use crate::storage::sql::{SqlBackend, SqlStorage};
+    use crate::storage::{AttachmentKind, ListOptions, Storage};
+    use crate::zephir_policy;
+    use sqlx::pool::PoolOptions;
+    use sqlx::{MySql, Postgres, Sqlite};
+
+    #[test]
+    fn queries_should_use_the_placeholders_of_the_database() {
+        let query = "SELECT id FROM policy WHERE id = ? OR id LIKE ?";
+        assert_eq!(
+            Postgres::sql(query),
+            "SELECT id FROM policy WHERE id = $1 OR id LIKE $2"
+        );
+        assert_eq!(MySql::sql(query), query);
+        assert_eq!(Sqlite::sql(query), query);
+    }
+
+    #[test]
+    fn upsert_should_follow_the_database_syntax() {
+        assert_eq!(
+            Sqlite::upsert(&["version", "effect"]),
+            "ON CONFLICT (id) DO UPDATE SET version = excluded.version, effect = excluded.effect"
+        );
+        assert_eq!(
+            MySql::upsert(&["policy_id"]),
+            "ON DUPLICATE KEY UPDATE policy_id = VALUES(policy_id)"
+        );
+    }
+
+    #[async_std::test]
+    async fn subjects_should_be_saved_and_loaded() {
+        // In-memory databases are bound to their connection.
+        let pool = PoolOptions::<Sqlite>::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let storage = SqlStorage::new(pool);
+        storage.migrate_up().await.unwrap();
+
+        let policy = zephir_policy!(
+            "SqlStoragePolicy",
+            PolicyVersion::Version1,
+            PolicyEffect::Allow,
+            vec!["core:GetVersion"]
+        )
+        .unwrap();
+        storage.save_policy(&policy).await.unwrap();
+
+        let inline_policy = zephir_policy!(
+            "",
+            PolicyVersion::Version1,
+            PolicyEffect::Allow,
+            vec!["core:GetSecret"]
+        )
+        .unwrap();
+        let mut identity = Identity::new("SqlStorageIdentity", None)
+            .set_inline_policy(inline_policy)
+            .add_policy(policy.clone());
+        storage.save_identity(&mut identity).await.unwrap();
+        storage.save_identity(&mut identity).await.unwrap();
+
+        let mut group = Group::new("SqlStorageGroup", None)
+            .add_policy(policy)
+            .add_identity(identity);
+        storage.save_group(&mut group).await.unwrap();
+
+        let identity = storage
+            .find_identity("SqlStorageIdentity")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(identity.get_inline_policy().is_some(), true);
+        assert_eq!(identity.linked_policies().len(), 1);
+
+        let group = storage
+            .find_group("SqlStorageGroup")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(group.identities.len(), 1);
+        assert_eq!(group.linked_policies().len(), 1);
+
+        let ids = vec![
+            "SqlStorageIdentity".to_string(),
+            "SqlStorageUnknown".to_string(),
+        ];
+        let identities = storage.find_identities(&ids).await.unwrap();
+        assert_eq!(identities.len(), 1);
+        assert_eq!(identities[0].linked_policies().len(), 1);
+
+        let groups = storage
+            .find_groups_for_identities(&ids, false)
+            .await
+            .unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups["SqlStorageIdentity"].len(), 1);
+        assert_eq!(groups["SqlStorageIdentity"][0].linked_policies().len(), 1);
+
+        let page = storage.list_groups(&ListOptions::default()).await.unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].linked_policies().len(), 1);
+        assert_eq!(page.next_cursor, None);
+
+        let page = storage
+            .list_identities(&ListOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].get_inline_policy().is_some(), true);
+        assert_eq!(page.items[0].linked_policies().len(), 1);
+
+        let page = storage
+            .list_inline_policies(&ListOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(page.items.len(), 1);
+
+        let page = storage
+            .list_policy_attachments(
+                "SqlStoragePolicy",
+                AttachmentKind::InheritedIdentity,
+                &ListOptions::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].id, "SqlStorageIdentity");
+        assert_eq!(page.items[0].groups, vec!["SqlStorageGroup"]);
+
+        let references = storage
+            .delete_unlinked_policy("SqlStoragePolicy")
+            .await
+            .unwrap();
+        assert_eq!(references.identities, vec!["SqlStorageIdentity"]);
+        assert_eq!(references.groups, vec!["SqlStorageGroup"]);
+
+        storage.delete_group("SqlStorageGroup").await.unwrap();
+        storage.delete_identity("SqlStorageIdentity").await.unwrap();
+        let references = storage
+            .delete_unlinked_policy("SqlStoragePolicy")
+            .await
+            .unwrap();
+        assert_eq!(references.is_empty(), true);
+        assert_eq!(
+            storage
+                .find_policy("SqlStoragePolicy")
+                .await
+                .unwrap()
+                .is_none(),
+            true
+        );
+
+        let page = storage
+            .list_identities(&ListOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(page.items.len(), 0);
+    }
+}
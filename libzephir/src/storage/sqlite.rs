@@ -0,0 +1,20 @@
+use crate::storage::sql::{SqlBackend, SqlStorage};
+use sqlx::migrate::Migrator;
+use sqlx::sqlite::SqliteQueryResult;
+use sqlx::Sqlite;
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
+
+/// Storage backend persisting data into a SQLite database.
+/// Suitable for single-node deployments.
+pub type SqliteStorage = SqlStorage<Sqlite>;
+
+impl SqlBackend for Sqlite {
+    fn migrator() -> &'static Migrator {
+        &MIGRATOR
+    }
+
+    fn rows_affected(result: &SqliteQueryResult) -> u64 {
+        result.rows_affected()
+    }
+}
@@ -0,0 +1,139 @@
+use crate::err::Error;
+use crate::policy::policy::{CompletePolicy, MatchablePolicy, ToJson};
+use crate::policy::PolicyVersion;
+use crate::storage::list::collect_policies;
+use crate::storage::Storage;
+use crate::utils::glob_to_regex;
+use serde_json::{Map, Value};
+
+/// A stored policy whose meaning would change with strict (anchored) glob matching.
+///
+/// Version 1 policies match any identifier containing a glob match
+/// (ex: "core:Get" matches "core:GetSecret"), while newer versions only
+/// match whole identifiers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StrictMatchingChange {
+    pub policy_id: String,
+
+    /// The action globs (positive or negated) affected by anchoring.
+    pub actions: Vec<String>,
+
+    /// The resource globs (positive or negated) affected by anchoring.
+    pub resources: Vec<String>,
+}
+
+impl StrictMatchingChange {
+    /// Gets the change of the given policy, None if its meaning does not depend on anchoring.
+    pub fn of(policy: &CompletePolicy) -> Option<Self> {
+        if policy.version != PolicyVersion::Version1 {
+            return Option::None;
+        }
+
+        let affected = |globs: &[&[String]]| -> Vec<String> {
+            globs
+                .iter()
+                .flat_map(|g| g.iter())
+                .filter(|g| !glob_to_regex::is_anchoring_neutral(g))
+                .cloned()
+                .collect()
+        };
+
+        let mut change = StrictMatchingChange {
+            policy_id: policy.id.clone(),
+            actions: vec![],
+            resources: vec![],
+        };
+
+        for statement in policy.statements() {
+            change.actions.extend(affected(&[
+                statement.get_actions(),
+                statement.get_not_actions(),
+            ]));
+            change.resources.extend(affected(&[
+                statement.get_resources(),
+                statement.get_not_resources(),
+            ]));
+        }
+
+        if change.actions.is_empty() && change.resources.is_empty() {
+            Option::None
+        } else {
+            Option::Some(change)
+        }
+    }
+}
+
+impl ToJson for StrictMatchingChange {
+    fn to_json(&self) -> Map<String, Value> {
+        let mut result = Map::new();
+        result.insert(String::from("policy"), Value::from(self.policy_id.as_str()));
+        result.insert(
+            String::from("actions"),
+            Value::from(self.actions.as_slice()),
+        );
+        result.insert(
+            String::from("resources"),
+            Value::from(self.resources.as_slice()),
+        );
+
+        result
+    }
+}
+
+/// Lists the stored policies (linked and inline) whose meaning would change
+/// if they were migrated to strict glob matching.
+pub async fn strict_matching_report(
+    storage: &dyn Storage,
+) -> Result<Vec<StrictMatchingChange>, Error> {
+    let policies = collect_policies(storage, |p| p.version == PolicyVersion::Version1).await?;
+
+    Ok(policies
+        .iter()
+        .filter_map(StrictMatchingChange::of)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::policy::policy::CompletePolicy;
+    use crate::policy::{PolicyEffect, PolicyVersion};
+    use crate::storage::strict_matching::strict_matching_report;
+    use crate::storage::{MemoryStorage, Storage};
+    use crate::zephir_policy;
+    use serde_json::json;
+
+    #[async_std::test]
+    async fn report_should_list_legacy_policies_with_affected_globs() {
+        let storage = MemoryStorage::new();
+        let legacy = zephir_policy!(
+            "StrictMatchingLegacy",
+            PolicyVersion::Version1,
+            PolicyEffect::Allow,
+            vec!["core:Get", "*"],
+            vec!["urn:bucket:*"]
+        )
+        .unwrap();
+        let match_all = zephir_policy!(
+            "StrictMatchingAll",
+            PolicyVersion::Version1,
+            PolicyEffect::Allow,
+            vec!["*"]
+        )
+        .unwrap();
+        let strict = CompletePolicy::from_statements(
+            "StrictMatchingStrict".to_string(),
+            &json!([{ "Effect": "Allow", "Action": "core:Get" }]),
+        )
+        .unwrap();
+
+        for policy in [&legacy, &match_all, &strict] {
+            storage.save_policy(policy).await.unwrap();
+        }
+
+        let report = strict_matching_report(&storage).await.unwrap();
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].policy_id, "StrictMatchingLegacy");
+        assert_eq!(report[0].actions, vec!["core:Get"]);
+        assert_eq!(report[0].resources, vec!["urn:bucket:*"]);
+    }
+}
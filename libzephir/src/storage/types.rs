@@ -1,16 +1,328 @@
+use crate::err::Error;
+use crate::policy::policy::{CompletePolicy, MatchablePolicy, ToJson};
+use crate::policy::statement::PolicyTargets;
+use crate::policy::{PolicyEffect, PolicyVersion};
+use crate::storage::AttachmentKind;
+use serde_json::{Map, Value};
 use sqlx::types::Json;
+use std::convert::TryFrom;
 
-#[derive(sqlx::FromRow)]
-pub(super) struct DbIdentity {
+#[derive(Clone, sqlx::FromRow)]
+pub struct DbIdentity {
     pub(super) id: String,
     pub(super) policy_id: Option<String>,
+    pub(super) attributes: Option<Json<Value>>,
+}
+
+impl DbIdentity {
+    /// Gets the attributes of the identity (or group).
+    /// Rows saved before the introduction of the attributes have none.
+    pub(super) fn attributes(&self) -> Map<String, Value> {
+        match &self.attributes {
+            Some(Json(Value::Object(attributes))) => attributes.clone(),
+            _ => Map::new(),
+        }
+    }
 }
 
-#[derive(sqlx::Type, sqlx::FromRow)]
-pub(super) struct DbPolicy {
+/// Gets the attributes of an identity (or group) in their storable form.
+/// Empty attributes are stored as NULL.
+pub(super) fn attributes_param(attributes: &Map<String, Value>) -> Option<Value> {
+    if attributes.is_empty() {
+        Option::None
+    } else {
+        Option::Some(Value::Object(attributes.clone()))
+    }
+}
+
+#[derive(sqlx::FromRow)]
+pub struct DbPolicy {
     pub(super) id: String,
     pub(super) version: i32,
     pub(super) effect: bool,
     pub(super) actions: Json<Vec<String>>,
+    pub(super) not_actions: Option<Json<Vec<String>>>,
     pub(super) resources: Json<Vec<String>>,
+    pub(super) not_resources: Option<Json<Vec<String>>>,
+    pub(super) conditions: Option<Json<Value>>,
+    pub(super) statements: Option<Json<Value>>,
+}
+
+/// The column values of a policy, in their storable form.
+/// Version 2 policies are stored in the statements column only,
+/// the other columns being kept with neutral values.
+pub(super) struct PolicyColumns {
+    pub(super) version: i32,
+    pub(super) effect: bool,
+    pub(super) actions: Value,
+    pub(super) not_actions: Option<Value>,
+    pub(super) resources: Value,
+    pub(super) not_resources: Option<Value>,
+    pub(super) conditions: Option<Value>,
+    pub(super) statements: Option<Value>,
+}
+
+/// Prefix of the ids of the inline policies.
+pub(super) const INLINE_POLICY_PREFIX: &str = "__embedded_policy_";
+
+/// Gets the id of the inline policy embedded into the given identity.
+pub(super) fn identity_policy_id(identity_id: &str) -> String {
+    format!("__embedded_policy_identity_{}__", identity_id)
+}
+
+/// Gets the id of the inline policy embedded into the given group.
+pub(super) fn group_policy_id(group_id: &str) -> String {
+    format!("__embedded_policy_group_{}__", group_id)
+}
+
+/// Whether the given id belongs to an inline policy embedded into an identity or a group.
+pub fn is_inline_policy_id(policy_id: &str) -> bool {
+    policy_id.starts_with(INLINE_POLICY_PREFIX) && policy_id.ends_with("__")
+}
+
+/// Gets the kind and the id of the subject owning the given inline policy.
+pub(super) fn inline_policy_owner(policy_id: &str) -> Option<(AttachmentKind, &str)> {
+    if !is_inline_policy_id(policy_id) || policy_id.len() < INLINE_POLICY_PREFIX.len() + 2 {
+        return Option::None;
+    }
+
+    let rest = &policy_id[INLINE_POLICY_PREFIX.len()..policy_id.len() - 2];
+    if let Some(id) = rest.strip_prefix("identity_") {
+        Some((AttachmentKind::Identity, id))
+    } else {
+        rest.strip_prefix("group_")
+            .map(|id| (AttachmentKind::Group, id))
+    }
+}
+
+/// Gets the conditions of a policy in their storable form.
+/// Policies without conditions are stored as NULL.
+fn conditions_param(conditions: &Value) -> Option<Value> {
+    match conditions {
+        Value::Null => Option::None,
+        conditions => Option::Some(conditions.clone()),
+    }
+}
+
+/// Gets a negated list of a policy in its storable form.
+/// Empty lists (positive policies) are stored as NULL.
+fn list_param(list: &[String]) -> Option<Value> {
+    if list.is_empty() {
+        Option::None
+    } else {
+        Option::Some(Value::from(list))
+    }
+}
+
+impl From<&CompletePolicy> for PolicyColumns {
+    fn from(policy: &CompletePolicy) -> Self {
+        let version: i32 = (&policy.version).into();
+        if policy.version != PolicyVersion::Version1 {
+            return PolicyColumns {
+                version,
+                effect: true,
+                actions: Value::Array(vec![]),
+                not_actions: Option::None,
+                resources: Value::Array(vec![]),
+                not_resources: Option::None,
+                conditions: Option::None,
+                statements: policy.to_json().remove("statements"),
+            };
+        }
+
+        let statement = &policy.statements()[0];
+        PolicyColumns {
+            version,
+            effect: (&statement.effect).into(),
+            actions: Value::from(statement.get_actions()),
+            not_actions: list_param(statement.get_not_actions()),
+            resources: Value::from(statement.get_resources()),
+            not_resources: list_param(statement.get_not_resources()),
+            conditions: conditions_param(statement.get_conditions()),
+            statements: Option::None,
+        }
+    }
+}
+
+impl TryFrom<DbPolicy> for CompletePolicy {
+    type Error = Error;
+
+    fn try_from(value: DbPolicy) -> Result<Self, Self::Error> {
+        let version = PolicyVersion::try_from(value.version)?;
+        if version == PolicyVersion::Version2 {
+            let statements = value
+                .statements
+                .ok_or_else(|| Error::from("Version 2 policy has no statements"))?;
+
+            return CompletePolicy::from_statements(value.id, &statements.0);
+        }
+
+        let targets = PolicyTargets::new(value.actions.to_vec(), value.resources.to_vec())
+            .not_actions(value.not_actions.map(|a| a.0).unwrap_or_default())
+            .not_resources(value.not_resources.map(|r| r.0).unwrap_or_default());
+
+        CompletePolicy::with_targets(
+            value.id,
+            version,
+            if value.effect {
+                PolicyEffect::Allow
+            } else {
+                PolicyEffect::Deny
+            },
+            targets,
+            value.conditions.map(|c| c.0).unwrap_or(Value::Null),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::policy::policy::{CompletePolicy, MatchablePolicy, ToJson};
+    use crate::storage::types::{
+        group_policy_id, identity_policy_id, inline_policy_owner, is_inline_policy_id, DbPolicy,
+        PolicyColumns,
+    };
+    use crate::storage::AttachmentKind;
+    use serde_json::Value;
+    use sqlx::types::Json;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn db_policy_conditions_should_be_loaded() {
+        let conditions = serde_json::json!({
+            "IpAddress": {
+                "source": "10.0.0.0/8",
+            }
+        });
+
+        let policy = CompletePolicy::try_from(DbPolicy {
+            id: "TestDbPolicyConditions".to_string(),
+            version: 1,
+            effect: true,
+            actions: Json(vec!["core:GetVersion".to_string()]),
+            not_actions: None,
+            resources: Json(vec!["*".to_string()]),
+            not_resources: None,
+            conditions: Some(Json(conditions.clone())),
+            statements: None,
+        })
+        .unwrap();
+
+        assert_eq!(policy.statements()[0].get_conditions(), &conditions);
+
+        let policy = CompletePolicy::try_from(DbPolicy {
+            id: "TestDbPolicyNoConditions".to_string(),
+            version: 1,
+            effect: true,
+            actions: Json(vec!["core:GetVersion".to_string()]),
+            not_actions: None,
+            resources: Json(vec!["*".to_string()]),
+            not_resources: None,
+            conditions: None,
+            statements: None,
+        })
+        .unwrap();
+
+        assert_eq!(policy.statements()[0].get_conditions(), &Value::Null);
+    }
+
+    #[test]
+    fn db_policy_with_invalid_conditions_should_not_be_loaded() {
+        let result = CompletePolicy::try_from(DbPolicy {
+            id: "TestDbPolicyInvalidConditions".to_string(),
+            version: 1,
+            effect: true,
+            actions: Json(vec!["core:GetVersion".to_string()]),
+            not_actions: None,
+            resources: Json(vec!["*".to_string()]),
+            not_resources: None,
+            conditions: Some(Json(serde_json::json!({ "UnknownOperator": {} }))),
+            statements: None,
+        });
+
+        assert_eq!(result.is_err(), true);
+    }
+
+    #[test]
+    fn db_policy_negated_lists_should_be_loaded() {
+        let policy = CompletePolicy::try_from(DbPolicy {
+            id: "TestDbPolicyNegated".to_string(),
+            version: 1,
+            effect: false,
+            actions: Json(vec![]),
+            not_actions: Some(Json(vec!["iam:ChangePassword".to_string()])),
+            resources: Json(vec![]),
+            not_resources: None,
+            conditions: None,
+            statements: None,
+        })
+        .unwrap();
+
+        let statement = &policy.statements()[0];
+        assert_eq!(statement.get_not_actions(), &["iam:ChangePassword"]);
+        assert_eq!(statement.get_resources(), &["*"]);
+
+        let columns = PolicyColumns::from(&policy);
+        assert_eq!(columns.actions, serde_json::json!([]));
+        assert_eq!(
+            columns.not_actions,
+            Some(serde_json::json!(["iam:ChangePassword"]))
+        );
+        assert_eq!(columns.not_resources, None);
+    }
+
+    #[test]
+    fn version_2_policy_should_be_stored_as_statements() {
+        let statements = serde_json::json!([
+            { "Sid": "Read", "Effect": "Allow", "Action": "core:Get*" },
+            { "Effect": "Deny", "Action": "core:GetSecret" },
+        ]);
+        let policy =
+            CompletePolicy::from_statements("TestDbPolicyStatements".to_string(), &statements)
+                .unwrap();
+
+        let columns = PolicyColumns::from(&policy);
+        assert_eq!(columns.version, 2);
+        assert_eq!(columns.conditions, None);
+
+        let loaded = CompletePolicy::try_from(DbPolicy {
+            id: "TestDbPolicyStatements".to_string(),
+            version: columns.version,
+            effect: columns.effect,
+            actions: Json(vec![]),
+            not_actions: None,
+            resources: Json(vec![]),
+            not_resources: None,
+            conditions: None,
+            statements: columns.statements.map(Json),
+        })
+        .unwrap();
+
+        assert_eq!(loaded.statements().len(), 2);
+        assert_eq!(loaded.to_value(), policy.to_value());
+    }
+
+    #[test]
+    fn inline_policy_ids_should_be_recognized() {
+        assert_eq!(
+            is_inline_policy_id(&identity_policy_id("TestIdentity")),
+            true
+        );
+        assert_eq!(is_inline_policy_id(&group_policy_id("TestGroup")), true);
+        assert_eq!(is_inline_policy_id("TestPolicy"), false);
+    }
+
+    #[test]
+    fn inline_policy_owner_should_be_parsed() {
+        assert_eq!(
+            inline_policy_owner(&identity_policy_id("TestIdentity")),
+            Some((AttachmentKind::Identity, "TestIdentity"))
+        );
+        assert_eq!(
+            inline_policy_owner(&group_policy_id("Test_Group")),
+            Some((AttachmentKind::Group, "Test_Group"))
+        );
+        assert_eq!(inline_policy_owner("TestPolicy"), None);
+        assert_eq!(inline_policy_owner("__embedded_policy__"), None);
+    }
 }
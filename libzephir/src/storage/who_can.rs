@@ -0,0 +1,338 @@
+use crate::err::Error;
+use crate::identity::authorization::allowed_for_identity;
+use crate::identity::identity::Identity;
+use crate::policy::allowed_result::{AllowedOutcome, AllowedResult, PolicyEvaluation};
+use crate::policy::policy::{CompletePolicy, ToJson};
+use crate::policy::PolicyEffect;
+use crate::storage::list::collect_policies;
+use crate::storage::types::inline_policy_owner;
+use crate::storage::{AttachmentKind, ListOptions, Storage};
+use serde_json::{Map, Value};
+use std::collections::{BTreeSet, HashMap};
+
+/// Number of candidate identities loaded (with their groups) at once.
+const CANDIDATES_BATCH_SIZE: usize = 500;
+
+/// An identity which could perform the requested action.
+#[derive(Clone, Debug)]
+pub struct WhoCanHit {
+    pub identity: String,
+
+    /// Allowed, or Abstain if the access depends on the request conditions.
+    pub outcome: AllowedOutcome,
+
+    /// The evaluations of the policies granting the access.
+    pub granted_by: Vec<PolicyEvaluation>,
+}
+
+impl WhoCanHit {
+    fn new(identity: String, result: &AllowedResult) -> Self {
+        let granted_by = match result.outcome() {
+            AllowedOutcome::Allowed => result.decided_by().into_iter().cloned().collect(),
+            _ => result
+                .get_evaluations()
+                .iter()
+                .filter(|e| e.effect == PolicyEffect::Allow && e.matched && !e.full)
+                .cloned()
+                .collect(),
+        };
+
+        WhoCanHit {
+            identity,
+            outcome: result.outcome(),
+            granted_by,
+        }
+    }
+}
+
+impl ToJson for WhoCanHit {
+    fn to_json(&self) -> Map<String, Value> {
+        let mut result = Map::new();
+        result.insert(
+            String::from("identity"),
+            Value::from(self.identity.as_str()),
+        );
+        result.insert(
+            String::from("granted_by"),
+            Value::from(
+                self.granted_by
+                    .iter()
+                    .map(|e| e.to_value())
+                    .collect::<Vec<Value>>(),
+            ),
+        );
+
+        result
+    }
+}
+
+/// Loads all the allow policies (linked and inline) which could match
+/// the given action and resource, regardless of their conditions.
+async fn candidate_policies(
+    storage: &dyn Storage,
+    action: &str,
+    resource: Option<&str>,
+) -> Result<Vec<CompletePolicy>, Error> {
+    collect_policies(storage, |p| {
+        p.statements()
+            .iter()
+            .any(|s| s.effect == PolicyEffect::Allow && s.may_match(action, resource))
+    })
+    .await
+}
+
+/// Collects the identities the given policy applies to, directly or through their groups.
+async fn policy_identities(
+    storage: &dyn Storage,
+    policy: &CompletePolicy,
+    identities: &mut BTreeSet<String>,
+) -> Result<(), Error> {
+    match inline_policy_owner(&policy.id) {
+        Some((AttachmentKind::Identity, id)) => {
+            identities.insert(id.to_string());
+        }
+        Some((_, id)) => {
+            if let Some(group) = storage.find_group(id).await? {
+                for identity in &group.identities {
+                    identities.insert(identity.get_id().clone());
+                }
+            }
+        }
+        Option::None => {
+            for kind in [AttachmentKind::Identity, AttachmentKind::InheritedIdentity] {
+                let mut options = ListOptions {
+                    limit: 500,
+                    ..ListOptions::default()
+                };
+
+                loop {
+                    let page = storage
+                        .list_policy_attachments(&policy.id, kind, &options)
+                        .await?;
+                    identities.extend(page.items.into_iter().map(|a| a.id));
+
+                    match page.next_cursor {
+                        Option::None => break,
+                        cursor => options.cursor = cursor,
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Finds the identities which could perform the given action on the given resource.
+///
+/// Only the identities attached (directly or through a group) to an allow policy
+/// possibly matching the request are evaluated. Each candidate is then checked
+/// as in a single allowed request, so that deny policies are taken into account.
+/// Identities with an Abstain outcome are returned as conditional hits.
+/// Hits are sorted by identity id.
+pub async fn who_can(
+    storage: &dyn Storage,
+    action: &str,
+    resource: Option<&str>,
+    params: &Value,
+) -> Result<Vec<WhoCanHit>, Error> {
+    let mut candidates = BTreeSet::new();
+    for policy in candidate_policies(storage, action, resource).await? {
+        policy_identities(storage, &policy, &mut candidates).await?;
+    }
+
+    let candidates: Vec<String> = candidates.into_iter().collect();
+    let mut hits = vec![];
+    for ids in candidates.chunks(CANDIDATES_BATCH_SIZE) {
+        let mut identities: HashMap<String, Identity> = storage
+            .find_identities(ids)
+            .await?
+            .into_iter()
+            .map(|i| (i.get_id().clone(), i))
+            .collect();
+        let mut groups = storage.find_groups_for_identities(ids, false).await?;
+
+        for id in ids {
+            let identity = match identities.remove(id) {
+                Option::None => continue,
+                Option::Some(identity) => identity,
+            };
+
+            let mut params = params.clone();
+            if let Value::Object(params) = &mut params {
+                params
+                    .entry("subject")
+                    .or_insert_with(|| Value::from(id.as_str()));
+            }
+
+            let groups = groups.remove(id).unwrap_or_default();
+            let result =
+                allowed_for_identity(&identity, &groups, Option::Some(action), resource, &params);
+            if result.outcome() != AllowedOutcome::Denied {
+                hits.push(WhoCanHit::new(id.clone(), &result));
+            }
+        }
+    }
+
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::identity::group::Group;
+    use crate::identity::identity::Identity;
+    use crate::policy::allowed_result::AllowedOutcome;
+    use crate::policy::policy::CompletePolicy;
+    use crate::policy::policy_set::PolicySetTrait;
+    use crate::policy::{PolicyEffect, PolicyVersion};
+    use crate::storage::who_can::who_can;
+    use crate::storage::{MemoryStorage, Storage};
+    use crate::zephir_policy;
+    use serde_json::json;
+
+    #[async_std::test]
+    async fn who_can_should_find_allowed_and_conditional_identities() {
+        let storage = MemoryStorage::new();
+        let read = zephir_policy!(
+            "WhoCanRead",
+            PolicyVersion::Version1,
+            PolicyEffect::Allow,
+            vec!["s3:Get*"],
+            vec!["urn:bucket:*"]
+        )
+        .unwrap();
+        let deny = zephir_policy!(
+            "WhoCanDeny",
+            PolicyVersion::Version1,
+            PolicyEffect::Deny,
+            vec!["s3:*"]
+        )
+        .unwrap();
+        storage.save_policy(&read).await.unwrap();
+        storage.save_policy(&deny).await.unwrap();
+
+        let mut direct = Identity::new("WhoCanDirect", None).add_policy(read.clone());
+        let mut member = Identity::new("WhoCanMember", None);
+        let mut denied = Identity::new("WhoCanDenied", None).add_policy(deny);
+        let mut conditional = Identity::new("WhoCanConditional", None).set_inline_policy(
+            zephir_policy!(
+                "",
+                PolicyVersion::Version1,
+                PolicyEffect::Allow,
+                vec!["s3:GetObject"],
+                vec!["*"],
+                json!({ "Bool": { "mfa": true } })
+            )
+            .unwrap(),
+        );
+        let mut other = Identity::new("WhoCanOther", None);
+        for identity in [
+            &mut direct,
+            &mut member,
+            &mut denied,
+            &mut conditional,
+            &mut other,
+        ] {
+            storage.save_identity(identity).await.unwrap();
+        }
+
+        let mut group = Group::new("WhoCanGroup", None)
+            .add_policy(read)
+            .add_identity(Identity::new("WhoCanMember", None))
+            .add_identity(Identity::new("WhoCanDenied", None));
+        storage.save_group(&mut group).await.unwrap();
+
+        let hits = who_can(
+            &storage,
+            "s3:GetObject",
+            Some("urn:bucket:logs"),
+            &json!({}),
+        )
+        .await
+        .unwrap();
+
+        let ids: Vec<&str> = hits.iter().map(|h| h.identity.as_str()).collect();
+        assert_eq!(ids, vec!["WhoCanDirect", "WhoCanMember"]);
+        assert_eq!(hits[0].outcome, AllowedOutcome::Allowed);
+        assert_eq!(hits[0].granted_by[0].policy_id, "WhoCanRead");
+        assert_eq!(
+            hits[1].granted_by[0].source,
+            Some("group:WhoCanGroup".to_string())
+        );
+
+        let hits = who_can(
+            &storage,
+            "s3:GetObject",
+            Some("urn:bucket:logs"),
+            &json!({ "mfa": true }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(hits.len(), 3);
+        assert_eq!(hits[0].identity, "WhoCanConditional");
+        assert_eq!(hits[0].outcome, AllowedOutcome::Allowed);
+        assert_eq!(
+            hits[0].granted_by[0].policy_id,
+            "__embedded_policy_identity_WhoCanConditional__"
+        );
+
+        let hits = who_can(&storage, "s3:GetObject", None, &json!({}))
+            .await
+            .unwrap();
+
+        let ids: Vec<&str> = hits.iter().map(|h| h.identity.as_str()).collect();
+        assert_eq!(ids, vec!["WhoCanDirect", "WhoCanMember"]);
+        assert_eq!(hits[0].outcome, AllowedOutcome::Abstain);
+        assert_eq!(hits[0].granted_by[0].policy_id, "WhoCanRead");
+    }
+
+    #[async_std::test]
+    async fn who_can_should_find_identities_through_negated_templates() {
+        let storage = MemoryStorage::new();
+        let others = CompletePolicy::from_statements(
+            "WhoCanOthers".to_string(),
+            &json!([{
+                "Effect": "Allow",
+                "NotAction": ["${zephir:subject}:*"],
+                "Resource": "*",
+            }]),
+        )
+        .unwrap();
+        storage.save_policy(&others).await.unwrap();
+
+        let mut alice = Identity::new("WhoCanAlice", None).add_policy(others.clone());
+        let mut bob = Identity::new("WhoCanBob", None);
+        storage.save_identity(&mut alice).await.unwrap();
+        storage.save_identity(&mut bob).await.unwrap();
+
+        let mut group = Group::new("WhoCanOthersGroup", None)
+            .add_policy(others)
+            .add_identity(Identity::new("WhoCanBob", None));
+        storage.save_group(&mut group).await.unwrap();
+
+        let hits = who_can(
+            &storage,
+            "WhoCanAlice:Read",
+            Some("urn:docs:report"),
+            &json!({}),
+        )
+        .await
+        .unwrap();
+
+        let ids: Vec<&str> = hits.iter().map(|h| h.identity.as_str()).collect();
+        assert_eq!(ids, vec!["WhoCanBob"]);
+        assert_eq!(hits[0].outcome, AllowedOutcome::Allowed);
+        assert_eq!(
+            hits[0].granted_by[0].source,
+            Some("group:WhoCanOthersGroup".to_string())
+        );
+
+        let hits = who_can(&storage, "WhoCanBob:Read", None, &json!({}))
+            .await
+            .unwrap();
+
+        let ids: Vec<&str> = hits.iter().map(|h| h.identity.as_str()).collect();
+        assert_eq!(ids, vec!["WhoCanAlice"]);
+    }
+}
@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// An arbitrary-precision decimal number, compared exactly.
+///
+/// The number is stored normalized as `±0.digits × 10^exponent`, without leading
+/// or trailing zeros in the digits: equal numbers have equal representations
+/// (ex: "1.50", "15e-1" and "0.15E1" are all stored as 0.15 × 10^1).
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Decimal {
+    negative: bool,
+    digits: String,
+    exponent: i64,
+}
+
+impl Decimal {
+    /// Gets the decimal value of a JSON number, or of a string containing a number.
+    ///
+    /// With the serde_json arbitrary_precision feature enabled, numbers keep their
+    /// original representation and are converted without loss of precision.
+    pub fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Number(n) => n.to_string().parse().ok(),
+            Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    /// Gets the comparable magnitude of the number (its absolute value).
+    fn magnitude(&self) -> (bool, i64, &[u8]) {
+        (!self.is_zero(), self.exponent, self.digits.as_bytes())
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = String;
+
+    /// Parses a decimal number, in plain (ex: "-12.5") or scientific notation (ex: "1.25e-3").
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!(r#""{}" is not a valid number"#, s);
+        let (negative, unsigned) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+
+        let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+            Some(i) => (
+                &unsigned[..i],
+                unsigned[i + 1..].parse::<i64>().map_err(|_| error())?,
+            ),
+            None => (unsigned, 0),
+        };
+
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if (integer.is_empty() && fraction.is_empty())
+            || !integer
+                .bytes()
+                .chain(fraction.bytes())
+                .all(|c| c.is_ascii_digit())
+        {
+            return Err(error());
+        }
+
+        let digits = format!("{}{}", integer, fraction);
+        let significant = digits.trim_start_matches('0');
+        let leading_zeros = (digits.len() - significant.len()) as i64;
+        let significant = significant.trim_end_matches('0');
+        if significant.is_empty() {
+            return Ok(Decimal {
+                negative: false,
+                digits: String::new(),
+                exponent: 0,
+            });
+        }
+
+        let exponent = exponent
+            .checked_add(integer.len() as i64 - leading_zeros)
+            .ok_or_else(error)?;
+
+        Ok(Decimal {
+            negative,
+            digits: significant.to_string(),
+            exponent,
+        })
+    }
+}
+
+impl Display for Decimal {
+    /// Formats the number in scientific notation (ex: "-1.25e-3").
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        let sign = if self.negative { "-" } else { "" };
+        let (first, rest) = self.digits.split_at(1);
+        if rest.is_empty() {
+            write!(f, "{}{}e{}", sign, first, self.exponent - 1)
+        } else {
+            write!(f, "{}{}.{}e{}", sign, first, rest, self.exponent - 1)
+        }
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Zero has no sign: it is compared as a positive number with the smallest magnitude.
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => self.magnitude().cmp(&other.magnitude()),
+            (true, true) => other.magnitude().cmp(&self.magnitude()),
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::decimal::Decimal;
+    use serde_json::Value;
+    use std::cmp::Ordering;
+
+    fn dec(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn equal_numbers_should_be_normalized() {
+        assert_eq!(dec("1.50"), dec("15e-1"));
+        assert_eq!(dec("0.15E1"), dec("1.5"));
+        assert_eq!(dec("-0"), dec("0.000"));
+        assert_eq!(dec("100"), dec("1e2"));
+        assert_eq!(dec("-0.0012").to_string(), "-1.2e-3");
+        assert_eq!(dec("1200").to_string(), "1.2e3");
+    }
+
+    #[test]
+    fn numbers_should_be_compared_exactly() {
+        assert_eq!(dec("0.1").cmp(&dec("0.10000000000000001")), Ordering::Less);
+        assert_eq!(
+            dec("9007199254740993").cmp(&dec("9007199254740992")),
+            Ordering::Greater
+        );
+        assert_eq!(dec("-2.5").cmp(&dec("-2.45")), Ordering::Less);
+        assert_eq!(dec("-1").cmp(&dec("0")), Ordering::Less);
+        assert_eq!(dec("0").cmp(&dec("0.001")), Ordering::Less);
+        assert_eq!(dec("0").cmp(&dec("-0.001")), Ordering::Greater);
+        assert_eq!(dec("12").cmp(&dec("9.99")), Ordering::Greater);
+        assert_eq!(dec("1e-5").cmp(&dec("0.00001")), Ordering::Equal);
+    }
+
+    #[test]
+    fn invalid_numbers_should_not_be_parsed() {
+        for s in [
+            "", "-", ".", "1.2.3", "1e", "e3", "abc", "1,5", "0x10", "NaN",
+        ] {
+            assert!(s.parse::<Decimal>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn decimals_should_be_read_from_json_values() {
+        let value: Value = serde_json::from_str("123456789012345678901234567890.5").unwrap();
+        assert_eq!(
+            Decimal::from_value(&value),
+            Some(dec("123456789012345678901234567890.5"))
+        );
+        assert_eq!(Decimal::from_value(&Value::from(" 2.5 ")), Some(dec("2.5")));
+        assert_eq!(Decimal::from_value(&Value::from(true)), None);
+    }
+
+    #[test]
+    fn decimals_should_be_serializable() {
+        let d = dec("-3.14159265358979323846264338327950288");
+        let serialized = serde_json::to_string(&d).unwrap();
+        assert_eq!(serde_json::from_str::<Decimal>(&serialized).unwrap(), d);
+    }
+}
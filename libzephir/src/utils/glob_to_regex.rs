@@ -1,15 +1,110 @@
+use crate::err::Error;
 use pcre2::bytes::{Regex, RegexBuilder};
+use std::iter::Peekable;
+use std::str::Chars;
 
-pub fn from_str(glob: &str) -> Regex {
-    from_string(glob.to_string())
+pub fn from_str(glob: &str) -> Result<Regex, Error> {
+    build(glob, to_pattern(glob)?.as_str())
 }
 
-pub fn from_string(glob: String) -> Regex {
+pub fn from_string(glob: String) -> Result<Regex, Error> {
+    from_str(glob.as_str())
+}
+
+/// Converts a glob into a regex matching whole values only:
+/// "core:Get" matches "core:Get", but neither "core:GetSecret" nor "xcore:Get".
+pub fn from_str_anchored(glob: &str) -> Result<Regex, Error> {
+    build(glob, format!("^(?:{})$", to_pattern(glob)?).as_str())
+}
+
+/// Converts a glob of a version 1 policy into a regex, with the legacy grammar:
+/// brackets are passed through to the regex and `**` only spans segments after a colon.
+pub fn from_str_legacy(glob: &str) -> Result<Regex, Error> {
+    build(glob, to_legacy_pattern(glob).as_str())
+}
+
+/// Whether the glob matches the same values, anchored or not.
+/// Only the match-all glob is not affected by anchoring.
+pub fn is_anchoring_neutral(glob: &str) -> bool {
+    glob == "*"
+}
+
+fn build(glob: &str, regex: &str) -> Result<Regex, Error> {
+    RegexBuilder::new()
+        .jit_if_available(true)
+        .build(regex)
+        .map_err(|e| Error::invalid_glob(glob, e))
+}
+
+/// Characters to be escaped when used literally in a regex.
+const REGEX_SPECIAL_CHARS: &str = r".()|+^$*?[]{}\";
+
+fn push_literal(regex: &mut String, c: char) {
+    if REGEX_SPECIAL_CHARS.contains(c) {
+        regex.push('\\');
+    }
+
+    regex.push(c);
+}
+
+/// Converts a glob into an (unanchored) regex pattern.
+///
+/// Segments are separated by colons. The supported syntax is:
+/// - `*` matches any sequence of characters within a segment
+/// - `**` matches any sequence of characters, across segments
+/// - `?` matches a single character within a segment
+/// - `[abc]` and `[a-z]` match a character of the class,
+///   `[!abc]` (or `[^abc]`) a character out of the class, within a segment
+/// - `{a,b}` matches one of the alternatives, which can contain any other construct
+/// - `\` escapes the following character
+///
+/// A lone `*` matches any non-empty value.
+pub fn to_pattern(glob: &str) -> Result<String, Error> {
     if glob == "*" {
-        return RegexBuilder::new()
-            .jit_if_available(true)
-            .build(r".+")
-            .unwrap();
+        return Ok(String::from(".+"));
+    }
+
+    let mut chars = glob.chars().peekable();
+    let mut regex = String::new();
+    let mut in_curlies = 0;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                regex += ".*";
+            }
+            '*' => regex += "[^:]*",
+            '?' => regex += "[^:]",
+            '[' => parse_class(glob, &mut chars, &mut regex)?,
+            '{' => {
+                in_curlies += 1;
+                regex.push('(');
+            }
+            '}' if in_curlies > 0 => {
+                in_curlies -= 1;
+                regex.push(')');
+            }
+            ',' if in_curlies > 0 => regex.push('|'),
+            '\\' => match chars.next() {
+                Some(escaped) => push_literal(&mut regex, escaped),
+                None => return Err(Error::invalid_glob(glob, "trailing escape character")),
+            },
+            c => push_literal(&mut regex, c),
+        }
+    }
+
+    if in_curlies > 0 {
+        return Err(Error::invalid_glob(glob, "unclosed alternatives group"));
+    }
+
+    Ok(regex)
+}
+
+/// Converts a glob into an (unanchored) regex pattern, with the grammar of version 1 policies.
+pub fn to_legacy_pattern(glob: &str) -> String {
+    if glob == "*" {
+        return String::from(".+");
     }
 
     let mut escaping = false;
@@ -69,29 +164,92 @@ pub fn from_string(glob: String) -> Regex {
         escaping = false;
     }
 
-    RegexBuilder::new()
-        .jit_if_available(true)
-        .build(regex.as_str())
-        .unwrap()
+    regex
+}
+
+/// Parses a character class, the opening bracket being already consumed.
+fn parse_class(glob: &str, chars: &mut Peekable<Chars>, regex: &mut String) -> Result<(), Error> {
+    let negated = matches!(chars.peek(), Some('!') | Some('^'));
+    if negated {
+        chars.next();
+    }
+
+    let mut class = String::new();
+    loop {
+        let (c, escaped) = match chars.next() {
+            None => return Err(Error::invalid_glob(glob, "unclosed character class")),
+            Some(']') if !class.is_empty() => break,
+            Some('\\') => match chars.next() {
+                Some(c) => (c, true),
+                None => return Err(Error::invalid_glob(glob, "trailing escape character")),
+            },
+            Some(c) => (c, false),
+        };
+
+        let is_range = c == '-' && !escaped && !class.is_empty() && chars.peek() != Some(&']');
+        if !is_range && matches!(c, '\\' | ']' | '[' | '^' | '-') {
+            class.push('\\');
+        }
+
+        class.push(c);
+    }
+
+    regex.push('[');
+    if negated {
+        // Negated classes do not cross segments, as "?" does.
+        regex.push('^');
+        regex.push(':');
+    }
+
+    regex.push_str(&class);
+    regex.push(']');
+
+    Ok(())
+}
+
+/// Escapes a value, so that it is matched literally when used in a glob.
+pub fn escape(value: &str) -> String {
+    let mut glob = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '{' | '}' | ',' | '\\') {
+            glob.push('\\');
+        }
+
+        glob.push(c);
+    }
+
+    glob
+}
+
+/// Whether the regex matches the whole given value.
+pub fn matches_whole(regex: &Regex, value: &str) -> bool {
+    match regex.find(value.as_bytes()) {
+        Ok(Some(m)) => m.start() == 0 && m.end() == value.len(),
+        _ => false,
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::utils::glob_to_regex::{from_str, from_string};
+    use crate::err::ErrorKind;
+    use crate::utils::glob_to_regex::{
+        escape, from_str, from_str_anchored, from_str_legacy, from_string, matches_whole,
+        to_legacy_pattern, to_pattern,
+    };
 
     #[test]
     fn from_string_should_return_match_all_regex() {
-        assert_eq!(from_string("*".to_string()).as_str(), ".+");
+        assert_eq!(from_string("*".to_string()).unwrap().as_str(), ".+");
     }
 
     #[test]
     fn from_string_should_work_correctly() {
         assert_eq!(
-            from_string("foo_{bar,foo}.*".to_string()).as_str(),
+            from_string("foo_{bar,foo}.*".to_string()).unwrap().as_str(),
             "foo_(bar|foo)\\.[^:]*"
         );
         assert_eq!(
-            from_string("foo_ba?.\\*".to_string()).as_str(),
+            from_string("foo_ba?.\\*".to_string()).unwrap().as_str(),
             "foo_ba[^:]\\.\\*"
         );
     }
@@ -99,8 +257,107 @@ mod tests {
     #[test]
     fn from_str_should_work_correctly() {
         assert_eq!(
-            from_str("foo_{bar,foo}.*").as_str(),
+            from_str("foo_{bar,foo}.*").unwrap().as_str(),
             "foo_(bar|foo)\\.[^:]*"
         );
     }
+
+    #[test]
+    fn matches_whole_should_not_match_substrings() {
+        assert_eq!(
+            matches_whole(&from_str("s3:*").unwrap(), "s3:GetObject"),
+            true
+        );
+        assert_eq!(
+            matches_whole(&from_str("s3:*").unwrap(), "foos3:GetObject"),
+            false
+        );
+        assert_eq!(
+            matches_whole(&from_str("s3:Get").unwrap(), "s3:GetObject"),
+            false
+        );
+    }
+
+    #[test]
+    fn from_str_anchored_should_match_whole_values() {
+        let regex = from_str_anchored("core:Get").unwrap();
+        assert_eq!(regex.as_str(), "^(?:core:Get)$");
+        assert_eq!(regex.is_match(b"core:Get").unwrap(), true);
+        assert_eq!(regex.is_match(b"core:GetSecret").unwrap(), false);
+        assert_eq!(regex.is_match(b"xcore:Getter").unwrap(), false);
+
+        let regex = from_str_anchored("s3:{Get,Put}*").unwrap();
+        assert_eq!(regex.is_match(b"s3:GetObject").unwrap(), true);
+        assert_eq!(regex.is_match(b"s3:DeleteObject").unwrap(), false);
+        assert_eq!(regex.is_match(b"s3:GetObject:acl").unwrap(), false);
+
+        assert_eq!(from_str_anchored("*").unwrap().as_str(), "^(?:.+)$");
+    }
+
+    #[test]
+    fn character_classes_should_be_supported() {
+        assert_eq!(to_pattern("s3:[a-c]ucket").unwrap(), "s3:[a-c]ucket");
+        assert_eq!(to_pattern("s3:[!x]").unwrap(), "s3:[^:x]");
+        assert_eq!(to_pattern("s3:[^x-]").unwrap(), "s3:[^:x\\-]");
+        assert_eq!(to_pattern("s3:[]a]").unwrap(), "s3:[\\]a]");
+
+        let regex = from_str_anchored("urn:bucket:[!a]*").unwrap();
+        assert_eq!(regex.is_match(b"urn:bucket:logs").unwrap(), true);
+        assert_eq!(regex.is_match(b"urn:bucket:audit").unwrap(), false);
+        assert_eq!(regex.is_match(b"urn:bucket::x").unwrap(), false);
+    }
+
+    #[test]
+    fn double_star_should_match_across_segments_anywhere() {
+        let regex = from_str_anchored("urn:**:object").unwrap();
+        assert_eq!(regex.is_match(b"urn:bucket:logs:object").unwrap(), true);
+        assert_eq!(regex.is_match(b"urn:bucket:logs:other").unwrap(), false);
+
+        let regex = from_str_anchored("s3:Get**").unwrap();
+        assert_eq!(regex.is_match(b"s3:GetObject:acl").unwrap(), true);
+    }
+
+    #[test]
+    fn escaped_characters_should_be_literal() {
+        assert_eq!(to_pattern("a\\[b\\]\\{c\\}").unwrap(), "a\\[b\\]\\{c\\}");
+        assert_eq!(to_pattern("a\\\\").unwrap(), "a\\\\");
+        assert_eq!(to_pattern("a,b}").unwrap(), "a,b\\}");
+    }
+
+    #[test]
+    fn escaped_values_should_match_literally() {
+        let regex = from_str_anchored(&escape("a*{b,c}?")).unwrap();
+        assert_eq!(regex.is_match(b"a*{b,c}?").unwrap(), true);
+        assert_eq!(regex.is_match(b"axbx").unwrap(), false);
+    }
+
+    #[test]
+    fn legacy_globs_should_keep_the_version_1_grammar() {
+        assert_eq!(to_legacy_pattern("*"), ".+");
+        assert_eq!(
+            to_legacy_pattern("foo_{bar,foo}.*"),
+            "foo_(bar|foo)\\.[^:]*"
+        );
+        assert_eq!(to_legacy_pattern("s3:[a-c]\\x"), "s3:[a-c]x");
+
+        let regex = from_str_legacy("s3:Get**").unwrap();
+        assert_eq!(matches_whole(&regex, "s3:GetObject"), true);
+        assert_eq!(matches_whole(&regex, "s3:GetObject:acl"), false);
+
+        let regex = from_str_legacy("urn:**").unwrap();
+        assert_eq!(matches_whole(&regex, "urn:bucket:logs"), true);
+    }
+
+    #[test]
+    fn invalid_globs_should_return_errors() {
+        for glob in ["s3:[abc", "s3:{Get,Put", "s3:Get\\", "s3:[z-a]", "s3:[a\\"] {
+            let error = from_str(glob).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidGlobError);
+        }
+
+        assert_eq!(
+            from_str("s3:[abc").unwrap_err().to_string(),
+            r#"Invalid glob "s3:[abc": unclosed character class"#
+        );
+    }
 }
@@ -1,2 +1,3 @@
+pub mod decimal;
 pub mod glob_to_regex;
 pub(crate) mod string_utils;
@@ -0,0 +1,90 @@
+use libzephir::err::{Error, ErrorKind};
+use libzephir::storage::Storage;
+
+/// Represents the command requested from the command line.
+pub(crate) enum Command {
+    Serve,
+    MigrateUp,
+    MigrateDown(Option<i64>),
+    MigrateStatus,
+}
+
+impl Command {
+    /// Parses the command line arguments (program name excluded).
+    pub(crate) fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Command, Error> {
+        let command = match args.next().as_deref() {
+            Option::None | Some("serve") => Command::Serve,
+            Some("migrate") => match args.next().as_deref() {
+                Option::None | Some("up") => Command::MigrateUp,
+                Some("down") => Command::MigrateDown(match args.next() {
+                    Option::None => Option::None,
+                    Some(version) => Some(version.parse().map_err(|_| {
+                        Error::new(
+                            ErrorKind::UnknownError,
+                            format!(r#"Invalid migration version "{}""#, version),
+                        )
+                    })?),
+                }),
+                Some("status") => Command::MigrateStatus,
+                Some(sub) => {
+                    return Err(Error::new(
+                        ErrorKind::UnknownError,
+                        format!(
+                            r#"Unknown migrate command "{}". Usage: zephir migrate [up|down [version]|status]"#,
+                            sub
+                        ),
+                    ))
+                }
+            },
+            Some(cmd) => {
+                return Err(Error::new(
+                    ErrorKind::UnknownError,
+                    format!(
+                        r#"Unknown command "{}". Usage: zephir [serve|migrate]"#,
+                        cmd
+                    ),
+                ))
+            }
+        };
+
+        match args.next() {
+            Option::None => Ok(command),
+            Some(arg) => Err(Error::new(
+                ErrorKind::UnknownError,
+                format!(r#"Unexpected argument "{}""#, arg),
+            )),
+        }
+    }
+
+    /// Runs a migrate command against the given storage.
+    pub(crate) async fn run_migrate(&self, storage: &dyn Storage) -> Result<(), Error> {
+        match self {
+            Command::Serve => {}
+            Command::MigrateUp => {
+                storage.migrate_up().await?;
+                println!("Database schema is up to date");
+            }
+            Command::MigrateDown(target) => {
+                storage.migrate_down(*target).await?;
+                println!("Migrations reverted");
+            }
+            Command::MigrateStatus => {
+                println!("{:<10} {:<10} Description", "Version", "Status");
+                for migration in storage.migration_status().await? {
+                    println!(
+                        "{:<10} {:<10} {}",
+                        migration.version,
+                        if migration.applied {
+                            "applied"
+                        } else {
+                            "pending"
+                        },
+                        migration.description
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use actix_web::{HttpResponse, ResponseError};
 use derive_more::{Display, From};
-use libzephir::err::Error as LibError;
+use libzephir::err::{Error as LibError, ErrorKind};
 use libzephir::policy::allowed_result::AllowedResult;
 use libzephir::policy::policy::ToJson;
 use serde_json::json;
@@ -14,6 +14,8 @@ pub enum ZephirError {
     AllowedError,
     ValidationError(ValidationErrors),
     InvalidRequestError,
+    #[from(ignore)]
+    Conflict(serde_json::Value),
 
     ServerError(LibError),
 }
@@ -32,6 +34,17 @@ impl ResponseError for ZephirError {
             ZephirError::AllowedError => {
                 HttpResponse::Forbidden().json(AllowedResult::denied().to_value())
             }
+            ZephirError::ServerError(ref err)
+                if matches!(
+                    err.kind(),
+                    ErrorKind::InvalidGlobError | ErrorKind::InvalidRegexError
+                ) =>
+            {
+                HttpResponse::BadRequest().json(json!({
+                    "status_code": 400,
+                    "errors": [err.to_string()]
+                }))
+            }
             ZephirError::ServerError(ref err) => HttpResponse::InternalServerError().json(json!({
                 "status_code": 500,
                 "errors": err.to_string()
@@ -40,6 +53,10 @@ impl ResponseError for ZephirError {
                 "status_code": 400,
                 "errors": serde_json::to_value(err.field_errors()).unwrap()
             })),
+            ZephirError::Conflict(ref err) => HttpResponse::Conflict().json(json!({
+                "status_code": 409,
+                "errors": err
+            })),
             ZephirError::InvalidRequestError => HttpResponse::BadRequest().json(json!({
                 "status_code": 400,
                 "errors": [
@@ -1,19 +1,121 @@
 use crate::err::ZephirError;
 use actix_web::{post, web, HttpResponse};
-use libzephir::identity::role::Role;
-use libzephir::policy::allowed_result::AllowedOutcome;
+use libzephir::identity::authorization::{
+    allowed_batch, allowed_for_identity, allowed_partial_for_identity, AllowedCheck,
+};
+use libzephir::policy::allowed_result::{AllowedOutcome, AllowedResult};
 use libzephir::policy::policy::ToJson;
-use libzephir::storage::StorageManager;
+use libzephir::policy::sql_filter::{SqlDialect, SqlFilterBuilder};
+use libzephir::storage::{who_can, Storage};
 use log::{debug, log_enabled, trace, Level};
-use serde::Deserialize;
-use serde_json::Value;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
 use std::convert::TryFrom;
+use validator::Validate;
+
+lazy_static! {
+    static ref RE_COLUMN: Regex =
+        Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*(\.[A-Za-z_][A-Za-z0-9_]*)?$").unwrap();
+    static ref RE_DIALECT: Regex = Regex::new(r"^(postgres|mysql|sqlite)$").unwrap();
+}
 
 #[derive(Deserialize)]
 pub struct AllowedInfo {
     subject: String,
     action: String,
     resource: Option<String>,
+    explain: bool,
+    residual_conditions: bool,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct AllowedQuery {
+    #[serde(default)]
+    explain: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct BatchCheck {
+    action: String,
+    resource: Option<String>,
+    #[serde(default)]
+    context: Value,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub(crate) struct AllowedBatchRequest {
+    #[validate(length(min = 1, message = "The value is too short"))]
+    subject: String,
+    #[validate(length(min = 1, max = 100, message = "Invalid number of checks."))]
+    checks: Vec<BatchCheck>,
+    #[serde(default)]
+    explain: bool,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub(crate) struct WhoCanRequest {
+    #[validate(length(min = 1, message = "The value is too short"))]
+    action: String,
+    resource: Option<String>,
+    #[serde(default)]
+    context: Value,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub(crate) struct AllowedFilterRequest {
+    #[validate(length(min = 1, message = "The value is too short"))]
+    subject: String,
+    #[validate(length(min = 1, message = "The value is too short"))]
+    action: String,
+    #[validate(regex(path = "RE_COLUMN", message = "Invalid field."))]
+    column: String,
+    #[validate(regex(path = "RE_DIALECT", message = "Invalid field."))]
+    dialect: Option<String>,
+    #[validate(range(min = 1, message = "Invalid field."))]
+    first_param: Option<usize>,
+    #[serde(default)]
+    context: Value,
+}
+
+/// Builds the condition parameters from the request context,
+/// making action and resource available to the conditions.
+fn context_params(
+    context: &Value,
+    action: &str,
+    resource: Option<&str>,
+) -> Result<Map<String, Value>, ZephirError> {
+    let mut params = match context {
+        Value::Null => Map::new(),
+        Value::Object(context) => context.clone(),
+        _ => return Err(ZephirError::InvalidRequestError),
+    };
+
+    params
+        .entry("action")
+        .or_insert_with(|| Value::from(action));
+    if let Some(resource) = resource {
+        params
+            .entry("resource")
+            .or_insert_with(|| Value::from(resource));
+    }
+
+    Ok(params)
+}
+
+/// Builds the condition parameters of a batch check.
+/// As in a single check, subject, action and resource are available to the conditions.
+fn check_params(subject: &str, check: &BatchCheck) -> Result<Value, ZephirError> {
+    let mut params = context_params(
+        &check.context,
+        check.action.as_str(),
+        check.resource.as_deref(),
+    )?;
+    params
+        .entry("subject")
+        .or_insert_with(|| Value::from(subject));
+
+    Ok(Value::Object(params))
 }
 
 impl TryFrom<&Value> for AllowedInfo {
@@ -47,21 +149,44 @@ impl TryFrom<&Value> for AllowedInfo {
             }
             _ => return Err(ZephirError::InvalidRequestError),
         };
+        let explain = match info.get("explain") {
+            None | Some(Value::Null) => false,
+            Some(Value::Bool(explain)) => *explain,
+            _ => return Err(ZephirError::InvalidRequestError),
+        };
+        let residual_conditions = match info.get("residual_conditions") {
+            None | Some(Value::Null) => false,
+            Some(Value::Bool(residual_conditions)) => *residual_conditions,
+            _ => return Err(ZephirError::InvalidRequestError),
+        };
 
         Ok(AllowedInfo {
             subject,
             action,
             resource,
+            explain,
+            residual_conditions,
         })
     }
 }
 
+fn result_to_json(result: &AllowedResult, explain: bool) -> Value {
+    let mut json = result.to_json();
+    if explain {
+        json.insert(String::from("explain"), result.explain());
+    }
+
+    Value::Object(json)
+}
+
 #[post("/allowed")]
 pub(crate) async fn allowed_action(
     body: web::Json<Value>,
-    storage: web::Data<StorageManager>,
+    query: web::Query<AllowedQuery>,
+    storage: web::Data<dyn Storage>,
 ) -> Result<HttpResponse, ZephirError> {
     let info = AllowedInfo::try_from(&body.0)?;
+    let explain = info.explain || query.explain;
     let storage = storage.get_ref();
     let identity = storage.find_identity(&info.subject).await?.ok_or_else(|| {
         trace!(
@@ -79,47 +204,148 @@ pub(crate) async fn allowed_action(
         );
     }
 
-    let action = Option::Some(&info.action);
-    let resource = info.resource.as_ref();
+    // Conditions on keys missing from the request are not matching,
+    // unless the residual conditions have been explicitly requested.
+    let groups = storage.find_groups_for_identity(&identity, false).await?;
+    let evaluate = if info.residual_conditions {
+        allowed_partial_for_identity
+    } else {
+        allowed_for_identity
+    };
+    let result = evaluate(
+        &identity,
+        &groups,
+        Option::Some(info.action.as_str()),
+        info.resource.as_deref(),
+        &body.0,
+    );
 
-    let mut result = identity.allowed(action, resource, &body.0);
-    match result.outcome() {
-        AllowedOutcome::Denied => {
-            trace!(r#"Identity policies denied access. Returning deny result."#);
-            Ok(HttpResponse::Forbidden().json(result.to_value()))
-        }
-        _ => {
+    let mut builder = if result.outcome() == AllowedOutcome::Denied {
+        HttpResponse::Forbidden()
+    } else {
+        HttpResponse::Ok()
+    };
+    debug!(
+        r#"{} access for action "{}" on resource {}"#,
+        match result.outcome() {
+            AllowedOutcome::Allowed => "Allowed",
+            AllowedOutcome::Abstain => "Conditional allowed",
+            AllowedOutcome::Denied => "Denied",
+        },
+        info.action,
+        info.resource.as_deref().unwrap_or("NULL")
+    );
+
+    Ok(builder.json(result_to_json(&result, explain)))
+}
+
+#[post("/allowed/batch")]
+pub(crate) async fn allowed_batch_action(
+    body: web::Json<AllowedBatchRequest>,
+    query: web::Query<AllowedQuery>,
+    storage: web::Data<dyn Storage>,
+) -> Result<HttpResponse, ZephirError> {
+    body.validate()?;
+    let explain = body.explain || query.explain;
+
+    let mut checks = vec![];
+    for check in &body.checks {
+        checks.push(AllowedCheck {
+            action: check.action.clone(),
+            resource: check.resource.clone().filter(|r| !r.is_empty()),
+            params: check_params(&body.subject, check)?,
+        });
+    }
+
+    let storage = storage.get_ref();
+    let results = match storage.find_identity(&body.subject).await? {
+        Option::None => {
             trace!(
-                r#"Identity policies {} access. Now evaluating groups policies..."#,
-                match result.outcome() {
-                    AllowedOutcome::Allowed => "allowed",
-                    AllowedOutcome::Abstain => "conditional allow",
-                    _ => "",
-                }
+                r#"Identity "{}" not found. Denying all the checks..."#,
+                body.subject.as_str()
             );
-
+            checks.iter().map(|_| AllowedResult::denied()).collect()
+        }
+        Option::Some(identity) => {
             let groups = storage.find_groups_for_identity(&identity, false).await?;
-            for g in groups {
-                result.merge(g.allowed(action, resource, &body.0));
-            }
+            allowed_batch(&identity, &groups, &checks)
+        }
+    };
 
-            let mut builder = if result.outcome() == AllowedOutcome::Denied {
-                HttpResponse::Forbidden()
-            } else {
-                HttpResponse::Ok()
-            };
-            debug!(
-                r#"{} access for action "{}" on resource {}"#,
-                match result.outcome() {
-                    AllowedOutcome::Allowed => "Allowed",
-                    AllowedOutcome::Abstain => "Conditional allowed",
-                    AllowedOutcome::Denied => "Denied",
-                },
-                action.unwrap(),
-                resource.unwrap_or(&"NULL".to_string())
-            );
+    Ok(HttpResponse::Ok().json(json!({
+        "results": results
+            .iter()
+            .map(|r| result_to_json(r, explain))
+            .collect::<Vec<Value>>(),
+    })))
+}
+
+#[post("/who-can")]
+pub(crate) async fn who_can_action(
+    body: web::Json<WhoCanRequest>,
+    storage: web::Data<dyn Storage>,
+) -> Result<HttpResponse, ZephirError> {
+    body.validate()?;
+
+    let resource = body.resource.as_deref().filter(|r| !r.is_empty());
+    let params = context_params(&body.context, body.action.as_str(), resource)?;
+    let hits = who_can(
+        storage.get_ref(),
+        body.action.as_str(),
+        resource,
+        &Value::Object(params),
+    )
+    .await?;
+
+    let (allowed, conditional): (Vec<_>, Vec<_>) = hits
+        .iter()
+        .partition(|h| h.outcome == AllowedOutcome::Allowed);
+
+    Ok(HttpResponse::Ok().json(json!({
+        "allowed": allowed.iter().map(|h| h.to_value()).collect::<Vec<Value>>(),
+        "conditional": conditional.iter().map(|h| h.to_value()).collect::<Vec<Value>>(),
+    })))
+}
+
+#[post("/allowed/filter")]
+pub(crate) async fn allowed_filter_action(
+    body: web::Json<AllowedFilterRequest>,
+    storage: web::Data<dyn Storage>,
+) -> Result<HttpResponse, ZephirError> {
+    body.validate()?;
+
+    let mut params = context_params(&body.context, body.action.as_str(), Option::None)?;
+    params
+        .entry("subject")
+        .or_insert_with(|| Value::from(body.subject.as_str()));
 
-            Ok(builder.json(result.to_value()))
+    let storage = storage.get_ref();
+    let result = match storage.find_identity(&body.subject).await? {
+        Option::None => AllowedResult::denied(),
+        Option::Some(identity) => {
+            let groups = storage.find_groups_for_identity(&identity, false).await?;
+            allowed_for_identity(
+                &identity,
+                &groups,
+                Option::Some(body.action.as_str()),
+                Option::None,
+                &Value::Object(params),
+            )
         }
-    }
+    };
+
+    let dialect = match body.dialect.as_deref() {
+        Some("mysql") => SqlDialect::MySql,
+        Some("sqlite") => SqlDialect::Sqlite,
+        _ => SqlDialect::Postgres,
+    };
+    let filter = SqlFilterBuilder::new(body.column.as_str(), dialect)?
+        .first_param(body.first_param.unwrap_or(1))
+        .build(&result)?;
+
+    let mut json = result.to_json();
+    json.insert(String::from("predicate"), Value::from(filter.predicate));
+    json.insert(String::from("params"), Value::from(filter.params));
+
+    Ok(HttpResponse::Ok().json(Value::Object(json)))
 }
@@ -1,12 +1,14 @@
 use crate::err::ZephirError;
+use crate::handlers::list::{page_to_json, ListQuery};
 use crate::handlers::policy::InlinePolicy;
-use actix_web::{get, patch, post, web, HttpResponse};
+use actix_web::{delete, get, patch, post, web, HttpResponse};
 use libzephir::identity::group::Group;
 use libzephir::policy::policy::{CompletePolicy, ToJson};
 use libzephir::policy::policy_set::PolicySetTrait;
-use libzephir::storage::StorageManager;
+use libzephir::storage::{ListOptions, Storage};
 use serde::de::Unexpected;
 use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
 use std::convert::TryFrom;
 use validator::Validate;
 
@@ -17,6 +19,8 @@ pub(crate) struct UpsertGroupRequest {
     linked_policies: Vec<String>,
     #[validate]
     inline_policy: Option<InlinePolicy>,
+    #[serde(default)]
+    attributes: Map<String, Value>,
 }
 
 type StringType<'a> = &'a str;
@@ -52,7 +56,7 @@ pub(crate) struct PatchGroupIdentitiesRequest {
 #[post("/groups")]
 pub(crate) async fn upsert_group(
     info: web::Json<UpsertGroupRequest>,
-    storage: web::Data<StorageManager>,
+    storage: web::Data<dyn Storage>,
 ) -> Result<HttpResponse, ZephirError> {
     info.validate()?;
     let inline_policy = match info.0.inline_policy {
@@ -60,7 +64,7 @@ pub(crate) async fn upsert_group(
         Option::Some(req_policy) => Option::Some(CompletePolicy::try_from(req_policy)?),
     };
 
-    let mut group = Group::new(info.0.id, inline_policy);
+    let mut group = Group::new(info.0.id, inline_policy).set_attributes(info.0.attributes);
     for ref p in info.0.linked_policies {
         match storage.find_policy(p).await? {
             Option::None => {
@@ -74,13 +78,24 @@ pub(crate) async fn upsert_group(
     Ok(HttpResponse::Ok().json(group.to_json()))
 }
 
+#[get("/groups")]
+pub(crate) async fn list_groups(
+    query: web::Query<ListQuery>,
+    storage: web::Data<dyn Storage>,
+) -> Result<HttpResponse, ZephirError> {
+    query.validate()?;
+    let page = storage.list_groups(&ListOptions::from(&query.0)).await?;
+
+    Ok(HttpResponse::Ok().json(page_to_json(&page)))
+}
+
 #[get("/group/{id}")]
 pub(crate) async fn get_group(
     path: web::Path<String>,
-    storage: web::Data<StorageManager>,
+    storage: web::Data<dyn Storage>,
 ) -> Result<HttpResponse, ZephirError> {
     let id = path.into_inner();
-    let result = storage.find_group(id).await?;
+    let result = storage.find_group(&id).await?;
     match result {
         Option::None => Err(ZephirError::NotFound),
         Option::Some(group) => Ok(HttpResponse::Ok().json(group.to_json())),
@@ -90,10 +105,10 @@ pub(crate) async fn get_group(
 #[get("/group/{id}/identities")]
 pub(crate) async fn get_group_identities(
     path: web::Path<String>,
-    storage: web::Data<StorageManager>,
+    storage: web::Data<dyn Storage>,
 ) -> Result<HttpResponse, ZephirError> {
     let id = path.into_inner();
-    let result = storage.find_group(id).await?;
+    let result = storage.find_group(&id).await?;
     match result {
         Option::None => Err(ZephirError::NotFound),
         Option::Some(group) => Ok(HttpResponse::Ok().json(
@@ -111,10 +126,10 @@ pub(crate) async fn get_group_identities(
 pub(crate) async fn patch_group_identities(
     info: web::Json<PatchGroupIdentitiesRequest>,
     path: web::Path<String>,
-    storage: web::Data<StorageManager>,
+    storage: web::Data<dyn Storage>,
 ) -> Result<HttpResponse, ZephirError> {
     let id = path.into_inner();
-    let result = storage.find_group(id).await?;
+    let result = storage.find_group(&id).await?;
     match result {
         Option::None => Err(ZephirError::NotFound),
         Option::Some(mut group) => {
@@ -136,3 +151,17 @@ pub(crate) async fn patch_group_identities(
         }
     }
 }
+
+#[delete("/group/{id}")]
+pub(crate) async fn delete_group(
+    path: web::Path<String>,
+    storage: web::Data<dyn Storage>,
+) -> Result<HttpResponse, ZephirError> {
+    let id = path.into_inner();
+    if storage.find_group(&id).await?.is_none() {
+        return Err(ZephirError::NotFound);
+    }
+
+    storage.delete_group(&id).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
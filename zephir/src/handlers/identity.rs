@@ -1,11 +1,14 @@
 use crate::err::ZephirError;
+use crate::handlers::list::{page_to_json, ListQuery};
 use crate::handlers::policy::InlinePolicy;
-use actix_web::{get, post, web, HttpResponse};
+use actix_web::{delete, get, post, web, HttpResponse};
+use libzephir::identity::authorization::{effective_policies, merge_by_action};
 use libzephir::identity::identity::Identity;
 use libzephir::policy::policy::{CompletePolicy, ToJson};
 use libzephir::policy::policy_set::PolicySetTrait;
-use libzephir::storage::StorageManager;
+use libzephir::storage::{ListOptions, Storage};
 use serde::Deserialize;
+use serde_json::{json, Map, Value};
 use std::convert::TryFrom;
 use validator::Validate;
 
@@ -16,12 +19,20 @@ pub(crate) struct UpsertIdentityRequest {
     linked_policies: Vec<String>,
     #[validate]
     inline_policy: Option<InlinePolicy>,
+    #[serde(default)]
+    attributes: Map<String, Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct EffectivePoliciesQuery {
+    #[serde(default)]
+    merged: bool,
 }
 
 #[post("/identities")]
 pub(crate) async fn upsert_identity(
     info: web::Json<UpsertIdentityRequest>,
-    storage: web::Data<StorageManager>,
+    storage: web::Data<dyn Storage>,
 ) -> Result<HttpResponse, ZephirError> {
     info.validate()?;
     let inline_policy = match info.0.inline_policy {
@@ -29,7 +40,7 @@ pub(crate) async fn upsert_identity(
         Option::Some(req_policy) => Option::Some(CompletePolicy::try_from(req_policy)?),
     };
 
-    let mut identity = Identity::new(info.0.id, inline_policy);
+    let mut identity = Identity::new(info.0.id, inline_policy).set_attributes(info.0.attributes);
     for ref p in info.0.linked_policies {
         match storage.find_policy(p).await? {
             Option::None => {
@@ -43,15 +54,73 @@ pub(crate) async fn upsert_identity(
     Ok(HttpResponse::Ok().json(identity.to_json()))
 }
 
+#[get("/identities")]
+pub(crate) async fn list_identities(
+    query: web::Query<ListQuery>,
+    storage: web::Data<dyn Storage>,
+) -> Result<HttpResponse, ZephirError> {
+    query.validate()?;
+    let page = storage
+        .list_identities(&ListOptions::from(&query.0))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(page_to_json(&page)))
+}
+
 #[get("/identity/{id}")]
 pub(crate) async fn get_identity(
     path: web::Path<String>,
-    storage: web::Data<StorageManager>,
+    storage: web::Data<dyn Storage>,
 ) -> Result<HttpResponse, ZephirError> {
     let id = path.into_inner();
-    let result = storage.find_identity(id).await?;
+    let result = storage.find_identity(&id).await?;
     match result {
         Option::None => Err(ZephirError::NotFound),
         Option::Some(identity) => Ok(HttpResponse::Ok().json(identity.to_json())),
     }
 }
+
+#[get("/identity/{id}/effective-policies")]
+pub(crate) async fn get_identity_effective_policies(
+    path: web::Path<String>,
+    query: web::Query<EffectivePoliciesQuery>,
+    storage: web::Data<dyn Storage>,
+) -> Result<HttpResponse, ZephirError> {
+    let id = path.into_inner();
+    let identity = storage
+        .find_identity(&id)
+        .await?
+        .ok_or(ZephirError::NotFound)?;
+    let groups = storage.find_groups_for_identity(&identity, false).await?;
+
+    let policies = effective_policies(&identity, &groups);
+    let mut result = json!({
+        "identity": id,
+        "policies": policies.iter().map(|p| p.to_value()).collect::<Vec<Value>>(),
+    });
+
+    if query.merged {
+        result["actions"] = Value::from(
+            merge_by_action(&policies)
+                .iter()
+                .map(|s| s.to_value())
+                .collect::<Vec<Value>>(),
+        );
+    }
+
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[delete("/identity/{id}")]
+pub(crate) async fn delete_identity(
+    path: web::Path<String>,
+    storage: web::Data<dyn Storage>,
+) -> Result<HttpResponse, ZephirError> {
+    let id = path.into_inner();
+    if storage.find_identity(&id).await?.is_none() {
+        return Err(ZephirError::NotFound);
+    }
+
+    storage.delete_identity(&id).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
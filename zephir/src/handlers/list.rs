@@ -0,0 +1,42 @@
+use libzephir::policy::policy::ToJson;
+use libzephir::storage::{ListOptions, Page, SortOrder};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use validator::Validate;
+
+lazy_static! {
+    static ref RE_ORDER: Regex = Regex::new(r"^(asc|desc)$").unwrap();
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub(crate) struct ListQuery {
+    prefix: Option<String>,
+    cursor: Option<String>,
+    #[validate(range(min = 1, max = 500, message = "Invalid limit."))]
+    limit: Option<usize>,
+    #[validate(regex(path = "RE_ORDER", message = "Invalid field."))]
+    order: Option<String>,
+}
+
+impl From<&ListQuery> for ListOptions {
+    fn from(query: &ListQuery) -> Self {
+        let default = ListOptions::default();
+        ListOptions {
+            prefix: query.prefix.clone().filter(|p| !p.is_empty()),
+            cursor: query.cursor.clone().filter(|c| !c.is_empty()),
+            limit: query.limit.unwrap_or(default.limit),
+            order: match query.order.as_deref() {
+                Some("desc") => SortOrder::Descending,
+                _ => SortOrder::Ascending,
+            },
+        }
+    }
+}
+
+pub(crate) fn page_to_json<T: ToJson>(page: &Page<T>) -> Value {
+    json!({
+        "items": page.items.iter().map(|i| Value::from(i.to_json())).collect::<Vec<Value>>(),
+        "next_cursor": page.next_cursor,
+    })
+}
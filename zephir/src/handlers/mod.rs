@@ -1,6 +1,7 @@
 mod allowed;
 mod group;
 mod identity;
+mod list;
 mod policy;
 mod status;
 
@@ -8,17 +9,28 @@ pub(crate) use status::get_status;
 
 // Allowed
 pub(crate) use allowed::allowed_action;
+pub(crate) use allowed::allowed_batch_action;
+pub(crate) use allowed::allowed_filter_action;
+pub(crate) use allowed::who_can_action;
 
 // Group
+pub(crate) use group::delete_group;
 pub(crate) use group::get_group;
 pub(crate) use group::get_group_identities;
+pub(crate) use group::list_groups;
 pub(crate) use group::patch_group_identities;
 pub(crate) use group::upsert_group;
 
 // Identity
+pub(crate) use identity::delete_identity;
 pub(crate) use identity::get_identity;
+pub(crate) use identity::get_identity_effective_policies;
+pub(crate) use identity::list_identities;
 pub(crate) use identity::upsert_identity;
 
 // Policy
+pub(crate) use policy::delete_policy;
 pub(crate) use policy::get_policy;
+pub(crate) use policy::get_policy_attachments;
+pub(crate) use policy::list_policies;
 pub(crate) use policy::upsert_policy;
@@ -1,18 +1,25 @@
 use crate::err::ZephirError;
-use actix_web::{get, post, web, HttpResponse};
+use crate::handlers::list::{page_to_json, ListQuery};
+use actix_web::{delete, get, post, web, HttpResponse};
 use libzephir::err::Error;
 use libzephir::policy::policy::{CompletePolicy, ToJson};
+use libzephir::policy::statement::PolicyTargets;
 use libzephir::policy::{PolicyEffect, PolicyVersion};
-use libzephir::storage::StorageManager;
+use libzephir::storage::{
+    is_inline_policy_id, AttachmentKind, ListOptions, Page, PolicyAttachment, PolicyFilter,
+    PolicyReferences, Storage,
+};
 use regex::Regex;
 use serde::Deserialize;
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::convert::TryFrom;
 use validator::Validate;
 
 lazy_static! {
     static ref RE_VALID_ID: Regex = Regex::new(r"^[A-Za-z][A-Za-z0-9_\-.]*$").unwrap();
     static ref RE_EFFECT: Regex = Regex::new(r"^(ALLOW|DENY)$").unwrap();
+    static ref RE_ATTACHMENT_KIND: Regex =
+        Regex::new(r"^(identities|groups|inherited_identities)$").unwrap();
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -46,15 +53,67 @@ pub(crate) struct UpsertPolicyRequest {
         regex(path = "RE_VALID_ID", message = "Invalid field.")
     )]
     id: String,
-    #[validate(range(min = 1, max = 1, message = "Invalid version."))]
+    #[validate(range(min = 1, max = 2, message = "Invalid version."))]
     version: i32,
     #[validate(regex(path = "RE_EFFECT", message = "Invalid field."))]
-    effect: String,
+    effect: Option<String>,
     #[validate(length(min = 1, message = "The value is too short"))]
-    actions: Vec<String>,
+    actions: Option<Vec<String>>,
+    #[validate(length(min = 1, message = "The value is too short"))]
+    not_actions: Option<Vec<String>>,
     #[validate(length(min = 1, message = "The value is too short"))]
     resources: Option<Vec<String>>,
+    #[validate(length(min = 1, message = "The value is too short"))]
+    not_resources: Option<Vec<String>>,
     conditions: Option<Value>,
+    #[validate(length(min = 1, max = 100, message = "Invalid number of statements."))]
+    statements: Option<Vec<Value>>,
+}
+
+impl UpsertPolicyRequest {
+    /// Checks that the fields required by the policy version are present:
+    /// version 1 policies need effect and either actions or not_actions
+    /// (and at most one of resources and not_resources), version 2 policies only statements.
+    fn check_version_fields(&self) -> Result<(), ZephirError> {
+        let valid = if self.version == 2 {
+            self.statements.is_some()
+                && self.effect.is_none()
+                && self.actions.is_none()
+                && self.not_actions.is_none()
+                && self.resources.is_none()
+                && self.not_resources.is_none()
+                && self.conditions.is_none()
+        } else {
+            self.statements.is_none()
+                && self.effect.is_some()
+                && self.actions.is_some() != self.not_actions.is_some()
+                && !(self.resources.is_some() && self.not_resources.is_some())
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(ZephirError::InvalidRequestError)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct PolicyFilterQuery {
+    action: Option<String>,
+    resource: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub(crate) struct AttachmentsQuery {
+    #[validate(regex(path = "RE_ATTACHMENT_KIND", message = "Invalid field."))]
+    kind: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct DeletePolicyQuery {
+    #[serde(default)]
+    force: bool,
 }
 
 impl TryFrom<EmbeddedPolicyRequest> for CompletePolicy {
@@ -76,12 +135,26 @@ impl TryFrom<UpsertPolicyRequest> for CompletePolicy {
     type Error = Error;
 
     fn try_from(value: UpsertPolicyRequest) -> Result<Self, Self::Error> {
-        CompletePolicy::new(
+        let version = PolicyVersion::try_from(value.version)?;
+        if version == PolicyVersion::Version2 {
+            return CompletePolicy::from_statements(
+                value.id,
+                &Value::from(value.statements.unwrap_or_default()),
+            );
+        }
+
+        let targets = PolicyTargets::new(
+            value.actions.unwrap_or_default(),
+            value.resources.unwrap_or_default(),
+        )
+        .not_actions(value.not_actions.unwrap_or_default())
+        .not_resources(value.not_resources.unwrap_or_default());
+
+        CompletePolicy::with_targets(
             value.id,
-            PolicyVersion::try_from(value.version)?,
-            PolicyEffect::try_from(&value.effect)?,
-            value.actions,
-            value.resources.unwrap_or_else(Vec::new),
+            version,
+            PolicyEffect::try_from(&value.effect.unwrap_or_default())?,
+            targets,
             value.conditions.unwrap_or(Value::Null),
         )
     }
@@ -96,7 +169,7 @@ impl TryFrom<InlinePolicy> for CompletePolicy {
             PolicyVersion::Version1,
             PolicyEffect::try_from(&value.effect)?,
             value.actions,
-            value.resources.unwrap_or_else(Vec::new),
+            value.resources.unwrap_or_default(),
             value.conditions.unwrap_or(Value::Null),
         )
     }
@@ -105,24 +178,151 @@ impl TryFrom<InlinePolicy> for CompletePolicy {
 #[post("/policies")]
 pub(crate) async fn upsert_policy(
     info: web::Json<UpsertPolicyRequest>,
-    storage: web::Data<StorageManager>,
+    storage: web::Data<dyn Storage>,
 ) -> Result<HttpResponse, ZephirError> {
     info.validate()?;
+    info.check_version_fields()?;
     let policy = CompletePolicy::try_from(info.0)?;
 
     storage.save_policy(&policy).await?;
     Ok(HttpResponse::Ok().json(policy.to_json()))
 }
 
+#[get("/policies")]
+pub(crate) async fn list_policies(
+    query: web::Query<ListQuery>,
+    filter: web::Query<PolicyFilterQuery>,
+    storage: web::Data<dyn Storage>,
+) -> Result<HttpResponse, ZephirError> {
+    query.validate()?;
+    let filter = PolicyFilter::new(
+        filter.action.as_deref().filter(|a| !a.is_empty()),
+        filter.resource.as_deref().filter(|r| !r.is_empty()),
+    )?;
+
+    let page = storage
+        .search_policies(&ListOptions::from(&query.0), &filter)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(page_to_json(&page)))
+}
+
 #[get("/policy/{id}")]
 pub(crate) async fn get_policy(
     path: web::Path<String>,
-    storage: web::Data<StorageManager>,
+    storage: web::Data<dyn Storage>,
 ) -> Result<HttpResponse, ZephirError> {
     let id = path.into_inner();
-    let result = storage.find_policy(id).await?;
+    let result = storage.find_policy(&id).await?;
     match result {
         Option::None => Err(ZephirError::NotFound),
         Option::Some(policy) => Ok(HttpResponse::Ok().json(policy.to_json())),
     }
 }
+
+fn attachments_to_json(kind: AttachmentKind, page: &Page<PolicyAttachment>) -> Value {
+    let items = page
+        .items
+        .iter()
+        .map(|a| match kind {
+            AttachmentKind::InheritedIdentity => json!({ "id": a.id, "groups": a.groups }),
+            _ => Value::from(a.id.as_str()),
+        })
+        .collect::<Vec<Value>>();
+
+    json!({
+        "items": items,
+        "next_cursor": page.next_cursor,
+    })
+}
+
+#[get("/policy/{id}/attachments")]
+pub(crate) async fn get_policy_attachments(
+    path: web::Path<String>,
+    query: web::Query<ListQuery>,
+    attachments: web::Query<AttachmentsQuery>,
+    storage: web::Data<dyn Storage>,
+) -> Result<HttpResponse, ZephirError> {
+    query.validate()?;
+    attachments.validate()?;
+
+    // Each list is paginated separately: a cursor is only valid for the list it comes from.
+    let options = ListOptions::from(&query.0);
+    if options.cursor.is_some() && attachments.kind.is_none() {
+        return Err(ZephirError::InvalidRequestError);
+    }
+
+    let id = path.into_inner();
+    if storage.find_policy(&id).await?.is_none() {
+        return Err(ZephirError::NotFound);
+    }
+
+    let counts = storage.count_policy_attachments(&id).await?;
+    let mut result = json!({
+        "policy": id,
+        "counts": {
+            "identities": counts.identities,
+            "groups": counts.groups,
+            "inherited_identities": counts.inherited_identities,
+        },
+    });
+
+    for (name, kind) in [
+        ("identities", AttachmentKind::Identity),
+        ("groups", AttachmentKind::Group),
+        ("inherited_identities", AttachmentKind::InheritedIdentity),
+    ] {
+        if attachments.kind.as_deref().is_none_or(|k| k == name) {
+            let page = storage.list_policy_attachments(&id, kind, &options).await?;
+            result[name] = attachments_to_json(kind, &page);
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(result))
+}
+
+/// Builds the conflict raised when a policy cannot be deleted.
+fn deletion_conflict(message: String, references: PolicyReferences) -> ZephirError {
+    ZephirError::Conflict(json!({
+        "message": message,
+        "identities": references.identities,
+        "groups": references.groups,
+    }))
+}
+
+#[delete("/policy/{id}")]
+pub(crate) async fn delete_policy(
+    path: web::Path<String>,
+    query: web::Query<DeletePolicyQuery>,
+    storage: web::Data<dyn Storage>,
+) -> Result<HttpResponse, ZephirError> {
+    let id = path.into_inner();
+    if storage.find_policy(&id).await?.is_none() {
+        return Err(ZephirError::NotFound);
+    }
+
+    if is_inline_policy_id(&id) {
+        return Err(deletion_conflict(
+            "Inline policies are removed along with their identity or group".to_string(),
+            PolicyReferences::default(),
+        ));
+    }
+
+    if query.force {
+        storage.delete_policy(&id).await?;
+        return Ok(HttpResponse::NoContent().finish());
+    }
+
+    let references = storage.delete_unlinked_policy(&id).await?;
+    if !references.is_empty() {
+        return Err(deletion_conflict(
+            format!(
+                "Policy {} is still linked. Pass force=true to unlink it",
+                id
+            ),
+            references,
+        ));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -1,20 +1,39 @@
 #[macro_use]
 extern crate lazy_static;
 
+mod command;
 mod err;
 mod handlers;
 
-use std::process::exit;
 use actix_web::middleware::Logger;
 use actix_web::rt::time::sleep;
 use actix_web::web::Data;
 use actix_web::{App, HttpServer};
+use command::Command;
 use libzephir::err::{Error, ErrorKind};
-use libzephir::storage::StorageManager;
-use log::{debug, error};
-use sqlx::postgres::PgPoolOptions;
+use libzephir::storage::{
+    strict_matching_report, MemoryStorage, MySqlStorage, PostgresStorage, SqliteStorage, Storage,
+};
+use log::{debug, error, info, warn};
+use sqlx::pool::PoolOptions;
+use sqlx::Database;
+use std::process::exit;
+use std::sync::Arc;
 use std::time::Duration;
 
+enum StorageBackend {
+    Postgres,
+    MySql,
+    Sqlite,
+    Memory,
+}
+
+struct PoolSettings {
+    min_connections: u32,
+    max_connections: u32,
+    connection_timeout: u64,
+}
+
 fn get_serve_port() -> u16 {
     let serve_port = std::env::var("SERVE_PORT");
     match serve_port {
@@ -48,73 +67,182 @@ fn get_db_connection_string() -> Result<String, Error> {
     }
 }
 
+fn get_storage_backend(dsn: &str) -> Result<StorageBackend, Error> {
+    let scheme = dsn.split(':').next().unwrap_or_default();
+    match scheme {
+        "postgres" | "postgresql" => Ok(StorageBackend::Postgres),
+        "mysql" | "mariadb" => Ok(StorageBackend::MySql),
+        "sqlite" => Ok(StorageBackend::Sqlite),
+        "memory" => Ok(StorageBackend::Memory),
+        _ => Err(Error::new(
+            ErrorKind::UnknownError,
+            format!(r#"Unsupported database DSN scheme "{}""#, scheme),
+        )),
+    }
+}
+
+fn get_auto_migrate() -> bool {
+    match std::env::var("AUTO_MIGRATE") {
+        Result::Err(_) => true,
+        Result::Ok(value) => !matches!(value.to_lowercase().as_str(), "0" | "false" | "no" | "off"),
+    }
+}
+
+/// Logs the stored policies whose meaning would change with strict glob matching.
+async fn report_strict_matching(storage: &dyn Storage) {
+    match strict_matching_report(storage).await {
+        Err(e) => error!("Strict matching report failed: {}", e.to_string()),
+        Ok(changes) if changes.is_empty() => {
+            info!("All the stored policies are unaffected by strict glob matching.")
+        }
+        Ok(changes) => {
+            warn!(
+                "{} version 1 policies would change meaning with strict glob matching (version 2)",
+                changes.len()
+            );
+            for change in changes {
+                warn!(
+                    r#"Policy "{}": actions {:?}, resources {:?}"#,
+                    change.policy_id, change.actions, change.resources
+                );
+            }
+        }
+    }
+}
+
+fn pool_options<DB: Database>(settings: &PoolSettings) -> PoolOptions<DB> {
+    PoolOptions::new()
+        .min_connections(settings.min_connections)
+        .max_connections(settings.max_connections)
+        .connect_timeout(Duration::from_millis(settings.connection_timeout))
+}
+
+async fn connect_storage(
+    backend: &StorageBackend,
+    dsn: &str,
+    settings: &PoolSettings,
+) -> Result<Arc<dyn Storage>, sqlx::Error> {
+    let storage: Arc<dyn Storage> = match backend {
+        StorageBackend::Postgres => Arc::new(PostgresStorage::new(
+            pool_options(settings).connect(dsn).await?,
+        )),
+        StorageBackend::MySql => Arc::new(MySqlStorage::new(
+            pool_options(settings).connect(dsn).await?,
+        )),
+        StorageBackend::Sqlite => Arc::new(SqliteStorage::new(
+            pool_options(settings).connect(dsn).await?,
+        )),
+        StorageBackend::Memory => Arc::new(MemoryStorage::new()),
+    };
+
+    Ok(storage)
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     env_logger::init();
     libzephir::initialize_libzephir();
 
-    let min_connections: u32 = std::env::var("MINCONN")
-        .unwrap_or_else(|_| "0".to_string())
-        .parse()
-        .unwrap();
-    let max_connections: u32 = std::env::var("MAXCONN")
-        .unwrap_or_else(|_| "5".to_string())
-        .parse()
-        .unwrap();
-    let connection_timeout: u64 = std::env::var("CONNECTION_TIMEOUT")
-        .unwrap_or_else(|_| "500".to_string())
-        .parse()
-        .unwrap();
-
-    let db_conn_string = get_db_connection_string();
-    if let Err(e) = db_conn_string {
-        error!("{}", e.to_string());
-        exit(1);
-    }
+    let command = match Command::parse(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(e) => {
+            error!("{}", e.to_string());
+            exit(1);
+        }
+    };
+
+    let settings = PoolSettings {
+        min_connections: std::env::var("MINCONN")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse()
+            .unwrap(),
+        max_connections: std::env::var("MAXCONN")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse()
+            .unwrap(),
+        connection_timeout: std::env::var("CONNECTION_TIMEOUT")
+            .unwrap_or_else(|_| "500".to_string())
+            .parse()
+            .unwrap(),
+    };
 
-    let pool = async {
-        let conn;
+    let db_conn_string = match get_db_connection_string() {
+        Ok(dsn) => dsn,
+        Err(e) => {
+            error!("{}", e.to_string());
+            exit(1);
+        }
+    };
+
+    let backend = match get_storage_backend(db_conn_string.as_str()) {
+        Ok(backend) => backend,
+        Err(e) => {
+            error!("{}", e.to_string());
+            exit(1);
+        }
+    };
+
+    let storage = async {
         loop {
             debug!("Connecting to database...");
-            let connection = PgPoolOptions::new()
-                .min_connections(min_connections)
-                .max_connections(max_connections)
-                .connect_timeout(Duration::from_millis(connection_timeout))
-                .connect(get_db_connection_string().unwrap().as_str())
-                .await;
-
-            if let Ok(c) = connection {
-                conn = Some(c);
-                break;
+            let connection = connect_storage(&backend, db_conn_string.as_str(), &settings).await;
+
+            match connection {
+                Ok(storage) => break storage,
+                Err(e) => {
+                    error!("Connection failed: {:#?}. Retrying...", e);
+                    sleep(Duration::from_secs(1)).await;
+                }
             }
+        }
+    }
+    .await;
 
-            error!(
-                "Connection failed: {:#?}. Retrying...",
-                connection.err().unwrap()
-            );
-            sleep(Duration::from_secs(1)).await;
+    if !matches!(command, Command::Serve) {
+        if let Err(e) = command.run_migrate(storage.as_ref()).await {
+            error!("Migration failed: {}", e.to_string());
+            exit(1);
         }
 
-        conn.unwrap()
+        return Ok(());
     }
-    .await;
 
-    let storage_manager = StorageManager::new(pool.clone());
+    if get_auto_migrate() {
+        info!("Applying pending database migrations...");
+        if let Err(e) = storage.migrate_up().await {
+            error!("Migration failed: {}", e.to_string());
+            exit(1);
+        }
+    }
+
+    report_strict_matching(storage.as_ref()).await;
+
+    let storage: Data<dyn Storage> = Data::from(storage);
 
     HttpServer::new(move || {
         App::new()
-            .app_data(Data::new(pool.clone()))
-            .app_data(Data::new(storage_manager.clone()))
+            .app_data(storage.clone())
             .wrap(Logger::default())
             .service(handlers::get_status)
             .service(handlers::allowed_action)
+            .service(handlers::allowed_batch_action)
+            .service(handlers::allowed_filter_action)
+            .service(handlers::who_can_action)
+            .service(handlers::delete_group)
             .service(handlers::get_group)
             .service(handlers::get_group_identities)
             .service(handlers::patch_group_identities)
+            .service(handlers::list_groups)
             .service(handlers::upsert_group)
+            .service(handlers::delete_identity)
             .service(handlers::get_identity)
+            .service(handlers::get_identity_effective_policies)
+            .service(handlers::list_identities)
             .service(handlers::upsert_identity)
+            .service(handlers::delete_policy)
             .service(handlers::get_policy)
+            .service(handlers::get_policy_attachments)
+            .service(handlers::list_policies)
             .service(handlers::upsert_policy)
     })
     .bind(("0.0.0.0", get_serve_port()))?